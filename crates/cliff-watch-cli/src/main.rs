@@ -67,21 +67,47 @@ enum Commands {
     },
     /// Verificación commit-por-commit (para CI/CD o auditorías)
     Verify {
-        /// Hash o referencia del commit
+        /// Hash o referencia del commit, o un rango `base..head`
         #[arg(default_value = "HEAD")]
         commit: String,
-        /// Formato de salida (json, text)
+        /// Formato de salida (text, json, sarif, junit)
         #[arg(short, long, default_value = "text")]
         format: String,
+        /// En un rango, recorre solo la línea del primer padre (omite los commits traídos por merges)
+        #[arg(long)]
+        first_parent: bool,
+        /// En un rango, qué hacer con los merges: `verify` (exigen ticket) o `exempt`
+        #[arg(long, default_value = "verify")]
+        merges: String,
+        /// Rama de destino para las reglas de policy.toml (por defecto, la de
+        /// `head` en un rango o la actual)
+        #[arg(long)]
+        branch: Option<String>,
+    },
+    /// Certificado de rama: una prueba agregada de que todo `base..head` alcanzó su umbral
+    Certify {
+        /// Rango `base..head` (p. ej. `main..HEAD`)
+        range: String,
+        /// Archivo de salida (por defecto, stdout)
+        #[arg(short, long)]
+        out: Option<String>,
+    },
+    /// Verifica un certificado de rama emitido con `certify`
+    VerifyCertificate {
+        /// Archivo del certificado
+        file: String,
     },
     /// Registra una clave pública para verificación en este repositorio
     RegisterKey {
-        /// Clave pública en formato hexadecimal
+        /// Clave pública en hexadecimal o en formato OpenSSH (`ssh-ed25519 AAAA…`)
         #[arg(short, long)]
         key: String,
         /// Alias para la clave (ej: nombre del dev)
         #[arg(short, long)]
         alias: String,
+        /// Email de autor por el que puede firmar la clave (repetible; sin él, cualquiera)
+        #[arg(short, long)]
+        email: Vec<String>,
     },
     /// Verificación termodinámica del trabajo (para hooks)
     VerifyWork,
@@ -96,6 +122,54 @@ enum Commands {
         #[command(subcommand)]
         action: ConfigAction,
     },
+    /// Gestión del ciclo de vida de las claves en trust.toml
+    Key {
+        #[command(subcommand)]
+        action: KeyAction,
+    },
+    /// Identidad del daemon residente en TPM (ECDSA-P256)
+    Tpm {
+        #[command(subcommand)]
+        action: TpmAction,
+    },
+    /// Co-firma el ticket de un commit con la clave de este daemon (pair programming, aval de maintainer)
+    Cosign {
+        /// Hash o referencia del commit
+        #[arg(default_value = "HEAD")]
+        commit: String,
+    },
+    /// Atestaciones en git notes (refs/notes/cliff-watch)
+    Notes {
+        #[command(subcommand)]
+        action: NotesAction,
+    },
+    /// Programa de firma de Git que exige un ticket antes de firmar cada commit
+    SignShim {
+        #[command(subcommand)]
+        action: SignShimAction,
+    },
+    /// Hook pre-push: verifica los commits que se publican y ofrece documentar los que no llevan ticket
+    PrePush {
+        /// Remoto al que se publica (primer argumento del hook)
+        remote: String,
+        /// URL del remoto
+        url: Option<String>,
+    },
+    /// Hooks del repositorio que recibe los pushes: rechazan commits sin ticket válido
+    ServerHook {
+        #[command(subcommand)]
+        action: ServerHookAction,
+    },
+    /// Credenciales verificables de reputación (did:key + W3C VC)
+    Credential {
+        #[command(subcommand)]
+        action: CredentialAction,
+    },
+    /// Log de transparencia local de los tickets emitidos
+    Log {
+        #[command(subcommand)]
+        action: LogAction,
+    },
     /// Genera reporte de auditoría
     Report {
         /// Número de commits a analizar
@@ -113,6 +187,69 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum SignShimAction {
+    /// Configura el shim como gpg.program y gpg.ssh.program de este repositorio
+    Install,
+    /// Restaura los programas de firma originales
+    Uninstall,
+}
+
+#[derive(Subcommand, Debug)]
+enum ServerHookAction {
+    /// Hook `pre-receive`: lee `<old> <new> <ref>` de stdin y rechaza el push si una ref incumple su política
+    PreReceive,
+    /// Hook `update <ref> <old> <new>`: rechaza solo esa ref
+    Update {
+        refname: String,
+        old: String,
+        new: String,
+    },
+    /// Instala el hook en este repositorio (normalmente bare)
+    Install {
+        /// Instala `update` (una decisión por ref) en lugar de `pre-receive` (todo o nada)
+        #[arg(long)]
+        update: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CredentialAction {
+    /// Muestra el did:key del daemon
+    Did,
+    /// Emite una credencial con los commits verificados de una identidad en este repositorio
+    Issue {
+        /// Alias de trust.toml, clave pública o did:key del sujeto (por defecto, este daemon)
+        #[arg(short, long)]
+        subject: Option<String>,
+        /// Número de commits a recorrer desde HEAD
+        #[arg(short, long, default_value_t = 1000)]
+        limit: usize,
+        /// Archivo de salida (por defecto, stdout)
+        #[arg(short, long)]
+        out: Option<String>,
+    },
+    /// Verifica una credencial (emitida en este u otro repositorio)
+    Verify {
+        /// Archivo JSON de la credencial
+        file: String,
+        /// Mínimo de commits verificados exigido
+        #[arg(long, default_value_t = 0)]
+        min_commits: usize,
+        /// Score medio mínimo exigido
+        #[arg(long)]
+        min_score: Option<f64>,
+        /// Registra la clave del sujeto en trust.toml con este alias
+        /// (solo si el emisor ya es de confianza aquí)
+        #[arg(long)]
+        import: Option<String>,
+        /// Permite `--import` con un emisor que no está en trust.toml
+        /// (nunca con una credencial autoemitida)
+        #[arg(long, requires = "import")]
+        allow_untrusted_issuer: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum ConfigAction {
     /// Crea un archivo de configuración por defecto
@@ -121,8 +258,169 @@ enum ConfigAction {
     Check,
 }
 
+#[derive(Subcommand, Debug)]
+enum KeyAction {
+    /// Añade una clave nueva a un alias y retira las vigentes tras el solape
+    Rotate {
+        /// Alias existente en trust.toml
+        #[arg(short, long)]
+        alias: String,
+        /// Nueva clave pública (hex u OpenSSH)
+        #[arg(short, long)]
+        key: String,
+        /// Días en que la clave vieja y la nueva conviven
+        #[arg(long, default_value_t = 0)]
+        overlap_days: i64,
+    },
+    /// Revoca una clave (hex o key id). `compromised` invalida también el historial
+    Revoke {
+        /// Clave pública en hexadecimal o su key id
+        #[arg(short, long)]
+        key: String,
+        /// Motivo (compromised, superseded, retired, ...)
+        #[arg(short, long)]
+        reason: String,
+        /// Instante de la revocación en RFC 3339 (por defecto: ahora)
+        #[arg(long)]
+        at: Option<String>,
+    },
+    /// Lista las claves con su ventana de validez y su estado
+    List {
+        /// Evalúa el estado en este instante RFC 3339 (por defecto: ahora)
+        #[arg(long)]
+        at: Option<String>,
+    },
+    /// Exporta la clave del daemon cifrada con una passphrase de transporte
+    Export {
+        /// Fichero de destino
+        #[arg(short, long)]
+        out: String,
+    },
+    /// Importa una clave exportada como clave del daemon (cifrada en reposo)
+    Import {
+        /// Fichero exportado con `key export`
+        #[arg(short, long)]
+        input: String,
+        /// Sobrescribe la clave existente del daemon
+        #[arg(long)]
+        force: bool,
+    },
+    /// Cifra la clave del daemon o cambia su passphrase
+    Passwd,
+    /// Importa un fichero allowed_signers de SSH a trust.toml
+    ImportSigners {
+        /// Ruta del fichero (por defecto: gpg.ssh.allowedSignersFile)
+        #[arg(short, long)]
+        file: Option<String>,
+    },
+    /// Exporta trust.toml en formato allowed_signers (para gpg.ssh.allowedSignersFile)
+    ExportSigners {
+        /// Fichero de destino (por defecto: stdout)
+        #[arg(short, long)]
+        out: Option<String>,
+    },
+    /// Fija los emails de autor por los que pueden firmar las claves de un alias
+    Emails {
+        /// Alias existente en trust.toml
+        #[arg(short, long)]
+        alias: String,
+        /// Email de autor (repetible; sin ninguno, la clave firma por cualquiera)
+        #[arg(short, long)]
+        email: Vec<String>,
+    },
+    /// Fija una clave raíz de la red de confianza en este clon (cliff-watch.trustRoot)
+    PinRoot {
+        /// Clave pública (hex u OpenSSH)
+        #[arg(short, long)]
+        key: String,
+    },
+    /// Avala con la clave del daemon las entradas de un alias o una clave de trust.toml
+    Endorse {
+        /// Alias, clave pública en hexadecimal o key id
+        #[arg(short, long)]
+        key: String,
+    },
+    /// Firma trust.toml con la clave del daemon (debe ser un Maintainer avalado)
+    SignTrust,
+}
+
+#[derive(Subcommand, Debug)]
+enum TpmAction {
+    /// Crea (o reutiliza) la clave persistente y la registra en trust.toml
+    Init {
+        /// Alias para la clave en trust.toml
+        #[arg(short, long)]
+        alias: String,
+        /// Handle persistente (por defecto: identity.tpm_handle de la configuración)
+        #[arg(long)]
+        handle: Option<String>,
+    },
+    /// Muestra la clave pública persistida en el TPM
+    Show {
+        /// Handle persistente (por defecto: identity.tpm_handle de la configuración)
+        #[arg(long)]
+        handle: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum NotesAction {
+    /// Guarda la atestación pendiente como nota del commit (hook post-commit)
+    Attach {
+        /// Commit al que se adjunta la nota
+        #[arg(default_value = "HEAD")]
+        commit: String,
+    },
+    /// Muestra la nota cliff-watch de un commit
+    Show {
+        #[arg(default_value = "HEAD")]
+        commit: String,
+    },
+    /// Documenta por qué unos commits no llevan ticket (`--no-verify`, daemon parado...)
+    Gap {
+        #[arg(required = true)]
+        commits: Vec<String>,
+        /// Explicación que queda en la nota de cada commit
+        #[arg(short, long)]
+        reason: String,
+    },
+    /// Publica refs/notes/cliff-watch en un remoto
+    Push {
+        #[arg(default_value = "origin")]
+        remote: String,
+    },
+    /// Trae refs/notes/cliff-watch de un remoto (solo fast-forward)
+    Fetch {
+        #[arg(default_value = "origin")]
+        remote: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum LogAction {
+    /// Comprueba los heads firmados y la inclusión de los tickets del historial
+    Verify {
+        /// Directorio del log (por defecto: ~/.local/share/cliff-watch/log)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Clave pública del daemon (hex u OpenSSH) si no está en trust.toml
+        #[arg(short, long)]
+        key: Option<String>,
+        /// Número de commits del historial a comprobar
+        #[arg(short, long, default_value_t = 100)]
+        limit: usize,
+    },
+}
+
 #[tokio::main]
 async fn main() {
+    // Invocado por Git como gpg.program / gpg.ssh.program (enlace cliff-watch-sign-shim)
+    let mut args = std::env::args();
+    let program = args.next().unwrap_or_default();
+    if Path::new(&program).file_name().is_some_and(|name| name == cliff_watch_core::signing::SHIM_NAME) {
+        run_sign_shim(args.collect()).await;
+    }
+
     let cli = Cli::parse();
 
     match cli.command {
@@ -148,6 +446,7 @@ async fn main() {
                     let total_commits = entries.len();
                     let total_score: f64 = entries.iter().map(|e| e.score).sum();
                     let avg_score = if total_commits > 0 { total_score / total_commits as f64 } else { 0.0 };
+                    let pow_paid = entries.iter().filter(|e| e.mode.as_deref() == Some("pow")).count();
                     
                     let mut authors = std::collections::HashMap::new();
                     for e in &entries {
@@ -159,6 +458,7 @@ async fn main() {
                          println!("- **Analyzed Commits:** {}", total_commits);
                          println!("- **Total Energy:** {:.2}", total_score);
                          println!("- **Average Entropy:** {:.2}", avg_score);
+                         println!("- **PoW-paid Commits:** {}", pow_paid);
                          println!("\n## Top Contributors (by Energy)");
                          println!("| Author | Energy |");
                          println!("|--------|--------|");
@@ -171,6 +471,7 @@ async fn main() {
                         println!("  Commits Analyzed: {}", total_commits);
                         println!("  Total Energy:     {:.2}", total_score);
                         println!("  Average Score:    {:.2}", avg_score);
+                        println!("  PoW-paid:         {} ⛏️", pow_paid);
                         println!("\n🏆 Top Contributors:");
                         for (author, score) in authors {
                             println!("  - {}: {:.2}", author, score);
@@ -279,14 +580,14 @@ async fn main() {
             }
         }
         Commands::On => {
-            use std::process::{Command, Stdio};
             println!("🚀 Encendiendo el centinela termodinámico...");
-            
-            match Command::new("cliff-watch-daemon")
-                .stdin(Stdio::null())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .spawn() {
+
+            // Si la clave del daemon está cifrada y el entorno no aporta la passphrase,
+            // se pide aquí y se entrega al daemon por stdin (CLIFF_WATCH_KEY_PASSPHRASE_FD=0)
+            let passphrase = daemon_key_needs_prompt()
+                .then(|| prompt_passphrase("Daemon key passphrase", false));
+
+            match spawn_daemon("cliff-watch-daemon", passphrase.as_deref()) {
                     Ok(_) => {
                         println!("✅ Centinela activado en background.");
                         println!("Usa 'cliff-watch status' para verificar.");
@@ -300,11 +601,7 @@ async fn main() {
                             "/usr/local/bin/cliff-watch-daemon"
                         };
 
-                        match Command::new(bin_path)
-                            .stdin(Stdio::null())
-                            .stdout(Stdio::null())
-                            .stderr(Stdio::null())
-                            .spawn() {
+                        match spawn_daemon(bin_path, passphrase.as_deref()) {
                                 Ok(_) => {
                                     println!("✅ Centinela activado en background ({}).", bin_path);
                                     println!("Usa 'cliff-watch status' para verificar.");
@@ -383,7 +680,7 @@ async fn main() {
                 }
             }
         }
-        Commands::RegisterKey { key, alias } => {
+        Commands::RegisterKey { key, alias, email } => {
             let repo = match open_repository(Path::new(".")) {
                 Ok(repo) => repo,
                 Err(e) => {
@@ -392,7 +689,8 @@ async fn main() {
                 }
             };
 
-            match cliff_watch_core::git::register_public_key(&repo, &key, &alias) {
+            let key = public_key_hex_or_exit(&key);
+            match cliff_watch_core::trust::register_public_key(&repo, &key, &alias, &email) {
                 Ok(_) => println!("✅ Key registered successfully for alias: {}", alias),
                 Err(e) => {
                     eprintln!("❌ Failed to register key: {}", e);
@@ -400,127 +698,320 @@ async fn main() {
                 }
             }
         }
-        Commands::Verify { commit, format } => {
-            use cliff_watch_core::git::{get_trusted_keys};
-            use cliff_watch_core::crypto::{verify_signature, VerifyingKey};
+        Commands::Verify { commit, format, first_parent, merges, branch }
+            if commit.contains("..") || format == "sarif" || format == "junit" =>
+        {
+            verify_range(&commit, &format, first_parent, &merges, branch)
+        }
+        Commands::Verify { commit, format, branch, .. } => {
+            use cliff_watch_core::ci::CommitStatus;
+            use cliff_watch_core::policy::{decide_with, PolicyScope, RuleHit};
+            use cliff_watch_core::trust::{load_trust_at_with, trust_toml_at, HistoryCache};
+            use cliff_watch_core::ticket::{verify_commit_ticket, verify_commit_proof};
+            use cliff_watch_core::git::{extract_witness_data, WitnessStatus};
 
-            #[derive(serde::Serialize)]
+            #[derive(serde::Serialize, Default)]
             struct VerificationReport {
                 status: String,
                 commit: String,
                 signer: Option<String>,
                 score: Option<f64>,
                 reason: Option<String>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                mode: Option<String>,
+                /// Umbral (%) probado por la prueba de rango, sin revelar el score humano
+                #[serde(skip_serializing_if = "Option::is_none")]
+                proven_threshold: Option<u64>,
+                /// Estado del trailer Cliff-Watch-Witness: authentic, forged o missing
+                #[serde(skip_serializing_if = "Option::is_none")]
+                witness: Option<String>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                witness_reason: Option<String>,
+                /// Dónde se encontró la atestación: trailers, notes o trailers+notes
+                #[serde(skip_serializing_if = "Option::is_none")]
+                attestation: Option<String>,
+                /// Alias de los co-firmantes válidos
+                #[serde(skip_serializing_if = "Vec::is_empty")]
+                cosigners: Vec<String>,
+                /// Co-autores (`Co-authored-by`) y el alias que los atestigua, si lo hay
+                #[serde(skip_serializing_if = "Vec::is_empty")]
+                co_authors: Vec<CoAuthorReport>,
+                /// Por qué el commit no lleva ticket, si se documentó (`Cliff-Watch-Gap`)
+                #[serde(skip_serializing_if = "Option::is_none")]
+                gap: Option<String>,
+                /// Exigencia de policy.toml con la que se juzgó
+                #[serde(skip_serializing_if = "Option::is_none")]
+                policy: Option<String>,
+                /// Reglas de policy.toml que aplicaron
+                #[serde(skip_serializing_if = "Vec::is_empty")]
+                rules: Vec<RuleHit>,
+            }
+
+            impl VerificationReport {
+                /// Informe sin ticket verificado: `status` y motivo, el resto vacío
+                fn failure(status: &str, commit: &str, reason: String) -> Self {
+                    Self { status: status.to_string(), commit: commit.to_string(), reason: Some(reason), ..Default::default() }
+                }
+            }
+
+            #[derive(serde::Serialize)]
+            struct CoAuthorReport {
+                email: String,
+                attested_by: Option<String>,
             }
 
+            // Fallo antes de poder verificar: informe JSON o mensaje, y salida 1
+            let fail = |reason: String| -> ! {
+                if format == "json" {
+                    println!("{}", serde_json::to_string(&VerificationReport::failure("error", &commit, reason)).unwrap());
+                } else {
+                    eprintln!("❌ {}", reason);
+                }
+                process::exit(1);
+            };
+
             let repo = match open_repository(Path::new(".")) {
                 Ok(repo) => repo,
-                Err(e) => {
-                    let report = VerificationReport {
-                        status: "error".to_string(),
-                        commit: commit.clone(),
-                        signer: None,
-                        score: None,
-                        reason: Some(format!("Error opening repository: {}", e)),
+                Err(e) => fail(format!("Error opening repository: {}", e)),
+            };
+
+            // Una revisión que no es un commit (árbol, blob) tampoco se puede verificar
+            let commit_obj = match repo.revparse_single(&commit).and_then(|obj| obj.peel_to_commit()) {
+                Ok(commit_obj) => commit_obj,
+                Err(e) => fail(format!("Commit not found: {}", e)),
+            };
+
+            // trust.toml y policy.toml tal como estaban antes de este commit (TOFU en el que los introduce)
+            let scope = PolicyScope::local(&repo, branch);
+            let mut history = HistoryCache::default();
+            let (trusted_keys, mut policy) = match load_trust_at_with(&repo, &commit_obj, &mut history)
+                .and_then(|t| decide_with(&repo, &commit_obj, &scope, &mut history).map(|p| (t, p)))
+            {
+                Ok(loaded) => loaded,
+                Err(e) => fail(e),
+            };
+
+            // El ticket debe estar firmado por una clave confiable y ligado al
+            // árbol, padre y autor que este commit realmente tiene. La atestación
+            // puede viajar en trailers del mensaje, en refs/notes/cliff-watch o en la firma
+            let attestation = cliff_watch_core::notes::read_attestation(&repo, &commit_obj);
+            let source = match attestation.source {
+                cliff_watch_core::notes::AttestationSource::None => None,
+                other => Some(other.label().to_string()),
+            };
+            let verification = verify_commit_ticket(&commit_obj, &attestation.text, &trusted_keys).and_then(|v| {
+                // Si el ticket se comprometió a una prueba de rango, debe estar y ser válida
+                verify_commit_proof(&commit_obj, &attestation.text, &v.ticket).map(|threshold| (v, threshold))
+            });
+
+            match verification {
+                Ok((verification, proven_threshold)) => {
+                    let payload = &verification.ticket.payload;
+                    let policy_met = policy.check(proven_threshold, Some(&verification.signers));
+                    let policy_rejects = !policy_met && policy.mode.rejects(CommitStatus::BelowThreshold);
+                    let witness = extract_witness_data(&attestation.text, &verification.ticket, &trusted_keys);
+                    let witness_reason = match &witness {
+                        WitnessStatus::Forged(reason) => Some(reason.clone()),
+                        _ => None,
                     };
                     if format == "json" {
+                        let report = VerificationReport {
+                            status: if policy_rejects { "failed" } else { "verified" }.to_string(),
+                            commit: commit.clone(),
+                            signer: Some(verification.signer.clone()),
+                            score: Some(payload.score),
+                            reason: (!policy_met).then(|| "policy_unmet".to_string()),
+                            mode: Some(payload.mode.as_str().to_string()),
+                            proven_threshold,
+                            witness: Some(witness.label().to_string()),
+                            witness_reason,
+                            gap: None,
+                            attestation: source,
+                            cosigners: verification.signers.iter().skip(1).map(|s| s.alias.clone()).collect(),
+                            co_authors: verification
+                                .co_authors
+                                .iter()
+                                .map(|c| CoAuthorReport { email: c.email.clone(), attested_by: c.attested_by.clone() })
+                                .collect(),
+                            policy: Some(policy.mode.as_str().to_string()),
+                            rules: policy.hits.clone(),
+                        };
                         println!("{}", serde_json::to_string(&report).unwrap());
                     } else {
-                        eprintln!("❌ Error opening repository: {}", e);
+                        println!("✅ Commit VERIFICADO Criptográficamente");
+                        println!("   Firmante: {} (kid {})", verification.signer, payload.key_id);
+                        for cosigner in verification.signers.iter().skip(1) {
+                            println!("   Co-firma: {} [{}] (kid {})", cosigner.alias, cosigner.role, cosigner.key_id);
+                        }
+                        for co_author in &verification.co_authors {
+                            match &co_author.attested_by {
+                                Some(alias) => println!("   Co-autor: {} <{}> (co-firmado por {})", co_author.name, co_author.email, alias),
+                                None => println!("   Co-autor: {} <{}> ⚠️  sin co-firma que lo atestigüe", co_author.name, co_author.email),
+                            }
+                        }
+                        if let Some(threshold) = &trusted_keys.threshold {
+                            println!("   Umbral:   {} ✅", threshold);
+                        }
+                        match trust_toml_at(&repo, &commit_obj, &mut history) {
+                            Ok(Some((source, _))) if source == commit_obj.id() => println!("   Trust:    trust.toml de este commit (TOFU)"),
+                            Ok(Some((source, _))) => println!("   Trust:    trust.toml de {}", &source.to_string()[..8]),
+                            _ => {}
+                        }
+                        println!("   Score:    {:.2}", payload.score);
+                        println!("   Árbol:    {}", payload.binding.tree);
+                        let issued = chrono::DateTime::from_timestamp(payload.issued_at, 0).map(|t| t.to_rfc3339()).unwrap_or_default();
+                        match &payload.clock {
+                            Some(c) => println!("   Emitido:  {} (arranque {}, +{}s)", issued, &c.boot_id[..8.min(c.boot_id.len())], c.mono_ms / 1000),
+                            None => println!("   Emitido:  {}", issued),
+                        }
+                        println!("   Fuente:   {}", attestation.source.label());
+                        match proven_threshold {
+                            Some(t) => println!("   🔐 ZKP:    Human Score ≥ {:.2} (prueba de rango válida)", t as f64 / 100.0),
+                            None => println!("   🔐 ZKP:    sin prueba de humanidad"),
+                        }
+                        if payload.mode == cliff_watch_core::ticket::TicketMode::Audit {
+                            println!("   ⚠️  Ticket emitido en Audit Mode (batería insuficiente)");
+                        }
+                        if let Some(pow) = &payload.pow {
+                            println!("   ⛏️  Déficit pagado con prueba de trabajo ({} bits, nonce {})", pow.difficulty, pow.nonce);
+                        }
+                        if let Some(link) = &payload.chain {
+                            println!("   🔗 Cadena: ticket #{} de la clave (ver `verify base..head`)", link.seq);
+                        }
+                        if let Some(index) = payload.log_index {
+                            println!("   📜 Log:    hoja #{} (comprobar con `cliff-watch log verify`)", index);
+                        }
+                        match &witness {
+                            WitnessStatus::Authentic(report) => println!(
+                                "   🧾 Witness: auténtico ({} eventos, probabilidad humana {})",
+                                report.observation.emitted_event_count,
+                                report.human_probability()
+                            ),
+                            WitnessStatus::Forged(reason) => println!("   🚨 Witness: FALSIFICADO ({})", reason),
+                            WitnessStatus::Missing => println!("   🧾 Witness: ausente"),
+                        }
+                        print_policy(&policy);
+                        if policy_rejects {
+                            eprintln!("❌ POLÍTICA INCUMPLIDA: {}", policy.unmet().join("; "));
+                        } else if !policy_met {
+                            println!("   ⚠️  Política incumplida, tolerada por policy {}: {}", policy.mode.as_str(), policy.unmet().join("; "));
+                        }
+                    }
+                    if policy_rejects {
+                        process::exit(1);
                     }
-                    process::exit(1);
                 }
-            };
-
-            let commit_obj = match repo.revparse_single(&commit) {
-                Ok(obj) => obj.peel_to_commit().unwrap(),
-                Err(e) => {
-                    let report = VerificationReport {
-                        status: "error".to_string(),
-                        commit: commit.clone(),
-                        signer: None,
-                        score: None,
-                        reason: Some(format!("Commit not found: {}", e)),
-                    };
+                Err(reason) => {
+                    let gap = (reason == "no_ticket").then(|| cliff_watch_core::notes::read_gap(&repo, commit_obj.id())).flatten();
+                    let tolerated = !policy.mode.rejects(CommitStatus::from_failure(&reason));
                     if format == "json" {
+                        let report = VerificationReport {
+                            gap,
+                            attestation: source,
+                            policy: Some(policy.mode.as_str().to_string()),
+                            rules: policy.hits.clone(),
+                            ..VerificationReport::failure("failed", &commit, reason)
+                        };
                         println!("{}", serde_json::to_string(&report).unwrap());
                     } else {
-                        eprintln!("❌ Commit not found: {}", e);
+                        eprintln!("❌ FALLO DE VERIFICACIÓN: No se encontró firma válida de Cliff-Watch ({}).", reason);
+                        if let Some(gap) = gap {
+                            eprintln!("   📝 Hueco documentado: {}", gap);
+                        }
+                        print_policy(&policy);
+                        if tolerated {
+                            println!("   ⚠️  Tolerado por policy {} ({})", policy.mode.as_str(), policy.mode_source);
+                        }
                     }
+                    process::exit(if tolerated { 0 } else { 1 });
+                }
+            }
+        }
+        Commands::Certify { range, out } => {
+            use cliff_watch_core::certificate::{BranchCertificate, OpeningStore};
+
+            let repo = match open_repository(Path::new(".")) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    eprintln!("❌ Error opening repository: {}", e);
                     process::exit(1);
                 }
             };
-
-            let message = commit_obj.message().unwrap_or("");
-            let trusted_keys = get_trusted_keys(&repo).unwrap_or_default();
-
-            // Buscar trailer de cliff-watch
-            let mut found = false;
-            for line in message.lines() {
-                if line.starts_with("cliff-watch-score:") {
-                    let score_part = line.replace("cliff-watch-score:", "").trim().to_string();
-                    // El formato esperado es "score=0.85:sig=<hex>"
-                    let parts: Vec<&str> = score_part.split(":sig=").collect();
-                    if parts.len() == 2 {
-                        let score_str = parts[0].replace("score=", "");
-                        let sig_hex = parts[1];
-                        let sig_bytes = hex::decode(sig_hex).unwrap_or_default();
-                        
-                        let mut verified = false;
-                        let mut signer_alias = "Unknown";
-
-                        for (alias, key_hex) in &trusted_keys {
-                            let key_bytes = hex::decode(key_hex).unwrap_or_default();
-                            if let Ok(verifying_key) = VerifyingKey::from_bytes(&key_bytes.try_into().unwrap_or([0;32])) {
-                                if verify_signature(&verifying_key, parts[0].as_bytes(), &sig_bytes).unwrap_or(false) {
-                                    verified = true;
-                                    signer_alias = alias;
-                                    break;
-                                }
-                            }
-                        }
-
-                        if verified {
-                            let score_val = score_str.parse::<f64>().ok();
-                            if format == "json" {
-                                let report = VerificationReport {
-                                    status: "verified".to_string(),
-                                    commit: commit.clone(),
-                                    signer: Some(signer_alias.to_string()),
-                                    score: score_val,
-                                    reason: None,
-                                };
-                                println!("{}", serde_json::to_string(&report).unwrap());
-                            } else {
-                                println!("✅ Commit VERIFICADO Criptográficamente");
-                                println!("   Firmante: {}", signer_alias);
-                                println!("   Score:    {}", score_str);
-                            }
-                            found = true;
-                        }
+            // trust.toml con el que se juzga la punta del rango, no el del working tree
+            let trust = match cliff_watch_core::certificate::resolve_range(&repo, &range)
+                .and_then(|(_, head)| cliff_watch_core::trust::load_trust_at_rev(&repo, &head.to_string()))
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("❌ Cannot certify {}: {}", range, e);
+                    process::exit(1);
+                }
+            };
+            let store = match OpeningStore::default_dir().and_then(|dir| OpeningStore::open(&dir)) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    process::exit(1);
+                }
+            };
+            let certificate = match BranchCertificate::certify(&repo, &range, &trust, &store) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("❌ Cannot certify {}: {}", range, e);
+                    process::exit(1);
+                }
+            };
+            match out {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, format!("{}\n", certificate.encode())) {
+                        eprintln!("❌ Failed to write {}: {}", path, e);
+                        process::exit(1);
                     }
+                    println!("✅ Certificate for {} commits written to {}", certificate.commits, path);
                 }
+                None => println!("{}", certificate.encode()),
             }
+        }
+        Commands::VerifyCertificate { file } => {
+            use cliff_watch_core::certificate::BranchCertificate;
 
-            if !found {
-                if format == "json" {
-                     let report = VerificationReport {
-                        status: "failed".to_string(),
-                        commit: commit.clone(),
-                        signer: None,
-                        score: None,
-                        reason: Some("no_valid_signature".to_string()),
-                    };
-                    println!("{}", serde_json::to_string(&report).unwrap());
-                } else {
-                    eprintln!("❌ FALLO DE VERIFICACIÓN: No se encontró firma válida de Cliff-Watch.");
+            let certificate = match std::fs::read_to_string(&file).map_err(|e| e.to_string()).and_then(|c| BranchCertificate::decode(&c)) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("❌ Invalid certificate {}: {}", file, e);
+                    process::exit(1);
+                }
+            };
+            let repo = match open_repository(Path::new(".")) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    eprintln!("❌ Error opening repository: {}", e);
+                    process::exit(1);
+                }
+            };
+            let trust = match cliff_watch_core::trust::load_trust_at_rev(&repo, &certificate.head.to_string()) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    process::exit(1);
+                }
+            };
+            match certificate.verify(&repo, &trust) {
+                Ok(summary) => {
+                    println!("✅ Branch certificate valid: {}..{}", certificate.base, certificate.head);
+                    println!("   {} commits, all above their threshold (lowest: {}%)", summary.commits, summary.min_threshold_percent);
+                }
+                Err(e) => {
+                    eprintln!("❌ Branch certificate rejected: {}", e);
+                    process::exit(1);
                 }
-                process::exit(1);
             }
         }
         Commands::VerifyWork => {
-            use cliff_watch_core::git::get_staged_diff;
+            use cliff_watch_core::git::{get_staged_diff, get_ticket_binding};
             use cliff_watch_core::complexity::estimate_entropic_cost;
+            use cliff_watch_core::policy::{decide_pending, PolicyScope};
+            use cliff_watch_core::ticket::SignedTicket;
 
             let repo = match open_repository(Path::new(".")) {
                 Ok(repo) => repo,
@@ -543,16 +1034,43 @@ async fn main() {
             }
 
             let cost = estimate_entropic_cost(&diff, None);
-            
-            match query_daemon(cliff_watch_core::protocol::Request::GetTicket { cost }).await {
-                Ok(cliff_watch_core::protocol::Response::Ticket { success, message, signature }) => {
+
+            let binding = match get_ticket_binding(&repo) {
+                Ok(b) => b,
+                Err(e) => {
+                    eprintln!("❌ Error binding ticket to staged tree: {}", e);
+                    process::exit(1);
+                }
+            };
+
+            // Reglas de policy.toml que aplican a lo staged en la rama actual
+            let mut policy = pending_policy(decide_pending(&repo, &binding, &PolicyScope::local(&repo, None)));
+            // Sin ticket, el commit solo pasa si su política lo tolera (p. ej. docs en audit)
+            let fail = |policy: &cliff_watch_core::policy::PolicyDecision| -> ! {
+                if tolerates_missing_ticket(policy) {
+                    process::exit(0);
+                }
+                process::exit(1);
+            };
+
+            match request_ticket(cost, binding, policy.required_percent()).await {
+                Ok(cliff_watch_core::protocol::Response::Ticket { success, message, signature, payload, proof, log_head }) => {
                     if success {
                         println!("✅ Thermodynamic check passed: {}", message);
+                        if let Err(e) = enforce_pending_policy(&mut policy, proof.as_deref()) {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
                         
                         // Guardar el ticket firmado para el hook prepare-commit-msg
-                        if let Some(sig_bytes) = signature {
-                            let sig_hex = hex::encode(sig_bytes);
-                            let ticket_data = format!("score={:.2}:sig={}", cost, sig_hex);
+                        if let (Some(sig_bytes), Some(payload)) = (signature, payload) {
+                            let ticket_data = match SignedTicket::from_parts(&payload, sig_bytes) {
+                                Ok(ticket) => ticket.to_trailer_value(),
+                                Err(e) => {
+                                    eprintln!("❌ Daemon returned a malformed ticket: {}", e);
+                                    process::exit(1);
+                                }
+                            };
                             
                             let gov_dir = repo.path().join("cliff-watch");
                             if !gov_dir.exists() {
@@ -562,38 +1080,43 @@ async fn main() {
                             if let Err(e) = std::fs::write(ticket_file, ticket_data) {
                                 eprintln!("⚠️ Error saving ticket: {}", e);
                             }
-                        }
 
-                        // v2.0: Obtener datos del Witness para certificación de foco
-                        match query_daemon(cliff_watch_core::protocol::Request::GetWitness { reset: true }).await {
-                            Ok(cliff_watch_core::protocol::Response::Witness { data }) => {
-                                let witness_file = repo.path().join("cliff-watch").join("latest_witness");
-                                if let Err(e) = std::fs::write(witness_file, data) {
-                                    eprintln!("⚠️ Error saving witness data: {}", e);
+                            // v3.0: Prueba de rango comprometida por el ticket
+                            if let Some(proof_data) = proof {
+                                if let Err(e) = std::fs::write(gov_dir.join("latest_proof"), proof_data) {
+                                    eprintln!("⚠️ Error saving humanity proof: {}", e);
                                 } else {
-                                    println!("✅ Focus witness data recorded (v2.0)");
+                                    println!("✅ Humanity range proof attached (ZKP)");
+                                }
+                            }
+
+                            // Head firmado del log de transparencia que incluye el ticket
+                            if let Some(head) = log_head {
+                                if let Err(e) = std::fs::write(gov_dir.join("latest_log"), head) {
+                                    eprintln!("⚠️ Error saving transparency log head: {}", e);
                                 }
                             }
-                            _ => eprintln!("⚠️ Could not retrieve focus witness data"),
                         }
-                        
+
+                        // El Sentinel Report lo emite `inspect`, último paso del hook pre-commit,
+                        // para que cada commit lleve un único informe (con la PoW, si la hubo)
                         process::exit(0);
                     } else {
                         eprintln!("❌ {}", message);
-                        process::exit(1);
+                        fail(&policy);
                     }
                 }
                 Ok(cliff_watch_core::protocol::Response::Error(e)) => {
                     eprintln!("❌ Daemon error: {}", e);
-                    process::exit(1);
+                    fail(&policy);
                 }
                 Err(e) => {
                     eprintln!("❌ Daemon communication error: {}", e);
-                    process::exit(1);
+                    fail(&policy);
                 }
                 _ => {
                     eprintln!("❌ Unexpected response from daemon");
-                    process::exit(1);
+                    fail(&policy);
                 }
             }
         }
@@ -606,9 +1129,10 @@ async fn main() {
                 }
             };
 
-            // 1. Obtener la evidencia del Witness (reseteando el acumulador)
-            match query_daemon(cliff_watch_core::protocol::Request::GetWitness { reset: true }).await {
-                Ok(cliff_watch_core::protocol::Response::Witness { data }) => {
+            // 1. Obtener el Sentinel Report sellado (abriendo una nueva ventana)
+            match query_daemon(cliff_watch_core::protocol::Request::GetReport { reset: true }).await {
+                Ok(cliff_watch_core::protocol::Response::Report { report }) => {
+                    let data = report.to_trailer_value();
                     let gov_dir = repo.path().join("cliff-watch");
                     if !gov_dir.exists() {
                         let _ = std::fs::create_dir_all(&gov_dir);
@@ -616,9 +1140,12 @@ async fn main() {
                     
                     let witness_file = gov_dir.join("latest_witness");
                     if let Err(e) = std::fs::write(&witness_file, &data) {
-                        eprintln!("⚠️ Error saving witness data: {}", e);
+                        eprintln!("⚠️ Error saving sentinel report: {}", e);
                     } else {
-                        println!("✅ Evidence generated: Cliff-Watch-Witness v2.0");
+                        println!("✅ Evidence generated: Cliff-Watch-Witness (Sentinel Report v{})", report.version);
+                        if let Some(pow) = &report.integrity.proof_of_work {
+                            println!("⛏️  Proof of work recorded ({} bits)", pow.difficulty);
+                        }
                     }
 
                     // 2. Si se proporciona un archivo de mensaje (Git hook manual), inyectamos ahora
@@ -626,39 +1153,1022 @@ async fn main() {
                         let msg_path = Path::new(&msg_file_path);
                         if msg_path.exists() {
                             let _ = Command::new("git")
-                                .args(["interpret-trailers", "--in-place", "--trailer", &format!("Cliff-Watch-Witness: {}", data), &msg_file_path])
+                                .args(["interpret-trailers", "--in-place", "--trailer", &format!("{}: {}", cliff_watch_core::report::REPORT_TRAILER, data), &msg_file_path])
                                 .status();
                         }
                     }
                 }
                 _ => {
-                    eprintln!("❌ Could not retrieve witness evidence from daemon.");
+                    eprintln!("❌ Could not retrieve sentinel report from daemon.");
                     process::exit(1);
                 }
             }
         }
-        Commands::Config { action } => {
-            match action {
-                ConfigAction::Init => {
-                    let config_content = r#"# Cliff-Watch Configuration
-
-[governance]
-# Dificultad de la "Prueba de Sudor" (Easy, Normal, Hardcore)
-difficulty = "Normal"
-# Entropía mínima para validar un commit (base 2.5)
-min_entropy = 2.5
+        Commands::Key { action } => {
+            use cliff_watch_core::trust::{load_trust, pinned_roots, trust_toml_path, TrustConfig, TrustSignature};
+            use cliff_watch_core::crypto::keyfile;
+            use cliff_watch_core::protocol::{Request, Response};
+            use chrono::SubsecRound;
 
-[monitoring]
-# Ventana de agrupación de eventos (ms)
-debounce_window_ms = 500
-# Directorios a ignorar (además de .git)
-ignore_top_level_dirs = [".git", "target", "node_modules", "dist", "build"]
-# Extensiones de archivo a ignorar
-ignore_extensions = ["log", "lock", "tmp", "bak"]
-"#;
-                    let path = Path::new("cliff-watch.toml");
-                    if path.exists() {
-                        eprintln!("⚠️ cliff-watch.toml already exists!");
+            let repo = match open_repository(Path::new(".")) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    eprintln!("❌ Error opening repository: {}", e);
+                    process::exit(1);
+                }
+            };
+            let parse_at = |at: Option<String>| match at {
+                None => chrono::Utc::now().trunc_subsecs(0),
+                Some(at) => match chrono::DateTime::parse_from_rfc3339(&at) {
+                    Ok(t) => t.with_timezone(&chrono::Utc),
+                    Err(e) => {
+                        eprintln!("❌ Invalid timestamp '{}': {}", at, e);
+                        process::exit(1);
+                    }
+                },
+            };
+            // Las mutaciones solo tocan trust.toml (nunca el fichero legacy)
+            let edit_trust = |edit: &dyn Fn(&mut TrustConfig) -> Result<String, String>| {
+                let path = match trust_toml_path(&repo) {
+                    Some(p) => p,
+                    None => {
+                        eprintln!("❌ trust.toml requires a working tree");
+                        process::exit(1);
+                    }
+                };
+                let result = TrustConfig::read(&path).and_then(|mut config| {
+                    let message = edit(&mut config)?;
+                    // Cualquier cambio invalida la firma del documento
+                    let unsigned = config.signature.take().is_some();
+                    config.write(&path)?;
+                    Ok((message, unsigned))
+                });
+                match result {
+                    Ok((message, unsigned)) => {
+                        println!("✅ {}", message);
+                        if unsigned {
+                            println!("💡 trust.toml changed: a maintainer must sign it again with `cliff-watch key sign-trust`");
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                }
+            };
+
+            match action {
+                KeyAction::Rotate { alias, key, overlap_days } => {
+                    let key = public_key_hex_or_exit(&key);
+                    let now = chrono::Utc::now().trunc_subsecs(0);
+                    edit_trust(&|config| {
+                        let retired = config.rotate(&alias, &key, now, chrono::Duration::days(overlap_days))?;
+                        Ok(format!(
+                            "Rotated '{}': new key active now, {} previous key(s) retire at {}",
+                            alias,
+                            retired.len(),
+                            (now + chrono::Duration::days(overlap_days)).to_rfc3339()
+                        ))
+                    });
+                }
+                KeyAction::Revoke { key, reason, at } => {
+                    let at = parse_at(at);
+                    edit_trust(&|config| {
+                        let revoked = config.revoke(&key, &reason, at)?;
+                        Ok(format!("Revoked {} ({}) of '{}': {}", revoked.key_id(), at.to_rfc3339(), revoked.alias, reason))
+                    });
+                }
+                KeyAction::Export { out } => {
+                    let signing_key = unlock_daemon_key();
+                    let passphrase = prompt_passphrase("Export passphrase", true);
+                    let sealed = match keyfile::seal_seed(&signing_key.to_bytes(), &passphrase, keyfile::KdfParams::default()) {
+                        Ok(b) => b,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    if let Err(e) = std::fs::write(&out, sealed) {
+                        eprintln!("❌ Failed to write {}: {}", out, e);
+                        process::exit(1);
+                    }
+                    println!("✅ Daemon key exported (encrypted) to {}", out);
+                    println!("   Key ID: {}", cliff_watch_core::crypto::key_id(&signing_key.verifying_key()));
+                }
+                KeyAction::Import { input, force } => {
+                    let key_path = daemon_key_path_or_exit();
+                    if key_path.exists() && !force {
+                        eprintln!("❌ {} already exists (use --force to replace it)", key_path.display());
+                        process::exit(1);
+                    }
+                    let sealed = match std::fs::read(&input) {
+                        Ok(b) => b,
+                        Err(e) => {
+                            eprintln!("❌ Failed to read {}: {}", input, e);
+                            process::exit(1);
+                        }
+                    };
+                    let seed = match keyfile::open_seed(&sealed, &prompt_passphrase("Export passphrase", false)) {
+                        Ok(seed) => seed,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let signing_key = cliff_watch_core::crypto::SigningKey::from_bytes(&seed);
+                    let passphrase = prompt_passphrase("New daemon key passphrase", true);
+                    if let Err(e) = cliff_watch_core::crypto::write_identity(&key_path, &signing_key, Some(&passphrase)) {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                    println!("✅ Daemon key imported to {}", key_path.display());
+                    println!("   Key ID: {}", cliff_watch_core::crypto::key_id(&signing_key.verifying_key()));
+                }
+                KeyAction::Passwd => {
+                    let key_path = daemon_key_path_or_exit();
+                    let signing_key = unlock_daemon_key();
+                    let passphrase = prompt_passphrase("New daemon key passphrase", true);
+                    if let Err(e) = cliff_watch_core::crypto::write_identity(&key_path, &signing_key, Some(&passphrase)) {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                    println!("✅ Daemon key encrypted at {}", key_path.display());
+                }
+                KeyAction::ImportSigners { file } => {
+                    let path = match file.map(std::path::PathBuf::from).or_else(|| cliff_watch_core::trust::allowed_signers_path(&repo)) {
+                        Some(p) => p,
+                        None => {
+                            eprintln!("❌ No file given and gpg.ssh.allowedSignersFile is not set");
+                            process::exit(1);
+                        }
+                    };
+                    let content = match std::fs::read_to_string(&path) {
+                        Ok(c) => c,
+                        Err(e) => {
+                            eprintln!("❌ Failed to read {}: {}", path.display(), e);
+                            process::exit(1);
+                        }
+                    };
+                    edit_trust(&|config| {
+                        let added = config.import_allowed_signers(&content)?;
+                        Ok(format!("Imported {} key(s) from {}", added.len(), path.display()))
+                    });
+                }
+                KeyAction::ExportSigners { out } => {
+                    let trust = match trust_toml_path(&repo).map(|p| TrustConfig::read(&p)) {
+                        Some(Ok(t)) => t,
+                        Some(Err(e)) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                        None => {
+                            eprintln!("❌ trust.toml requires a working tree");
+                            process::exit(1);
+                        }
+                    };
+                    let signers = trust.to_allowed_signers();
+                    match out {
+                        Some(out) => {
+                            if let Err(e) = std::fs::write(&out, &signers) {
+                                eprintln!("❌ Failed to write {}: {}", out, e);
+                                process::exit(1);
+                            }
+                            println!("✅ {} key(s) exported to {}", signers.lines().count(), out);
+                            println!("   git config gpg.ssh.allowedSignersFile {}", out);
+                        }
+                        None => print!("{}", signers),
+                    }
+                }
+                KeyAction::Emails { alias, email } => {
+                    edit_trust(&|config| {
+                        let changed = config.set_emails(&alias, &email)?;
+                        Ok(match email.is_empty() {
+                            true => format!("'{}' ({} key(s)) may sign for any author", alias, changed),
+                            false => format!("'{}' ({} key(s)) may sign for {}", alias, changed, email.join(", ")),
+                        })
+                    });
+                }
+                KeyAction::PinRoot { key } => match cliff_watch_core::trust::pin_root(&repo, &public_key_hex_or_exit(&key)) {
+                    Ok(root) => {
+                        println!("✅ Trust root pinned: {} ({})", root.key_id(), cliff_watch_core::trust::TRUST_ROOT_CONFIG);
+                        println!("   Only keys endorsed from the pinned roots are trusted, and trust.toml must be signed by a maintainer.");
+                    }
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                },
+                KeyAction::Endorse { key } => {
+                    let trust = match trust_toml_path(&repo).map(|p| TrustConfig::read(&p)) {
+                        Some(Ok(t)) => t,
+                        Some(Err(e)) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                        None => {
+                            eprintln!("❌ trust.toml requires a working tree");
+                            process::exit(1);
+                        }
+                    };
+                    // Todas las claves del alias, o la clave indicada
+                    let mut entries: Vec<_> = trust.keys.iter().filter(|k| k.alias == key).cloned().collect();
+                    if entries.is_empty() {
+                        entries.extend(trust.find_key(&key).cloned());
+                    }
+                    if entries.is_empty() {
+                        eprintln!("❌ '{}' is not an alias or key in trust.toml", key);
+                        process::exit(1);
+                    }
+
+                    // El daemon solo avala si es Maintainer efectivo de este trust.toml
+                    let config = match trust.to_toml() {
+                        Ok(c) => c,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let roots: Vec<String> = pinned_roots(&repo).unwrap_or_default().iter().map(|r| r.to_hex()).collect();
+                    let mut endorsements = Vec::new();
+                    for entry in entries {
+                        match query_daemon(Request::Endorse { key: entry.clone(), config: config.clone(), roots: roots.clone() }).await {
+                            Ok(Response::TrustSignature { value }) => endorsements.push((entry, value)),
+                            Ok(Response::Error(e)) => {
+                                eprintln!("❌ {}", e);
+                                process::exit(1);
+                            }
+                            Ok(_) => {
+                                eprintln!("❌ Unexpected response from daemon");
+                                process::exit(1);
+                            }
+                            Err(e) => {
+                                eprintln!("❌ Daemon not running: {}", e);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    edit_trust(&|config| {
+                        for (entry, value) in &endorsements {
+                            let endorser = TrustSignature::parse(value)?.key_id;
+                            let target = config
+                                .keys
+                                .iter_mut()
+                                .find(|k| k.key_id() == entry.key_id() && k.alias == entry.alias)
+                                .ok_or("trust.toml changed while endorsing")?;
+                            // Un aval nuevo sustituye al anterior del mismo firmante
+                            target.endorsements.retain(|e| TrustSignature::parse(e).map(|e| e.key_id != endorser).unwrap_or(true));
+                            target.endorsements.push(value.clone());
+                        }
+                        let endorser = endorsements.first().and_then(|(_, v)| TrustSignature::parse(v).ok()).map(|s| s.key_id).unwrap_or_default();
+                        Ok(format!("Endorsed {} key(s) of '{}' with {}", endorsements.len(), endorsements[0].0.alias, endorser))
+                    });
+                }
+                KeyAction::SignTrust => {
+                    let path = match trust_toml_path(&repo) {
+                        Some(p) => p,
+                        None => {
+                            eprintln!("❌ trust.toml requires a working tree");
+                            process::exit(1);
+                        }
+                    };
+                    let mut trust = match TrustConfig::read(&path) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    trust.signature = None;
+                    let config = match trust.to_toml() {
+                        Ok(c) => c,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let roots = pinned_roots(&repo).unwrap_or_default().iter().map(|r| r.to_hex()).collect();
+                    match query_daemon(Request::SignTrust { config, roots }).await {
+                        Ok(Response::TrustSignature { value }) => trust.signature = Some(value),
+                        Ok(Response::Error(e)) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                        Ok(_) => {
+                            eprintln!("❌ Unexpected response from daemon");
+                            process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Daemon not running: {}", e);
+                            process::exit(1);
+                        }
+                    }
+                    if let Err(e) = trust.write(&path) {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                    println!("✅ trust.toml signed");
+                    match pinned_roots(&repo) {
+                        Ok(roots) if !roots.is_empty() => match trust.verify_document(&roots) {
+                            Ok(signer) => println!("   Signer: {} (endorsed maintainer)", signer),
+                            Err(e) => println!("⚠️  {}", e),
+                        },
+                        Ok(_) => println!("💡 No trust root pinned: the signature is only enforced after `cliff-watch key pin-root`"),
+                        Err(e) => println!("⚠️  {}", e),
+                    }
+                }
+                KeyAction::List { at } => {
+                    let at = parse_at(at);
+                    let trust = match load_trust(&repo) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    if trust.keys.is_empty() {
+                        println!("No trusted keys registered.");
+                    }
+                    let fmt_time = |t: Option<chrono::DateTime<chrono::Utc>>| {
+                        t.map(|t| t.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "-".to_string())
+                    };
+                    println!("{:<16} {:<17} {:<12} {:<17} {:<17} {:<12} EMAILS", "ALIAS", "KEY ID", "ROLE", "NOT BEFORE", "NOT AFTER", "STATUS");
+                    for key in &trust.keys {
+                        println!(
+                            "{:<16} {:<17} {:<12} {:<17} {:<17} {:<12} {}",
+                            key.alias,
+                            key.key_id(),
+                            key.role,
+                            fmt_time(key.not_before),
+                            fmt_time(key.not_after),
+                            trust.status_at(key, at.timestamp()).label(),
+                            if key.emails.is_empty() { "*".to_string() } else { key.emails.join(", ") }
+                        );
+                    }
+
+                    // Con raíces fijadas, las entradas de trust.toml sin aval quedan fuera
+                    let roots = pinned_roots(&repo).unwrap_or_default();
+                    if !roots.is_empty() {
+                        let raw = trust_toml_path(&repo).and_then(|p| TrustConfig::read(&p).ok()).unwrap_or_default();
+                        for key in raw.keys.iter().filter(|k| !trust.keys.iter().any(|t| t.key_id() == k.key_id())) {
+                            println!(
+                                "{:<16} {:<17} {:<12} {:<17} {:<17} not endorsed",
+                                key.alias,
+                                key.key_id(),
+                                key.role,
+                                fmt_time(key.not_before),
+                                fmt_time(key.not_after)
+                            );
+                        }
+                        let signer = raw.verify_document(&roots).unwrap_or_default();
+                        let roots: Vec<String> = roots.iter().map(|r| r.key_id()).collect();
+                        println!("\nWeb of trust: root(s) {} · trust.toml signed by {}", roots.join(", "), signer);
+                    }
+                }
+            }
+        }
+        Commands::Tpm { action } => {
+            use cliff_watch_core::crypto::{tpm::TpmSigner, PublicKey};
+
+            let configured = cliff_watch_core::config::GovConfig::load()
+                .map(|c| c.identity.tpm_handle)
+                .unwrap_or(cliff_watch_core::crypto::tpm::DEFAULT_IDENTITY_HANDLE);
+            let resolve_handle = |handle: Option<String>| match handle {
+                None => configured,
+                Some(h) => match parse_tpm_handle(&h) {
+                    Some(h) => h,
+                    None => {
+                        eprintln!("❌ Invalid TPM handle: {}", h);
+                        process::exit(1);
+                    }
+                },
+            };
+
+            match action {
+                TpmAction::Init { alias, handle } => {
+                    let handle = resolve_handle(handle);
+                    let signer = match TpmSigner::load_or_create(handle) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            eprintln!("❌ TPM identity failed: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let public_key = PublicKey::P256(*signer.public_key());
+
+                    let repo = match open_repository(Path::new(".")) {
+                        Ok(repo) => repo,
+                        Err(e) => {
+                            eprintln!("❌ Error opening repository: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    if let Err(e) = cliff_watch_core::trust::register_public_key(&repo, &public_key.to_hex(), &alias, &[]) {
+                        eprintln!("❌ Failed to register key: {}", e);
+                        process::exit(1);
+                    }
+
+                    println!("✅ TPM identity persisted at 0x{:08x} and registered as '{}'", handle, alias);
+                    println!("   Public Key: {}", public_key.to_hex());
+                    println!("   Key ID:     {}", public_key.key_id());
+                    println!("   Set [identity] backend = \"tpm\" in cliff-watch.toml so the daemon signs with it.");
+                }
+                TpmAction::Show { handle } => {
+                    let handle = resolve_handle(handle);
+                    match TpmSigner::load(handle) {
+                        Ok(signer) => {
+                            let public_key = PublicKey::P256(*signer.public_key());
+                            println!("Handle:     0x{:08x}", handle);
+                            println!("Public Key: {}", public_key.to_hex());
+                            println!("Key ID:     {}", public_key.key_id());
+                        }
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+        Commands::Cosign { commit } => {
+            use cliff_watch_core::notes::{append_to_note, read_attestation, NOTES_REF};
+            use cliff_watch_core::ticket::{extract_ticket_values, SignedTicket, COSIGN_TRAILER};
+
+            let repo = match open_repository(Path::new(".")) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    eprintln!("❌ Error opening repository: {}", e);
+                    process::exit(1);
+                }
+            };
+            let commit_obj = match repo.revparse_single(&commit).and_then(|o| o.peel_to_commit()) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("❌ Commit not found: {}", e);
+                    process::exit(1);
+                }
+            };
+
+            let attestation = read_attestation(&repo, &commit_obj);
+            let ticket = match extract_ticket_values(&attestation.text)
+                .into_iter()
+                .find_map(|v| SignedTicket::parse_trailer_value(v).ok())
+            {
+                Some(t) => t,
+                None => {
+                    eprintln!("❌ {} has no cliff-watch ticket to co-sign", commit);
+                    process::exit(1);
+                }
+            };
+
+            // La co-firma se añade a la nota: el commit (y su hash) no cambian
+            match query_daemon(cliff_watch_core::protocol::Request::Cosign { payload: ticket.encoded.clone() }).await {
+                Ok(cliff_watch_core::protocol::Response::Cosignature { value }) => {
+                    match append_to_note(&repo, commit_obj.id(), COSIGN_TRAILER, &value) {
+                        Ok(true) => {
+                            println!("✅ Ticket of {} co-signed (issuer kid {})", commit, ticket.payload.key_id);
+                            println!("   Stored in {}. Share it with `cliff-watch notes push`.", NOTES_REF);
+                        }
+                        Ok(false) => println!("⚠️ This daemon already co-signed {}", commit),
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    }
+                }
+                Ok(cliff_watch_core::protocol::Response::Error(e)) => {
+                    eprintln!("❌ Daemon error: {}", e);
+                    process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("❌ Unexpected response from daemon");
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("❌ Could not connect to daemon: {}. Is it running?", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Commands::Notes { action } => {
+            use cliff_watch_core::notes::{attach_pending, fetch_notes, push_notes, read_note, record_gap, NOTES_REF};
+
+            let repo = match open_repository(Path::new(".")) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    eprintln!("❌ Error opening repository: {}", e);
+                    process::exit(1);
+                }
+            };
+            let resolve = |rev: &str| match repo.revparse_single(rev).and_then(|o| o.peel_to_commit()) {
+                Ok(c) => c.id(),
+                Err(e) => {
+                    eprintln!("❌ Commit not found: {}", e);
+                    process::exit(1);
+                }
+            };
+
+            match action {
+                NotesAction::Attach { commit } => match attach_pending(&repo, resolve(&commit)) {
+                    Ok(true) => println!("✅ Attestation stored in {}", NOTES_REF),
+                    Ok(false) => {}
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                },
+                NotesAction::Show { commit } => match read_note(&repo, resolve(&commit)) {
+                    Some(note) => print!("{}", note),
+                    None => {
+                        eprintln!("⚠️ No {} note for {}", NOTES_REF, commit);
+                        process::exit(1);
+                    }
+                },
+                NotesAction::Gap { commits, reason } => {
+                    for commit in &commits {
+                        match record_gap(&repo, resolve(commit), &reason) {
+                            Ok(true) => println!("📝 Gap documented for {}", commit),
+                            Ok(false) => println!("➖ {} already documents this gap", commit),
+                            Err(e) => {
+                                eprintln!("❌ {}", e);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    println!("💡 Share it: cliff-watch notes push <remote>");
+                }
+                NotesAction::Push { remote } => match push_notes(&repo, &remote) {
+                    Ok(()) => println!("✅ {} pushed to '{}'", NOTES_REF, remote),
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                },
+                NotesAction::Fetch { remote } => match fetch_notes(&repo, &remote) {
+                    Ok(()) => println!("✅ {} fetched from '{}'", NOTES_REF, remote),
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                },
+            }
+        }
+        Commands::SignShim { action } => {
+            use cliff_watch_core::signing::{install_sign_shim, real_program, remove_sign_shim, SignatureFormat};
+
+            let repo = match open_repository(Path::new(".")) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    eprintln!("❌ Error opening repository: {}", e);
+                    process::exit(1);
+                }
+            };
+            match action {
+                SignShimAction::Install => {
+                    let installed = std::env::current_exe()
+                        .map_err(|e| e.to_string())
+                        .and_then(|exe| install_sign_shim(&repo, &exe));
+                    match installed {
+                        Ok(shim) => {
+                            println!("✅ Sign shim installed: {}", shim.display());
+                            println!("   gpg.program     → shim → {}", real_program(Some(&repo), SignatureFormat::OpenPgp));
+                            println!("   gpg.ssh.program → shim → {}", real_program(Some(&repo), SignatureFormat::Ssh));
+                            if !repo.config().and_then(|c| c.get_bool("commit.gpgsign")).unwrap_or(false) {
+                                println!("💡 Commits are not signed by default: git config commit.gpgsign true");
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Failed to install sign shim: {}", e);
+                            process::exit(1);
+                        }
+                    }
+                }
+                SignShimAction::Uninstall => match remove_sign_shim(&repo) {
+                    Ok(()) => println!("✅ Sign shim removed, original signing programs restored"),
+                    Err(e) => {
+                        eprintln!("❌ Failed to remove sign shim: {}", e);
+                        process::exit(1);
+                    }
+                },
+            }
+        }
+        Commands::PrePush { remote, .. } => {
+            use cliff_watch_core::ci::CommitStatus;
+            use cliff_watch_core::server_hook::{check_push, parse_pre_push};
+
+            let repo = match open_repository(Path::new(".")) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    eprintln!("❌ Error opening repository: {}", e);
+                    process::exit(1);
+                }
+            };
+            let mut input = String::new();
+            let updates = std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)
+                .map_err(|e| e.to_string())
+                .and_then(|_| parse_pre_push(&input));
+            let updates = match updates {
+                Ok(updates) => updates,
+                Err(e) => {
+                    eprintln!("❌ cliff-watch pre-push: {}", e);
+                    process::exit(1);
+                }
+            };
+
+            let mut blocked = Vec::new();
+            let mut gaps: Vec<(String, String)> = Vec::new();
+            for update in &updates {
+                let verdict = match check_push(&repo, &remote, update) {
+                    Ok(verdict) => verdict,
+                    Err(e) => {
+                        eprintln!("❌ {}: {}", update.refname, e);
+                        blocked.push(update.refname.clone());
+                        continue;
+                    }
+                };
+                if !print_ref_verdict(&verdict, "bloqueada") {
+                    blocked.push(update.refname.clone());
+                }
+                for c in &verdict.outcomes {
+                    if c.status == CommitStatus::Unsigned && c.gap.is_none() && !gaps.iter().any(|(oid, _)| *oid == c.commit) {
+                        gaps.push((c.commit.clone(), c.summary.clone()));
+                    }
+                }
+            }
+
+            if let Some(reason) = (!gaps.is_empty()).then(|| ask_gap_reason(&gaps)).flatten() {
+                for (commit, _) in &gaps {
+                    let recorded = repo
+                        .revparse_single(commit)
+                        .map_err(|e| e.to_string())
+                        .and_then(|o| cliff_watch_core::notes::record_gap(&repo, o.id(), &reason));
+                    if let Err(e) = recorded {
+                        eprintln!("❌ {}: {}", &commit[..8], e);
+                    }
+                }
+                println!("✅ Huecos documentados en {}. Publica las notas: cliff-watch notes push {}", cliff_watch_core::notes::NOTES_REF, remote);
+            }
+            if !blocked.is_empty() {
+                eprintln!("\n⛔ Push bloqueado por cliff-watch: {}", blocked.join(", "));
+                eprintln!("💡 Rehaz esos commits con el daemon activo, o publícalos igualmente con git push --no-verify");
+                eprintln!("   (el hook del servidor puede rechazarlos).");
+                process::exit(1);
+            }
+        }
+        Commands::ServerHook { action } => {
+            use cliff_watch_core::server_hook::{install_server_hook, parse_updates, RefUpdate};
+
+            let updates = match action {
+                ServerHookAction::Install { update } => {
+                    let repo = match open_repository(Path::new(".")) {
+                        Ok(repo) => repo,
+                        Err(e) => {
+                            eprintln!("❌ Error opening repository: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let hook = if update { "update" } else { "pre-receive" };
+                    let installed = std::env::current_exe()
+                        .map_err(|e| e.to_string())
+                        .and_then(|exe| install_server_hook(&repo, hook, &exe));
+                    match installed {
+                        Ok(path) => {
+                            println!("✅ Server hook installed: {}", path.display());
+                            println!("💡 Ramas: strict por defecto. Política por ref:");
+                            println!("   git config cliff-watch.refs/heads/feature/*.policy audit   # strict, signed, audit, off");
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Failed to install server hook: {}", e);
+                            process::exit(1);
+                        }
+                    }
+                    return;
+                }
+                ServerHookAction::PreReceive => {
+                    let mut input = String::new();
+                    if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), &mut input) {
+                        eprintln!("❌ cliff-watch: failed to read pre-receive input: {}", e);
+                        process::exit(1);
+                    }
+                    parse_updates(&input)
+                }
+                ServerHookAction::Update { refname, old, new } => RefUpdate::new(&old, &new, &refname).map(|u| vec![u]),
+            };
+            match updates {
+                Ok(updates) => run_server_hook(&updates),
+                Err(e) => {
+                    eprintln!("❌ cliff-watch: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Commands::Credential { action } => {
+            use cliff_watch_core::credential::verify_credential;
+            use cliff_watch_core::crypto::did::{from_did_key, to_did_key};
+            use cliff_watch_core::crypto::PublicKey;
+            use cliff_watch_core::protocol::{Request, Response};
+
+            match action {
+                CredentialAction::Did => match query_daemon(Request::GetIdentity).await {
+                    Ok(Response::Identity { public_key, did, backend }) => {
+                        println!("🆔 {}", did);
+                        println!("   Public key: {} (via {})", public_key, backend);
+                    }
+                    Ok(Response::Error(e)) => {
+                        eprintln!("❌ Daemon error: {}", e);
+                        process::exit(1);
+                    }
+                    Ok(_) => {
+                        eprintln!("❌ Unexpected response from daemon");
+                        process::exit(1);
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Daemon communication error: {}", e);
+                        process::exit(1);
+                    }
+                },
+                CredentialAction::Issue { subject, limit, out } => {
+                    let issuer = match query_daemon(Request::GetIdentity).await {
+                        Ok(Response::Identity { public_key, .. }) => match PublicKey::from_hex(&public_key) {
+                            Ok(k) => k,
+                            Err(e) => {
+                                eprintln!("❌ Daemon returned an invalid key: {}", e);
+                                process::exit(1);
+                            }
+                        },
+                        Ok(_) => {
+                            eprintln!("❌ Unexpected response from daemon");
+                            process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Daemon communication error: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let repo = match open_repository(Path::new(".")) {
+                        Ok(repo) => repo,
+                        Err(e) => {
+                            eprintln!("❌ Error opening repository: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let trust = match cliff_watch_core::trust::load_trust_at_rev(&repo, "HEAD") {
+                        Ok(t) => t,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+
+                    // Alias (su clave más reciente), clave de trust.toml o did:key
+                    let subject_key = match &subject {
+                        None => Ok(issuer.clone()),
+                        Some(s) if s.starts_with("did:") => from_did_key(s),
+                        Some(s) => trust
+                            .keys
+                            .iter()
+                            .rfind(|k| &k.alias == s)
+                            .or_else(|| trust.find_key(s))
+                            .ok_or_else(|| format!("'{}' is not an alias or key in trust.toml", s))
+                            .and_then(|k| PublicKey::from_hex(&k.public_key)),
+                    };
+                    let subject_key = match subject_key {
+                        Ok(k) => k,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    // El daemon resume el historial él mismo antes de firmarlo
+                    let request = Request::IssueCredential { repo: repo.path().display().to_string(), subject: subject_key.to_hex(), limit };
+                    let document = match query_daemon(request).await {
+                        Ok(Response::Credential { document }) => document,
+                        Ok(Response::Error(e)) => {
+                            eprintln!("❌ Daemon error: {}", e);
+                            process::exit(1);
+                        }
+                        Ok(_) => {
+                            eprintln!("❌ Unexpected response from daemon");
+                            process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Daemon communication error: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    match out {
+                        Some(path) => {
+                            if let Err(e) = std::fs::write(&path, format!("{}\n", document)) {
+                                eprintln!("❌ Failed to write {}: {}", path, e);
+                                process::exit(1);
+                            }
+                            match verify_credential(&document, chrono::Utc::now()) {
+                                Ok(credential) => println!(
+                                    "✅ Credential for {} written to {} ({} verified commits, average score {:.2})",
+                                    to_did_key(&subject_key),
+                                    path,
+                                    credential.summary.verified_commits,
+                                    credential.summary.average_score
+                                ),
+                                Err(e) => {
+                                    eprintln!("❌ Daemon returned an invalid credential: {}", e);
+                                    process::exit(1);
+                                }
+                            }
+                        }
+                        None => println!("{}", document),
+                    }
+                }
+                CredentialAction::Verify { file, min_commits, min_score, import, allow_untrusted_issuer } => {
+                    let document = match std::fs::read_to_string(&file) {
+                        Ok(d) => d,
+                        Err(e) => {
+                            eprintln!("❌ Failed to read {}: {}", file, e);
+                            process::exit(1);
+                        }
+                    };
+                    let credential = match verify_credential(&document, chrono::Utc::now()) {
+                        Ok(c) => c,
+                        Err(e) => {
+                            eprintln!("❌ Credential rejected: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let summary = &credential.summary;
+                    let repo = open_repository(Path::new(".")).ok();
+                    let trust = repo.as_ref().and_then(|r| cliff_watch_core::trust::load_trust_at_rev(r, "HEAD").ok());
+                    let issuer_alias = trust.as_ref().and_then(|t| t.find_key(&credential.issuer.to_hex())).map(|k| k.alias.clone());
+
+                    println!("✅ Credential signature valid");
+                    match (&issuer_alias, credential.is_self_issued()) {
+                        (Some(alias), _) => println!("   Issuer:  {} (trusted here as '{}')", to_did_key(&credential.issuer), alias),
+                        (None, true) => println!("   Issuer:  self-issued by the subject"),
+                        (None, false) => println!("   Issuer:  {} (not in trust.toml)", to_did_key(&credential.issuer)),
+                    }
+                    println!("   Subject: {} (kid {})", to_did_key(&credential.subject), credential.subject.key_id());
+                    if let Some(repository) = &summary.repository {
+                        println!("   Repo:    {} @ {}", repository, &summary.head[..summary.head.len().min(8)]);
+                    }
+                    println!(
+                        "   Commits: {} verified ({} valid, {} audit, {} pow), average score {:.2}",
+                        summary.verified_commits, summary.valid_commits, summary.audit_commits, summary.pow_commits, summary.average_score
+                    );
+                    println!("   Period:  {} → {}", summary.first_commit.format("%Y-%m-%d"), summary.last_commit.format("%Y-%m-%d"));
+
+                    if summary.verified_commits < min_commits {
+                        eprintln!("❌ {} verified commits, {} required", summary.verified_commits, min_commits);
+                        process::exit(1);
+                    }
+                    if let Some(min) = min_score.filter(|min| summary.average_score < *min) {
+                        eprintln!("❌ Average score {:.2} below the required {:.2}", summary.average_score, min);
+                        process::exit(1);
+                    }
+
+                    if let Some(alias) = import {
+                        let Some(repo) = repo else {
+                            eprintln!("❌ --import requires a repository");
+                            process::exit(1);
+                        };
+                        // Una credencial autoemitida solo prueba que el sujeto tiene su clave
+                        if credential.is_self_issued() {
+                            eprintln!("❌ Refusing to import a self-issued credential: its history is a claim of the subject");
+                            process::exit(1);
+                        }
+                        if issuer_alias.is_none() {
+                            if !allow_untrusted_issuer {
+                                eprintln!("❌ The issuer is not in trust.toml; pass --allow-untrusted-issuer to import anyway");
+                                process::exit(1);
+                            }
+                            println!("⚠️  The issuer is not trusted here: the history is a claim of an unknown issuer");
+                        }
+                        match cliff_watch_core::trust::register_public_key(&repo, &credential.subject.to_hex(), &alias, &[]) {
+                            Ok(()) => println!("✅ Key {} registered as '{}'", credential.subject.key_id(), alias),
+                            Err(e) => {
+                                eprintln!("❌ {}", e);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Commands::Log { action } => match action {
+            LogAction::Verify { dir, key, limit } => {
+                use cliff_watch_core::crypto::PublicKey;
+                use cliff_watch_core::translog::TransparencyLog;
+
+                let dir = match dir.map(std::path::PathBuf::from).map(Ok).unwrap_or_else(TransparencyLog::default_dir) {
+                    Ok(d) => d,
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                };
+                let log = match TransparencyLog::open(&dir) {
+                    Ok(l) => l,
+                    Err(e) => {
+                        eprintln!("❌ {}", e);
+                        process::exit(1);
+                    }
+                };
+
+                // Claves del daemon: --key y, dentro de un repositorio, trust.toml
+                let repo = open_repository(Path::new(".")).ok();
+                let mut keys: Vec<PublicKey> = Vec::new();
+                if let Some(key) = key {
+                    match PublicKey::from_hex(&public_key_hex_or_exit(&key)) {
+                        Ok(k) => keys.push(k),
+                        Err(e) => {
+                            eprintln!("❌ Invalid public key: {}", e);
+                            process::exit(1);
+                        }
+                    }
+                }
+                if let Some(trust) = repo.as_ref().and_then(|r| cliff_watch_core::trust::load_trust_at_rev(r, "HEAD").ok()) {
+                    keys.extend(trust.keys.iter().filter_map(|k| PublicKey::from_hex(&k.public_key).ok()));
+                }
+                let lookup = |kid: &str| keys.iter().find(|k| k.key_id() == kid).cloned();
+
+                println!("📜 Transparency log: {} ({} entries)", log.dir().display(), log.size());
+                let mut failed = false;
+                match log.audit(lookup) {
+                    Ok(heads) => println!("✅ {} signed heads consistent with the log", heads),
+                    Err(reason) => {
+                        eprintln!("❌ Signed heads do not match the log ({})", reason);
+                        failed = true;
+                    }
+                }
+
+                if let Some(repo) = &repo {
+                    let revwalk = match repo.revwalk().and_then(|mut w| w.push_head().map(|_| w)) {
+                        Ok(w) => w,
+                        Err(e) => {
+                            eprintln!("❌ Error walking history: {}", e);
+                            process::exit(1);
+                        }
+                    };
+                    let (mut included, mut unlogged) = (0, 0);
+                    for oid in revwalk.take(limit).flatten() {
+                        let commit = match repo.find_commit(oid) {
+                            Ok(c) => c,
+                            Err(_) => continue,
+                        };
+                        let attestation = cliff_watch_core::notes::read_attestation(repo, &commit);
+                        match log.check_attestation(&attestation.text, lookup) {
+                            Ok(Some(_)) => included += 1,
+                            Ok(None) => unlogged += 1,
+                            Err(reason) if reason == "no_ticket" => {}
+                            Err(reason) => {
+                                eprintln!("❌ {} {} ({})", &oid.to_string()[..8], commit.summary().unwrap_or(""), reason);
+                                failed = true;
+                            }
+                        }
+                    }
+                    println!("✅ {} commit tickets proven included in the log", included);
+                    if unlogged > 0 {
+                        println!("⚠️  {} tickets predate the transparency log", unlogged);
+                    }
+                }
+
+                if failed {
+                    process::exit(1);
+                }
+            }
+        },
+        Commands::Config { action } => {
+            match action {
+                ConfigAction::Init => {
+                    let config_content = r#"# Cliff-Watch Configuration
+
+[governance]
+# Dificultad de la "Prueba de Sudor" (Easy, Normal, Hardcore)
+difficulty = "Normal"
+# Entropía mínima para validar un commit (base 2.5)
+min_entropy = 2.5
+
+[monitoring]
+# Ventana de agrupación de eventos (ms)
+debounce_window_ms = 500
+# Directorios a ignorar (además de .git)
+ignore_top_level_dirs = [".git", "target", "node_modules", "dist", "build"]
+# Extensiones de archivo a ignorar
+ignore_extensions = ["log", "lock", "tmp", "bak"]
+
+[identity]
+# Clave con la que el daemon firma los tickets: "file" (Ed25519), "tpm" (ECDSA-P256),
+# "ssh" (clave OpenSSH ed25519 sin cifrar) o "ssh-agent" (SSH_AUTH_SOCK)
+backend = "file"
+# Handle persistente de la clave en el TPM (ver `cliff-watch tpm init`)
+tpm_handle = 0x81000C1F
+# Clave SSH: privada para "ssh", pública (.pub) para elegir identidad en "ssh-agent"
+# ssh_key = "~/.ssh/id_ed25519"
+
+[attestation]
+# Dónde se guarda la atestación: "trailers" (mensaje), "notes" (refs/notes/cliff-watch) o "both"
+backend = "trailers"
+"#;
+                    let path = Path::new("cliff-watch.toml");
+                    if path.exists() {
+                        eprintln!("⚠️ cliff-watch.toml already exists!");
                         process::exit(1);
                     }
                     if let Err(e) = std::fs::write(path, config_content) {
@@ -674,6 +2184,12 @@ ignore_extensions = ["log", "lock", "tmp", "bak"]
                             println!("   Difficulty:  {}", cfg.governance.difficulty);
                             println!("   Min Entropy: {}", cfg.governance.min_entropy);
                             println!("   Watch Root:  {}", cfg.monitoring.watch_root);
+                            println!("   Identity:    {} (TPM handle 0x{:08x})", cfg.identity.backend, cfg.identity.tpm_handle);
+                            if let Err(e) = cfg.attestation.backend() {
+                                eprintln!("❌ Configuration invalid: {}", e);
+                                process::exit(1);
+                            }
+                            println!("   Attestation: {}", cfg.attestation.backend);
                         }
                         Err(e) => {
                             eprintln!("❌ Configuration invalid: {}", e);
@@ -686,6 +2202,698 @@ ignore_extensions = ["log", "lock", "tmp", "bak"]
     }
 }
 
+/// Verifica cada commit de `base..head` (o solo `spec`) y las cadenas de tickets de sus firmantes
+fn verify_range(spec: &str, format: &str, first_parent: bool, merges: &str, branch: Option<String>) {
+    use cliff_watch_core::ci::{verify_commits, worst_status, CommitOutcome, CommitStatus};
+    use cliff_watch_core::policy::PolicyScope;
+    use std::collections::BTreeMap;
+
+    #[derive(serde::Serialize)]
+    struct ChainIssueReport {
+        kind: &'static str,
+        key_id: String,
+        commit: String,
+        detail: String,
+    }
+
+    #[derive(serde::Serialize, Default)]
+    struct RangeSummary {
+        total: usize,
+        verified: usize,
+        exempt: usize,
+        below_threshold: usize,
+        unsigned: usize,
+        bad_signature: usize,
+        /// Commits verificados por alias
+        signers: BTreeMap<String, usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        average_score: Option<f64>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct RangeReport {
+        status: &'static str,
+        range: String,
+        first_parent: bool,
+        merges: String,
+        exit_code: i32,
+        summary: RangeSummary,
+        commits: Vec<CommitOutcome>,
+        chain_issues: Vec<ChainIssueReport>,
+    }
+
+    if !["text", "json", "sarif", "junit"].contains(&format) {
+        eprintln!("❌ Invalid --format '{}' (expected text, json, sarif or junit)", format);
+        process::exit(1);
+    }
+    if merges != "verify" && merges != "exempt" {
+        eprintln!("❌ Invalid --merges '{}' (expected verify or exempt)", merges);
+        process::exit(1);
+    }
+    let repo = match open_repository(Path::new(".")) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("❌ Error opening repository: {}", e);
+            process::exit(1);
+        }
+    };
+    // Un commit suelto (p. ej. `verify HEAD --format sarif`) es un rango de un elemento
+    let walk = if spec.contains("..") {
+        repo.revwalk().and_then(|mut w| {
+            w.push_range(spec)?;
+            if first_parent {
+                w.simplify_first_parent()?;
+            }
+            Ok(w.flatten().collect::<Vec<_>>())
+        })
+    } else {
+        repo.revparse_single(spec).and_then(|o| o.peel_to_commit()).map(|c| vec![c.id()])
+    };
+    let mut oids = match walk {
+        Ok(oids) => oids,
+        Err(e) => {
+            eprintln!("❌ Invalid range '{}': {}", spec, e);
+            process::exit(1);
+        }
+    };
+    oids.reverse();
+
+    // Reglas de rama de policy.toml: `--branch`, la rama `head` del rango o la actual
+    let head_branch = spec
+        .split_once("..")
+        .map(|(_, head)| head.trim_start_matches('.'))
+        .filter(|head| repo.find_reference(&format!("refs/heads/{}", head)).is_ok())
+        .map(|head| format!("refs/heads/{}", head));
+    let scope = PolicyScope::local(&repo, branch.or(head_branch));
+    let (commits, issues) = verify_commits(&repo, &oids, merges == "exempt", &scope);
+    let worst = worst_status(&commits, &issues);
+
+    let mut summary = RangeSummary { total: commits.len(), ..Default::default() };
+    for c in &commits {
+        match c.status {
+            CommitStatus::Verified => summary.verified += 1,
+            CommitStatus::Exempt => summary.exempt += 1,
+            CommitStatus::BelowThreshold => summary.below_threshold += 1,
+            CommitStatus::Unsigned => summary.unsigned += 1,
+            CommitStatus::BadSignature => summary.bad_signature += 1,
+        }
+        if let Some(signer) = &c.signer {
+            *summary.signers.entry(signer.clone()).or_default() += 1;
+        }
+    }
+    let scores: Vec<f64> = commits.iter().filter_map(|c| c.score).collect();
+    summary.average_score = (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64);
+
+    if format == "sarif" {
+        println!("{}", serde_json::to_string_pretty(&cliff_watch_core::ci::to_sarif(&commits, &issues)).unwrap());
+    } else if format == "junit" {
+        print!("{}", cliff_watch_core::ci::to_junit(&format!("cliff-watch verify {}", spec), &commits, &issues));
+    } else if format == "json" {
+        let report = RangeReport {
+            status: if worst.exit_code() == 0 { "verified" } else { "failed" },
+            range: spec.to_string(),
+            first_parent,
+            merges: merges.to_string(),
+            exit_code: worst.exit_code(),
+            summary,
+            commits,
+            chain_issues: issues
+                .iter()
+                .map(|i| ChainIssueReport { kind: i.kind.code(), key_id: i.key_id.clone(), commit: i.commit.to_string(), detail: i.detail.clone() })
+                .collect(),
+        };
+        println!("{}", serde_json::to_string(&report).unwrap());
+    } else {
+        println!("   {:<9} {:<16} {:<14} {:>6}  SUBJECT", "COMMIT", "STATUS", "SIGNER", "SCORE");
+        for c in &commits {
+            let signer = match (&c.signer, c.seq) {
+                (Some(signer), Some(seq)) => format!("{} #{}", signer, seq),
+                (Some(signer), None) => signer.clone(),
+                (None, _) => "-".to_string(),
+            };
+            let score = c.score.map(|s| format!("{:.2}", s)).unwrap_or_else(|| "-".to_string());
+            let reason = c.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+            let merge = if c.merge { "[merge] " } else { "" };
+            println!(
+                "{} {:<9} {:<16} {:<14} {:>6}  {}{}{}",
+                c.status.icon(),
+                &c.commit[..8],
+                c.status.label(),
+                signer,
+                score,
+                merge,
+                c.summary,
+                reason
+            );
+            if let Some(gap) = &c.gap {
+                println!("   {:<9} 📝 hueco documentado: {}", "", gap);
+            }
+            for rule in &c.rules {
+                println!("   {:<9} 📋 {}", "", rule.explain());
+            }
+            if c.status.is_failure() && !c.is_violation() {
+                println!("   {:<9} ➖ tolerado por policy {}", "", c.mode.as_str());
+            }
+        }
+
+        for issue in &issues {
+            let icon = if issue.kind.is_fatal() { "❌" } else { "⚠️ " };
+            println!("{} Cadena kid {}: {} en {} ({})", icon, issue.key_id, issue.kind.code(), &issue.commit.to_string()[..8], issue.detail);
+        }
+        let chained = commits.iter().filter(|c| c.seq.is_some()).count();
+        if issues.is_empty() && chained > 0 {
+            println!("🔗 Cadenas de tickets íntegras ({} tickets encadenados)", chained);
+        }
+
+        println!(
+            "\n📊 {} commits{}: {} verified, {} below threshold, {} unsigned, {} bad signature, {} exempt",
+            summary.total,
+            if first_parent { " (first parent)" } else { "" },
+            summary.verified,
+            summary.below_threshold,
+            summary.unsigned,
+            summary.bad_signature,
+            summary.exempt
+        );
+        if !summary.signers.is_empty() {
+            let signers: Vec<String> = summary.signers.iter().map(|(alias, n)| format!("{} ({})", alias, n)).collect();
+            println!("   Firmantes: {}", signers.join(", "));
+        }
+        if let Some(average) = summary.average_score {
+            println!("   Score medio: {:.2}", average);
+        }
+    }
+
+    process::exit(worst.exit_code());
+}
+
+/// Commits sin ticket que se van a publicar: pregunta por qué, para registrarlo en su nota.
+///
+/// El hook no tiene stdin interactivo, así que se pregunta en la terminal de
+/// control; sin terminal (CI, IDE) solo se indica el comando.
+fn ask_gap_reason(gaps: &[(String, String)]) -> Option<String> {
+    println!("\n📝 {} commits sin ticket (¿--no-verify o daemon parado?):", gaps.len());
+    for (commit, summary) in gaps {
+        println!("   {} {}", &commit[..8], summary);
+    }
+    let reason = prompt_tty("   Motivo para documentarlos en la nota cliff-watch (vacío para omitir): ").filter(|r| !r.is_empty());
+    if reason.is_none() {
+        let shorts: Vec<&str> = gaps.iter().map(|(commit, _)| &commit[..8]).collect();
+        println!("💡 Para documentarlos: cliff-watch notes gap {} --reason \"...\"", shorts.join(" "));
+    }
+    reason
+}
+
+/// Lee una línea de la terminal de control; None si no hay terminal
+fn prompt_tty(prompt: &str) -> Option<String> {
+    use std::io::{BufRead, Write};
+
+    let tty = std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    let mut out = tty.try_clone().ok()?;
+    write!(out, "{}", prompt).ok()?;
+    out.flush().ok()?;
+    let mut line = String::new();
+    std::io::BufReader::new(tty).read_line(&mut line).ok()?;
+    Some(line.trim().to_string())
+}
+
+/// Exigencia de policy.toml y reglas que aplicaron a un commit
+fn print_policy(policy: &cliff_watch_core::policy::PolicyDecision) {
+    if policy.hits.is_empty() {
+        return;
+    }
+    println!("   📋 Policy: {} ({})", policy.mode.as_str(), policy.mode_source);
+    for hit in &policy.hits {
+        println!("      {}", hit.explain());
+    }
+}
+
+/// Muestra el veredicto de una ref (hooks de push). Devuelve si la política la acepta
+fn print_ref_verdict(verdict: &cliff_watch_core::server_hook::RefVerdict, rejected: &str) -> bool {
+    use cliff_watch_core::policy::Enforcement;
+
+    let (update, policy) = (&verdict.update, &verdict.policy);
+    if update.is_delete() {
+        println!("🗑️  {}: borrado", update.refname);
+        return true;
+    }
+    if policy.enforcement == Enforcement::Off {
+        println!("➖ {}: sin verificar (policy off)", update.refname);
+        return true;
+    }
+    println!(
+        "🛡️  cliff-watch · {} · policy {} ({}): {} commits nuevos",
+        update.refname,
+        policy.enforcement.as_str(),
+        policy.pattern.as_deref().unwrap_or("por defecto"),
+        verdict.outcomes.len()
+    );
+    for c in &verdict.outcomes {
+        let signer = c.signer.as_deref().unwrap_or("-");
+        let reason = c.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+        println!("   {} {:<9} {:<16} {:<12} {}{}", c.status.icon(), &c.commit[..8], c.status.label(), signer, c.summary, reason);
+        if let Some(gap) = &c.gap {
+            println!("      📝 hueco documentado: {}", gap);
+        }
+        for rule in &c.rules {
+            println!("      📋 {}", rule.explain());
+        }
+        if c.mode != policy.enforcement {
+            println!("      policy {} para este commit", c.mode.as_str());
+        }
+        if c.is_violation() && !c.files.is_empty() {
+            println!("      archivos: {}", c.files.join(", "));
+        }
+    }
+    for issue in &verdict.issues {
+        let icon = if issue.kind.is_fatal() { "❌" } else { "⚠️ " };
+        println!("   {} Cadena kid {}: {} en {} ({})", icon, issue.key_id, issue.kind.code(), &issue.commit.to_string()[..8], issue.detail);
+    }
+
+    if verdict.accepted() {
+        let failing = verdict.outcomes.iter().filter(|c| c.status.is_failure()).count();
+        if failing > 0 {
+            println!("⚠️  {}: aceptada con {} avisos (policy {})", update.refname, failing, policy.enforcement.as_str());
+        } else {
+            println!("✅ {}: aceptada", update.refname);
+        }
+        return true;
+    }
+    eprintln!(
+        "⛔ {}: {} por la policy {} ({} commits, {} anomalías de cadena)",
+        update.refname,
+        rejected,
+        policy.enforcement.as_str(),
+        verdict.violations().len(),
+        verdict.chain_violations().len()
+    );
+    false
+}
+
+/// Verifica las refs recibidas y sale con 1 (push rechazado) si alguna incumple su política
+fn run_server_hook(updates: &[cliff_watch_core::server_hook::RefUpdate]) {
+    use cliff_watch_core::ci::CommitStatus;
+    use cliff_watch_core::server_hook::{check_update, open_receiving_repository};
+
+    let repo = match open_receiving_repository() {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("❌ cliff-watch: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let mut rejected = Vec::new();
+    let mut worst = CommitStatus::Verified;
+    for update in updates {
+        let verdict = match check_update(&repo, update) {
+            Ok(verdict) => verdict,
+            Err(e) => {
+                eprintln!("❌ {}: {}", update.refname, e);
+                rejected.push(update.refname.clone());
+                continue;
+            }
+        };
+        if !print_ref_verdict(&verdict, "rechazada") {
+            rejected.push(update.refname.clone());
+            worst = worst.max(verdict.violations().iter().map(|c| c.status).max().unwrap_or(CommitStatus::BadSignature));
+        }
+    }
+
+    if !rejected.is_empty() {
+        eprintln!("\n❌ Push rechazado por cliff-watch: {}", rejected.join(", "));
+        match worst {
+            CommitStatus::BelowThreshold => {
+                eprintln!("💡 Hay tickets emitidos en Audit Mode o sin las firmas que exige el threshold de trust.toml.");
+            }
+            CommitStatus::Unsigned => {
+                eprintln!("💡 Los commits hechos con --no-verify o con el daemon parado no llevan ticket:");
+                eprintln!("   rehazlos con el daemon activo (git commit --amend, git rebase -i) y vuelve a hacer push.");
+            }
+            _ => eprintln!("💡 Comprueba los detalles con: cliff-watch verify <base>..<head>"),
+        }
+        process::exit(1);
+    }
+}
+
+/// Lanza el daemon en background, entregándole la passphrase por stdin si se indica
+fn spawn_daemon(bin: &str, passphrase: Option<&str>) -> std::io::Result<std::process::Child> {
+    use std::io::Write;
+
+    let mut command = Command::new(bin);
+    command.stdout(Stdio::null()).stderr(Stdio::null());
+    match passphrase {
+        Some(_) => command
+            .stdin(Stdio::piped())
+            .env(cliff_watch_core::crypto::keyfile::PASSPHRASE_FD_ENV, "0"),
+        None => command.stdin(Stdio::null()),
+    };
+
+    let mut child = command.spawn()?;
+    if let (Some(passphrase), Some(mut stdin)) = (passphrase, child.stdin.take()) {
+        writeln!(stdin, "{}", passphrase)?;
+    }
+    Ok(child)
+}
+
+/// Acepta una clave en hex o una línea OpenSSH (`ssh-ed25519 AAAA…`) y devuelve hex
+fn public_key_hex_or_exit(key: &str) -> String {
+    use cliff_watch_core::crypto::ssh;
+
+    if !ssh::looks_like_public_key(key) {
+        return key.to_string();
+    }
+    match ssh::parse_public_key(key) {
+        Ok(k) => k.to_hex(),
+        Err(e) => {
+            eprintln!("❌ Invalid SSH public key: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn daemon_key_path_or_exit() -> std::path::PathBuf {
+    match cliff_watch_core::crypto::daemon_key_path() {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// La clave del daemon está cifrada y el entorno no trae la passphrase
+fn daemon_key_needs_prompt() -> bool {
+    let encrypted = cliff_watch_core::crypto::daemon_key_path()
+        .map(|p| cliff_watch_core::crypto::identity_is_encrypted(&p))
+        .unwrap_or(false);
+    encrypted && !matches!(cliff_watch_core::crypto::keyfile::passphrase_from_env(), Ok(Some(_)))
+}
+
+/// Pide una passphrase sin eco (con confirmación opcional); vacía no se acepta
+fn prompt_passphrase(prompt: &str, confirm: bool) -> String {
+    let term = console::Term::stderr();
+    let read = |label: &str| {
+        eprint!("{}: ", label);
+        term.read_secure_line().unwrap_or_else(|e| {
+            eprintln!("❌ Cannot read passphrase: {}", e);
+            process::exit(1);
+        })
+    };
+
+    let passphrase = read(prompt);
+    if passphrase.is_empty() {
+        eprintln!("❌ Empty passphrase");
+        process::exit(1);
+    }
+    if confirm && read("Repeat passphrase") != passphrase {
+        eprintln!("❌ Passphrases do not match");
+        process::exit(1);
+    }
+    passphrase
+}
+
+/// Carga la clave del daemon, pidiendo la passphrase si está cifrada y no viene del entorno
+fn unlock_daemon_key() -> cliff_watch_core::crypto::SigningKey {
+    let key_path = daemon_key_path_or_exit();
+    if !key_path.exists() {
+        eprintln!("❌ No daemon key at {}", key_path.display());
+        process::exit(1);
+    }
+
+    let passphrase = match cliff_watch_core::crypto::keyfile::passphrase_from_env() {
+        Ok(Some(p)) => Some(p.to_string()),
+        _ if cliff_watch_core::crypto::identity_is_encrypted(&key_path) => Some(prompt_passphrase("Daemon key passphrase", false)),
+        _ => None,
+    };
+
+    match cliff_watch_core::crypto::read_identity(&key_path, passphrase.as_deref()) {
+        Ok(k) => k,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Acepta handles en hex (`0x81000C1F`) o decimal
+fn parse_tpm_handle(handle: &str) -> Option<u32> {
+    match handle.strip_prefix("0x").or_else(|| handle.strip_prefix("0X")) {
+        Some(h) => u32::from_str_radix(h, 16).ok(),
+        None => handle.parse().ok(),
+    }
+}
+
+/// Resuelve un reto de prueba de trabajo mostrando el avance.
+///
+/// La barra se dibuja en la terminal de control para que sea visible aunque el
+/// hook pre-commit redirija la salida del CLI.
+fn solve_pow_with_progress(challenge: &cliff_watch_core::pow::PowChallenge) -> Result<u64, String> {
+    let target = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()
+        .and_then(|tty| tty.try_clone().ok().map(|out| (tty, out)))
+        .map(|(input, output)| indicatif::ProgressDrawTarget::term(console::Term::read_write_pair(input, output), 10))
+        .unwrap_or_else(indicatif::ProgressDrawTarget::stderr);
+
+    // El número de intentos es geométrico: la barra marca el valor esperado
+    let pb = ProgressBar::with_draw_target(Some(challenge.expected_attempts()), target);
+    pb.set_style(
+        ProgressStyle::with_template("⛏️  [{elapsed_precise}] {bar:40.cyan/blue} {human_pos}/{human_len} hashes {msg}")
+            .unwrap()
+            .progress_chars("##-"),
+    );
+    pb.set_message(format!("({} bits)", challenge.difficulty));
+
+    let nonce = cliff_watch_core::pow::solve(challenge, |attempts| {
+        if attempts > pb.length().unwrap_or(0) {
+            pb.set_length(attempts.saturating_mul(2));
+        }
+        pb.set_position(attempts);
+    })?;
+    pb.finish_with_message(format!("({} bits) ✅ nonce {}", challenge.difficulty, nonce));
+    Ok(nonce)
+}
+
+/// Programa de firma de Git (`gpg.program` / `gpg.ssh.program`).
+///
+/// Solo firma un commit tras obtener su ticket del daemon, y lo incrusta en la
+/// firma. Verificaciones y objetos que no son commits pasan al firmante real sin
+/// tocar. Con OpenPGP, stdout es la firma que lee Git: los mensajes van a stderr.
+async fn run_sign_shim(args: Vec<String>) -> ! {
+    use cliff_watch_core::policy::{decide_pending, PolicyScope};
+    use cliff_watch_core::signing::{embed_attestation, real_program, CommitBuffer, SignatureFormat};
+    use std::io::{Read, Write};
+
+    let format = SignatureFormat::from_args(&args);
+    let repo = open_repository(Path::new(".")).ok();
+    let program = real_program(repo.as_ref(), format);
+    let exit_code = |status: std::io::Result<process::ExitStatus>| match status {
+        Ok(status) => status.code().unwrap_or(1),
+        Err(e) => {
+            eprintln!("❌ cliff-watch: cannot run {}: {}", program, e);
+            1
+        }
+    };
+
+    if !format.is_sign_request(&args) {
+        process::exit(exit_code(Command::new(&program).args(&args).status()));
+    }
+
+    // gpg recibe el objeto por stdin; ssh-keygen, como último argumento
+    let buffer_file = match format {
+        SignatureFormat::Ssh => args.last().cloned(),
+        SignatureFormat::OpenPgp => None,
+    };
+    let buffer = match &buffer_file {
+        Some(file) => std::fs::read(file),
+        None => {
+            let mut buffer = Vec::new();
+            std::io::stdin().read_to_end(&mut buffer).map(|_| buffer)
+        }
+    };
+    let buffer = match buffer {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("❌ cliff-watch: cannot read the object to sign: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let lines = match (CommitBuffer::parse(&String::from_utf8_lossy(&buffer)), &repo) {
+        (None, _) => Vec::new(),
+        (Some(commit), Some(repo)) if commit.has_ticket(repo) => Vec::new(),
+        (Some(commit), Some(repo)) => {
+            let binding = commit.binding;
+            let mut policy = pending_policy(decide_pending(repo, &binding, &PolicyScope::local(repo, None)));
+            let ticket = match cliff_watch_core::git::get_tree_diff(repo, &binding.tree, binding.parent.as_deref()) {
+                Ok(diff) => sign_shim_ticket(cliff_watch_core::complexity::estimate_entropic_cost(&diff, None), binding, &mut policy).await,
+                Err(e) => Err(e),
+            };
+            match ticket {
+                Ok(lines) => lines,
+                Err(e) if tolerates_missing_ticket(&policy) => {
+                    eprintln!("⚠️  cliff-watch: commit signed without ticket: {}", e);
+                    Vec::new()
+                }
+                Err(e) => {
+                    eprintln!("❌ cliff-watch: commit not signed: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        (Some(_), None) => {
+            eprintln!("❌ cliff-watch: commit not signed: not inside a repository");
+            process::exit(1);
+        }
+    };
+
+    let signature = match &buffer_file {
+        None => {
+            let child = Command::new(&program).args(&args).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn();
+            let output = child.and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(&buffer)?;
+                }
+                child.wait_with_output()
+            });
+            match output {
+                Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).to_string(),
+                other => process::exit(exit_code(other.map(|o| o.status))),
+            }
+        }
+        Some(file) => {
+            let code = exit_code(Command::new(&program).args(&args).status());
+            if code != 0 {
+                process::exit(code);
+            }
+            std::fs::read_to_string(format!("{}.sig", file)).unwrap_or_default()
+        }
+    };
+
+    let signature = match embed_attestation(&signature, &lines) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("❌ cliff-watch: {}", e);
+            process::exit(1);
+        }
+    };
+    match &buffer_file {
+        None => {
+            print!("{}", signature);
+            let _ = std::io::stdout().flush();
+        }
+        Some(file) => {
+            if let Err(e) = std::fs::write(format!("{}.sig", file), signature) {
+                eprintln!("❌ cliff-watch: cannot write signature: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    process::exit(0);
+}
+
+/// Ticket del commit que el shim va a firmar, como líneas `Clave: valor`
+async fn sign_shim_ticket(
+    cost: f64,
+    binding: cliff_watch_core::ticket::TicketBinding,
+    policy: &mut cliff_watch_core::policy::PolicyDecision,
+) -> Result<Vec<(&'static str, String)>, String> {
+    use cliff_watch_core::protocol::Response;
+    use cliff_watch_core::ticket::{SignedTicket, PROOF_TRAILER, TICKET_TRAILER};
+
+    match request_ticket(cost, binding, policy.required_percent()).await {
+        Ok(Response::Ticket { success: true, message, signature: Some(signature), payload: Some(payload), proof, log_head }) => {
+            eprintln!("✅ Thermodynamic check passed: {}", message);
+            enforce_pending_policy(policy, proof.as_deref())?;
+            let ticket = SignedTicket::from_parts(&payload, signature).map_err(|e| format!("Daemon returned a malformed ticket: {}", e))?;
+            let mut lines = vec![(TICKET_TRAILER, ticket.to_trailer_value())];
+            lines.extend(proof.map(|p| (PROOF_TRAILER, p)));
+            lines.extend(log_head.map(|h| (cliff_watch_core::translog::LOG_TRAILER, h)));
+            Ok(lines)
+        }
+        Ok(Response::Ticket { message, .. }) => Err(message),
+        Ok(Response::Error(e)) => Err(format!("Daemon error: {}", e)),
+        Err(e) => Err(format!("Daemon communication error: {}", e)),
+        _ => Err("Unexpected response from daemon".to_string()),
+    }
+}
+
+/// Política del commit en curso (`policy::decide_pending`): explica las reglas que aplican
+fn pending_policy(
+    decision: Result<cliff_watch_core::policy::PolicyDecision, String>,
+) -> cliff_watch_core::policy::PolicyDecision {
+    match decision {
+        Ok(policy) => {
+            for hit in &policy.hits {
+                eprintln!("📋 Policy {}", hit.explain());
+            }
+            policy
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// ¿Deja la política hacer el commit sin ticket? Lo avisa si es así
+fn tolerates_missing_ticket(policy: &cliff_watch_core::policy::PolicyDecision) -> bool {
+    let tolerated = !policy.mode.rejects(cliff_watch_core::ci::CommitStatus::Unsigned);
+    if tolerated {
+        eprintln!("⚠️  Policy {} ({}): commit allowed without ticket", policy.mode.as_str(), policy.mode_source);
+    }
+    tolerated
+}
+
+/// Contrasta el ticket recién emitido con los requisitos de la política; Err si bloquea el commit.
+///
+/// La co-firma solo puede añadirse con el commit hecho: aquí solo se recuerda.
+fn enforce_pending_policy(policy: &mut cliff_watch_core::policy::PolicyDecision, proof: Option<&str>) -> Result<(), String> {
+    use cliff_watch_core::crypto::zkp::HumanityProof;
+
+    for role in policy.cosign_roles() {
+        eprintln!("💡 Policy: this commit needs a {} co-signature (cliff-watch cosign <commit>)", role);
+    }
+    let proven = proof.and_then(|p| HumanityProof::parse_trailer_value(p).ok()).map(|p| p.threshold_percent);
+    if policy.check(proven, None) {
+        return Ok(());
+    }
+    let unmet = format!("Policy {} ({}): {}", policy.mode.as_str(), policy.mode_source, policy.unmet().join("; "));
+    if policy.mode.rejects(cliff_watch_core::ci::CommitStatus::BelowThreshold) {
+        return Err(unmet);
+    }
+    eprintln!("⚠️  {} (warning only)", unmet);
+    Ok(())
+}
+
+/// Pide un ticket al daemon; si la batería no alcanza y el daemon ofrece
+/// una prueba de trabajo, la resuelve y la canjea. `threshold`: umbral (%) que
+/// policy.toml exige a la prueba de rango
+async fn request_ticket(
+    cost: f64,
+    binding: cliff_watch_core::ticket::TicketBinding,
+    threshold: Option<u64>,
+) -> anyhow::Result<cliff_watch_core::protocol::Response> {
+    match query_daemon(cliff_watch_core::protocol::Request::GetTicket { cost, binding: binding.clone(), threshold }).await {
+        // Batería insuficiente: el daemon ofrece pagar el déficit con prueba de trabajo
+        Ok(cliff_watch_core::protocol::Response::PowChallenge { challenge, message }) => {
+            eprintln!("⛏️  {}", message);
+            let nonce = match solve_pow_with_progress(&challenge) {
+                Ok(n) => n,
+                Err(e) => {
+                    eprintln!("❌ Proof of work failed: {}", e);
+                    process::exit(1);
+                }
+            };
+            query_daemon(cliff_watch_core::protocol::Request::RedeemPow { cost, binding, salt: challenge.salt, nonce, threshold }).await
+        }
+        other => other,
+    }
+}
+
 async fn query_daemon(request: cliff_watch_core::protocol::Request) -> anyhow::Result<cliff_watch_core::protocol::Response> {
     use tokio::net::UnixStream;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -695,11 +2903,14 @@ async fn query_daemon(request: cliff_watch_core::protocol::Request) -> anyhow::R
     
     let request_json = serde_json::to_vec(&request)?;
     stream.write_all(&request_json).await?;
+    // Fin de la petición: el daemon lee hasta EOF
+    stream.shutdown().await?;
     
-    let mut buffer = vec![0; 1024];
-    let n = stream.read(&mut buffer).await?;
+    // El daemon cierra la conexión tras responder; las pruebas ZKP superan 1 KiB
+    let mut buffer = Vec::new();
+    stream.read_to_end(&mut buffer).await?;
     
-    let response: cliff_watch_core::protocol::Response = serde_json::from_slice(&buffer[..n])?;
+    let response: cliff_watch_core::protocol::Response = serde_json::from_slice(&buffer)?;
     Ok(response)
 }
 async fn run_setup(no_confirm: bool) {
@@ -0,0 +1,85 @@
+//! Hook de servidor de extremo a extremo
+//!
+//! `server-hook install` en un repositorio bare y pushes reales por `file://`:
+//! un commit sin ticket se rechaza y uno con ticket firmado por trust.toml entra.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use cliff_watch_core::crypto::zkp::{HumanityProof, HUMANITY_THRESHOLD_PERCENT};
+use cliff_watch_core::crypto::{generate_keypair, key_id, DaemonIdentity};
+use cliff_watch_core::testing::TempDir;
+use cliff_watch_core::ticket::{SignedTicket, TicketBinding, TicketMode, TicketPayload, PROOF_TRAILER, TICKET_TRAILER};
+use cliff_watch_core::trust::TrustConfig;
+
+fn run(program: &str, dir: &Path, home: &Path, args: &[&str]) -> Output {
+    Command::new(program)
+        .current_dir(dir)
+        .args(args)
+        // Sin la configuración global del usuario (hooks, programas de firma)
+        .env("HOME", home)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_AUTHOR_NAME", "Dev")
+        .env("GIT_AUTHOR_EMAIL", "dev@example.com")
+        .env("GIT_COMMITTER_NAME", "Dev")
+        .env("GIT_COMMITTER_EMAIL", "dev@example.com")
+        .output()
+        .unwrap()
+}
+
+fn git(dir: &Path, home: &Path, args: &[&str]) -> String {
+    let out = run("git", dir, home, args);
+    assert!(out.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&out.stderr));
+    String::from_utf8_lossy(&out.stdout).trim().to_string()
+}
+
+#[test]
+fn server_hook_rejects_unsigned_pushes_and_accepts_ticketed_ones() {
+    let root = TempDir::new("cliff_watch_server_push");
+    let (work, server) = (root.join("work"), root.join("server.git"));
+    std::fs::create_dir_all(&work).unwrap();
+    let url = format!("file://{}", server.display());
+
+    let (signing_key, verifying_key) = generate_keypair();
+    let identity = DaemonIdentity::Software(signing_key);
+    let mut trust = TrustConfig::default();
+    trust.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+
+    // Base con trust.toml, publicada antes de instalar el hook
+    git(&work, &root, &["init", "-q", "-b", "main"]);
+    std::fs::write(work.join("trust.toml"), trust.to_toml().unwrap()).unwrap();
+    git(&work, &root, &["add", "-A"]);
+    git(&work, &root, &["commit", "-qm", "chore: trust"]);
+    git(&root, &root, &["init", "-q", "--bare", "server.git"]);
+    git(&work, &root, &["push", "-q", &url, "HEAD:refs/heads/main"]);
+
+    let install = run(env!("CARGO_BIN_EXE_cliff-watch-cli"), &server, &root, &["server-hook", "install"]);
+    assert!(install.status.success(), "{}", String::from_utf8_lossy(&install.stderr));
+    assert!(server.join("hooks").join("pre-receive").exists());
+
+    // Sin ticket (p. ej. `git commit --no-verify`)
+    std::fs::write(work.join("a.rs"), "fn a() {}\n").unwrap();
+    git(&work, &root, &["add", "-A"]);
+    git(&work, &root, &["commit", "-qm", "feat: unsigned"]);
+    let unsigned = run("git", &work, &root, &["push", &url, "HEAD:refs/heads/main"]);
+    assert!(!unsigned.status.success());
+    assert!(String::from_utf8_lossy(&unsigned.stderr).contains("cliff-watch"));
+    git(&work, &root, &["reset", "-q", "--hard", "HEAD~1"]);
+
+    // Ticket ligado al árbol, padre y autor del commit
+    std::fs::write(work.join("b.rs"), "fn b() {}\n").unwrap();
+    git(&work, &root, &["add", "-A"]);
+    let binding = TicketBinding {
+        tree: git(&work, &root, &["write-tree"]),
+        parent: Some(git(&work, &root, &["rev-parse", "HEAD"])),
+        author: "dev@example.com".to_string(),
+    };
+    let proof = HumanityProof::generate(82, HUMANITY_THRESHOLD_PERCENT, binding.tree.as_bytes()).unwrap().to_trailer_value();
+    let payload = TicketPayload::new(TicketMode::Valid, 5.0, binding, key_id(&verifying_key)).with_proof(&proof);
+    let ticket = SignedTicket::sign(payload, &identity).unwrap().to_trailer_value();
+    let message = format!("feat: ticketed\n\n{}: {}\n{}: {}\n", TICKET_TRAILER, ticket, PROOF_TRAILER, proof);
+    git(&work, &root, &["commit", "-qm", &message]);
+    let ticketed = run("git", &work, &root, &["push", &url, "HEAD:refs/heads/main"]);
+    assert!(ticketed.status.success(), "{}", String::from_utf8_lossy(&ticketed.stderr));
+    assert_eq!(git(&server, &root, &["rev-parse", "refs/heads/main"]), git(&work, &root, &["rev-parse", "HEAD"]));
+}
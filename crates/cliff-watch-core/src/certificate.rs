@@ -0,0 +1,253 @@
+//! Certificado de rama: una sola prueba de rango agregada para `base..head`
+//!
+//! Cada commit del rango lleva un compromiso de Pedersen a `score - umbral`
+//! dentro de su `Cliff-Watch-Proof`, atado al ticket firmado. El daemon guarda
+//! la apertura de cada compromiso que emite (`<data_dir>/openings/`), y con
+//! ellas `cliff-watch certify` produce una única prueba Bulletproofs agregada
+//! de que todos los commits alcanzaron su umbral:
+//!
+//! ```text
+//! v=1;base=<oid>;head=<oid>;commits=<n>;proof=<hex>
+//! ```
+//!
+//! El verificador toma los compromisos del propio historial (tras autenticar
+//! cada ticket), así que el certificado no puede hablar de otros commits.
+
+use std::path::{Path, PathBuf};
+
+use bulletproofs::RangeProof;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use git2::{Oid, Repository};
+use merlin::Transcript;
+
+use crate::crypto::zkp::{prove_aggregate, verify_aggregate, ProofOpening};
+use crate::trust::TrustConfig;
+
+/// Versión del formato del certificado
+pub const CERTIFICATE_VERSION: u32 = 1;
+
+/// Aperturas de los compromisos emitidos por este daemon.
+///
+/// Revelan el score de cada commit: no se publican ni se sincronizan.
+#[derive(Debug)]
+pub struct OpeningStore {
+    dir: PathBuf,
+}
+
+impl OpeningStore {
+    /// `openings/` dentro del directorio de datos (ver `config::data_dir`)
+    pub fn default_dir() -> Result<PathBuf, String> {
+        Ok(crate::config::data_dir()?.join("openings"))
+    }
+
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700));
+        }
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    pub fn save(&self, opening: &ProofOpening) -> Result<(), String> {
+        let path = self.dir.join(hex::encode(opening.commitment().as_bytes()));
+        std::fs::write(&path, opening.encode()).map_err(|e| format!("Failed to store proof opening: {}", e))
+    }
+
+    pub fn load(&self, commitment: &CompressedRistretto) -> Option<ProofOpening> {
+        let content = std::fs::read_to_string(self.dir.join(hex::encode(commitment.as_bytes()))).ok()?;
+        ProofOpening::decode(&content).ok().filter(|o| o.commitment() == *commitment)
+    }
+}
+
+/// Commit del rango con el compromiso de su prueba de rango
+#[derive(Debug, Clone)]
+pub struct CertifiedCommit {
+    pub commit: Oid,
+    pub commitment: CompressedRistretto,
+    pub threshold_percent: u64,
+}
+
+/// Resuelve `base..head` a sus OIDs
+pub fn resolve_range(repo: &Repository, range: &str) -> Result<(Oid, Oid), String> {
+    let (base, head) = range.split_once("..").ok_or_else(|| format!("Expected a range base..head, got '{}'", range))?;
+    let resolve = |rev: &str| {
+        repo.revparse_single(if rev.is_empty() { "HEAD" } else { rev })
+            .and_then(|o| o.peel_to_commit())
+            .map(|c| c.id())
+            .map_err(|e| format!("Unknown revision '{}': {}", rev, e))
+    };
+    Ok((resolve(base)?, resolve(head)?))
+}
+
+/// Autentica cada commit de `base..head` (del más antiguo al más nuevo) y
+/// extrae el compromiso de su prueba de rango.
+///
+/// Falla con `<oid>: <razón>` en el primer commit sin ticket válido o sin
+/// prueba (`no_humanity_proof` si el ticket no se comprometió a ninguna).
+pub fn collect_commits(repo: &Repository, base: Oid, head: Oid, trust: &TrustConfig) -> Result<Vec<CertifiedCommit>, String> {
+    let mut walk = repo.revwalk().map_err(|e| e.to_string())?;
+    walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE).map_err(|e| e.to_string())?;
+    walk.push(head).map_err(|e| e.to_string())?;
+    walk.hide(base).map_err(|e| e.to_string())?;
+
+    let mut commits = Vec::new();
+    for oid in walk {
+        let oid = oid.map_err(|e| e.to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+        let attestation = crate::notes::read_attestation(repo, &commit);
+        let fail = |reason: String| format!("{}: {}", oid, reason);
+
+        let verification = crate::ticket::verify_commit_ticket(&commit, &attestation.text, trust).map_err(fail)?;
+        let proof = crate::ticket::committed_proof(&attestation.text, &verification.ticket)
+            .map_err(fail)?
+            .ok_or_else(|| fail("no_humanity_proof".to_string()))?;
+        commits.push(CertifiedCommit { commit: oid, commitment: proof.commitment, threshold_percent: proof.threshold_percent });
+    }
+    if commits.is_empty() {
+        return Err("The range contains no commits".to_string());
+    }
+    Ok(commits)
+}
+
+/// Resumen de un certificado válido
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateSummary {
+    pub commits: usize,
+    /// Umbral más bajo probado en el rango (%)
+    pub min_threshold_percent: u64,
+}
+
+/// Prueba agregada de que todos los commits de `base..head` alcanzaron su umbral
+#[derive(Clone)]
+pub struct BranchCertificate {
+    pub base: Oid,
+    pub head: Oid,
+    pub commits: usize,
+    pub proof: RangeProof,
+}
+
+impl BranchCertificate {
+    /// Emite el certificado con las aperturas guardadas por el daemon
+    pub fn certify(repo: &Repository, range: &str, trust: &TrustConfig, openings: &OpeningStore) -> Result<Self, String> {
+        let (base, head) = resolve_range(repo, range)?;
+        let commits = collect_commits(repo, base, head, trust)?;
+
+        let openings = commits
+            .iter()
+            .map(|c| openings.load(&c.commitment).ok_or_else(|| format!("{}: opening not found on this machine", c.commit)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = prove_aggregate(&openings, &mut Self::transcript(base, head, &commits))?;
+        Ok(Self { base, head, commits: commits.len(), proof })
+    }
+
+    /// Verifica el certificado contra el historial local
+    pub fn verify(&self, repo: &Repository, trust: &TrustConfig) -> Result<CertificateSummary, String> {
+        let commits = collect_commits(repo, self.base, self.head, trust)?;
+        if commits.len() != self.commits {
+            return Err(format!("Certificate covers {} commits, the range has {}", self.commits, commits.len()));
+        }
+        let commitments: Vec<CompressedRistretto> = commits.iter().map(|c| c.commitment).collect();
+        verify_aggregate(&self.proof, &commitments, &mut Self::transcript(self.base, self.head, &commits))?;
+        Ok(CertificateSummary {
+            commits: commits.len(),
+            min_threshold_percent: commits.iter().map(|c| c.threshold_percent).min().unwrap_or(0),
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        format!(
+            "v={};base={};head={};commits={};proof={}",
+            CERTIFICATE_VERSION,
+            self.base,
+            self.head,
+            self.commits,
+            hex::encode(self.proof.to_bytes())
+        )
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, String> {
+        let mut fields = std::collections::HashMap::new();
+        for part in encoded.trim().split(';') {
+            let (k, v) = part.split_once('=').ok_or_else(|| format!("Malformed certificate field '{}'", part))?;
+            fields.insert(k, v);
+        }
+        let get = |k: &str| fields.get(k).copied().ok_or_else(|| format!("Certificate field '{}' missing", k));
+
+        if get("v")? != CERTIFICATE_VERSION.to_string() {
+            return Err("Unsupported certificate version".to_string());
+        }
+        let oid = |k: &str| get(k).and_then(|v| Oid::from_str(v).map_err(|_| format!("Invalid certificate {}", k)));
+        let proof_bytes = hex::decode(get("proof")?).map_err(|_| "Invalid certificate proof encoding".to_string())?;
+        Ok(Self {
+            base: oid("base")?,
+            head: oid("head")?,
+            commits: get("commits")?.parse().map_err(|_| "Invalid certificate commit count".to_string())?,
+            proof: RangeProof::from_bytes(&proof_bytes).map_err(|e| format!("Invalid aggregated proof: {}", e))?,
+        })
+    }
+
+    /// Transcript ligado al rango, a cada commit y a su umbral
+    fn transcript(base: Oid, head: Oid, commits: &[CertifiedCommit]) -> Transcript {
+        let mut transcript = Transcript::new(b"cliff-watch-branch-certificate");
+        transcript.append_message(b"base", base.as_bytes());
+        transcript.append_message(b"head", head.as_bytes());
+        for commit in commits {
+            transcript.append_message(b"commit", commit.commit.as_bytes());
+            transcript.append_u64(b"threshold", commit.threshold_percent);
+        }
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::zkp::{HumanityProof, HUMANITY_THRESHOLD_PERCENT};
+    use crate::crypto::DaemonIdentity;
+    use crate::ticket::{SignedTicket, TicketBinding, TicketMode, TicketPayload, PROOF_TRAILER, TICKET_TRAILER};
+
+    #[test]
+    fn certifies_a_branch_with_one_proof() {
+        let dir = crate::testing::TempDir::new("cliff_watch_cert");
+        let repo = Repository::init(dir.join("repo")).unwrap();
+        let store = OpeningStore::open(&dir.join("openings")).unwrap();
+        let (signing_key, verifying_key) = crate::crypto::generate_keypair();
+        let identity = DaemonIdentity::Software(signing_key);
+        let mut trust = TrustConfig::default();
+        trust.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+
+        // Commits con ticket y prueba, como los que deja el hook
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let mut parent: Option<Oid> = None;
+        for (i, score) in [80u64, 55, 91].iter().enumerate() {
+            let mut builder = repo.treebuilder(None).unwrap();
+            builder.insert("f", repo.blob(format!("{}", i).as_bytes()).unwrap(), 0o100644).unwrap();
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+
+            let (proof, opening) = HumanityProof::generate_with_opening(*score, HUMANITY_THRESHOLD_PERCENT, tree.id().to_string().as_bytes()).unwrap();
+            store.save(&opening).unwrap();
+            let proof_value = proof.to_trailer_value();
+            let binding = TicketBinding { tree: tree.id().to_string(), parent: parent.map(|p| p.to_string()), author: "dev@example.com".into() };
+            let payload = TicketPayload::new(TicketMode::Valid, 1.0, binding, crate::crypto::key_id(&verifying_key)).with_proof(&proof_value);
+            let ticket = SignedTicket::sign(payload, &identity).unwrap();
+
+            let message = format!("c{}\n\n{}: {}\n{}: {}\n", i, TICKET_TRAILER, ticket.to_trailer_value(), PROOF_TRAILER, proof_value);
+            let parents: Vec<git2::Commit> = parent.map(|p| repo.find_commit(p).unwrap()).into_iter().collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            parent = Some(repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &parents).unwrap());
+        }
+
+        let certificate = BranchCertificate::certify(&repo, "HEAD~2..HEAD", &trust, &store).unwrap();
+        let parsed = BranchCertificate::decode(&certificate.encode()).unwrap();
+        assert_eq!(parsed.verify(&repo, &trust).unwrap(), CertificateSummary { commits: 2, min_threshold_percent: 50 });
+
+        // El mismo certificado no vale para otro rango
+        let mut other = parsed.clone();
+        other.base = repo.revparse_single("HEAD~1").unwrap().id();
+        other.commits = 1;
+        assert!(other.verify(&repo, &trust).is_err());
+
+    }
+}
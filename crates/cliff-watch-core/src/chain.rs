@@ -0,0 +1,318 @@
+//! Cadena de tickets por identidad
+//!
+//! Cada daemon numera sus tickets (`seq`) y encadena cada uno al hash del
+//! anterior (`prev`), ambos dentro del payload firmado. Con solo el historial
+//! de git, un verificador detecta sobre un rango de commits:
+//!
+//! - `gap`: faltan números entre dos tickets de la misma clave (tickets
+//!   emitidos que no llegaron a ningún commit del rango)
+//! - `fork`: dos tickets distintos con el mismo número o el mismo predecesor
+//! - `replay`: el mismo ticket en más de un commit
+//! - `broken_link`: `prev` no es el hash del ticket con el número anterior
+//! - `reordered`: un ticket con número menor en un commit descendiente
+//! - `clock_jump`: el reloj de pared retrocedió entre dos tickets consecutivos
+//!   (ver `crate::clock`)
+//!
+//! El estado (último número, hash y hora) vive en `<data_dir>/chain/<kid>`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use git2::{Oid, Repository};
+
+use crate::clock::{backwards_jump, ClockStamp};
+use crate::ticket::{ChainLink, SignedTicket, TicketPayload};
+
+/// Hash (hex) con el que el siguiente ticket apunta a este
+pub fn ticket_hash(encoded: &str) -> String {
+    hex::encode(crate::crypto::calculate_sha256(encoded.as_bytes()))
+}
+
+/// Último eslabón emitido por una identidad, persistido entre reinicios
+#[derive(Debug)]
+pub struct ChainState {
+    path: PathBuf,
+    pub seq: u64,
+    pub last_hash: Option<String>,
+    /// Hora de pared y sello del último ticket
+    pub last_time: Option<(i64, Option<ClockStamp>)>,
+}
+
+impl ChainState {
+    /// `chain/` dentro del directorio de datos (ver `config::data_dir`)
+    pub fn default_dir() -> Result<PathBuf, String> {
+        Ok(crate::config::data_dir()?.join("chain"))
+    }
+
+    /// Carga el estado de la clave `key_id` (vacío si aún no emitió tickets)
+    pub fn open(dir: &Path, key_id: &str) -> Result<Self, String> {
+        let path = dir.join(key_id);
+        let mut state = Self { path, seq: 0, last_hash: None, last_time: None };
+        let (mut ts, mut boot, mut mono) = (None, None, None);
+        let content = match std::fs::read_to_string(&state.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(state),
+            Err(e) => return Err(format!("Failed to read chain state {}: {}", state.path.display(), e)),
+        };
+        for part in content.trim().split(';') {
+            match part.split_once('=') {
+                Some(("seq", v)) => state.seq = v.parse().map_err(|_| "Invalid chain state sequence".to_string())?,
+                Some(("hash", v)) => state.last_hash = Some(v.to_string()),
+                Some(("ts", v)) => ts = v.parse().ok(),
+                Some(("boot", v)) => boot = Some(v.to_string()),
+                Some(("mono", v)) => mono = v.parse().ok(),
+                _ => return Err(format!("Malformed chain state in {}", state.path.display())),
+            }
+        }
+        let stamp = boot.zip(mono).map(|(boot_id, mono_ms)| ClockStamp { boot_id, mono_ms });
+        state.last_time = ts.map(|ts| (ts, stamp));
+        Ok(state)
+    }
+
+    /// Retroceso de reloj del próximo ticket respecto al último emitido, si lo hay
+    pub fn clock_jump(&self, payload: &TicketPayload) -> Option<String> {
+        let (wall, stamp) = self.last_time.as_ref()?;
+        backwards_jump(*wall, stamp.as_ref(), payload.issued_at, payload.clock.as_ref())
+    }
+
+    /// Eslabón del próximo ticket
+    pub fn next_link(&self) -> ChainLink {
+        ChainLink { seq: self.seq + 1, prev: self.last_hash.clone() }
+    }
+
+    /// Avanza la cadena tras emitir `ticket` y la persiste (escritura atómica)
+    pub fn record(&mut self, ticket: &SignedTicket) -> Result<(), String> {
+        let seq = ticket.payload.chain.as_ref().map(|l| l.seq).ok_or("Ticket is not chained")?;
+        let hash = ticket_hash(&ticket.encoded);
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        let stamp = match &ticket.payload.clock {
+            Some(c) => format!(";boot={};mono={}", c.boot_id, c.mono_ms),
+            None => String::new(),
+        };
+        std::fs::write(&tmp, format!("seq={};hash={};ts={}{}\n", seq, hash, ticket.payload.issued_at, stamp))
+            .and_then(|_| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("Failed to write chain state {}: {}", self.path.display(), e))?;
+
+        self.seq = seq;
+        self.last_hash = Some(hash);
+        self.last_time = Some((ticket.payload.issued_at, ticket.payload.clock.clone()));
+        Ok(())
+    }
+}
+
+/// Ticket encadenado encontrado en un commit
+#[derive(Debug, Clone)]
+pub struct ChainEntry {
+    pub commit: Oid,
+    pub key_id: String,
+    pub seq: u64,
+    pub prev: Option<String>,
+    pub hash: String,
+    pub issued_at: i64,
+    pub clock: Option<ClockStamp>,
+}
+
+impl ChainEntry {
+    /// None si el ticket es anterior a la numeración
+    pub fn from_ticket(commit: Oid, ticket: &SignedTicket) -> Option<Self> {
+        let link = ticket.payload.chain.as_ref()?;
+        Some(Self {
+            commit,
+            key_id: ticket.payload.key_id.clone(),
+            seq: link.seq,
+            prev: link.prev.clone(),
+            hash: ticket_hash(&ticket.encoded),
+            issued_at: ticket.payload.issued_at,
+            clock: ticket.payload.clock.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainIssueKind {
+    Gap,
+    Fork,
+    Replay,
+    BrokenLink,
+    Reordered,
+    ClockJump,
+}
+
+impl ChainIssueKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Gap => "gap",
+            Self::Fork => "fork",
+            Self::Replay => "replay",
+            Self::BrokenLink => "broken_link",
+            Self::Reordered => "reordered",
+            Self::ClockJump => "clock_jump",
+        }
+    }
+
+    /// Un hueco puede venir de tickets usados en otros repositorios o commits
+    /// abortados: se avisa, pero no invalida la cadena
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, Self::Gap)
+    }
+}
+
+/// Anomalía en la cadena de una identidad
+#[derive(Debug, Clone)]
+pub struct ChainIssue {
+    pub kind: ChainIssueKind,
+    pub key_id: String,
+    /// Commit en el que se manifiesta
+    pub commit: Oid,
+    pub detail: String,
+}
+
+/// Analiza las cadenas de todas las identidades presentes en `entries`
+pub fn check_chain(repo: &Repository, entries: &[ChainEntry]) -> Vec<ChainIssue> {
+    let mut by_key: HashMap<&str, Vec<&ChainEntry>> = HashMap::new();
+    for entry in entries {
+        by_key.entry(entry.key_id.as_str()).or_default().push(entry);
+    }
+
+    let mut issues = Vec::new();
+    let mut keys: Vec<&str> = by_key.keys().copied().collect();
+    keys.sort();
+    for key_id in keys {
+        let mut chain = by_key.remove(key_id).unwrap_or_default();
+        chain.sort_by_key(|e| e.seq);
+        let issue = |kind, commit, detail: String| ChainIssue { kind, key_id: key_id.to_string(), commit, detail };
+
+        // El mismo ticket en varios commits
+        let mut unique: Vec<&ChainEntry> = Vec::new();
+        for entry in chain {
+            match unique.iter().find(|u| u.hash == entry.hash) {
+                Some(first) => issues.push(issue(
+                    ChainIssueKind::Replay,
+                    entry.commit,
+                    format!("ticket #{} already used by {}", entry.seq, first.commit),
+                )),
+                None => unique.push(entry),
+            }
+        }
+
+        for (i, entry) in unique.iter().enumerate() {
+            if let Some(other) = unique[..i].iter().find(|u| u.seq == entry.seq || (entry.prev.is_some() && u.prev == entry.prev)) {
+                issues.push(issue(
+                    ChainIssueKind::Fork,
+                    entry.commit,
+                    format!("ticket #{} competes with #{} in {}", entry.seq, other.seq, other.commit),
+                ));
+            }
+        }
+
+        for pair in unique.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if a.seq == b.seq {
+                continue;
+            }
+            if b.seq > a.seq + 1 {
+                let missing = match b.seq - a.seq - 1 {
+                    1 => format!("ticket #{} is not in the range", a.seq + 1),
+                    _ => format!("tickets #{}..#{} are not in the range", a.seq + 1, b.seq - 1),
+                };
+                issues.push(issue(ChainIssueKind::Gap, b.commit, missing));
+            } else if b.prev.as_deref() != Some(a.hash.as_str()) {
+                issues.push(issue(ChainIssueKind::BrokenLink, b.commit, format!("ticket #{} does not follow #{}", b.seq, a.seq)));
+            }
+            if let Some(detail) = backwards_jump(a.issued_at, a.clock.as_ref(), b.issued_at, b.clock.as_ref()) {
+                issues.push(issue(ChainIssueKind::ClockJump, b.commit, format!("between #{} and #{}: {}", a.seq, b.seq, detail)));
+            }
+            if repo.graph_descendant_of(a.commit, b.commit).unwrap_or(false) {
+                issues.push(issue(
+                    ChainIssueKind::Reordered,
+                    a.commit,
+                    format!("ticket #{} comes after #{} in history", a.seq, b.seq),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::DaemonIdentity;
+    use crate::testing::TempDir;
+    use crate::ticket::{TicketBinding, TicketMode};
+
+    fn issue(state: &mut ChainState, identity: &DaemonIdentity) -> SignedTicket {
+        let binding = TicketBinding { tree: "t".into(), parent: None, author: "dev@example.com".into() };
+        let payload = TicketPayload::new(TicketMode::Valid, 1.0, binding, identity.public_key().key_id())
+            .with_clock(ClockStamp::now())
+            .with_chain(state.next_link());
+        let ticket = SignedTicket::sign(payload, identity).unwrap();
+        state.record(&ticket).unwrap();
+        ticket
+    }
+
+    #[test]
+    fn chain_state_survives_restarts_and_links_tickets() {
+        let dir = TempDir::new("cliff_watch_chain");
+        let identity = DaemonIdentity::Software(crate::crypto::generate_keypair().0);
+        let kid = identity.public_key().key_id();
+
+        let mut state = ChainState::open(&dir, &kid).unwrap();
+        let first = issue(&mut state, &identity);
+        let reopened = ChainState::open(&dir, &kid).unwrap();
+        assert_eq!(reopened.next_link(), ChainLink { seq: 2, prev: Some(ticket_hash(&first.encoded)) });
+        assert_eq!(first.payload.chain, Some(ChainLink { seq: 1, prev: None }));
+
+        // La hora del último ticket también sobrevive: un reloj retrasado se nota al emitir
+        let mut backdated = first.payload.clone();
+        assert!(reopened.clock_jump(&backdated).is_none());
+        backdated.issued_at -= 7200;
+        assert!(reopened.clock_jump(&backdated).is_some());
+    }
+
+    #[test]
+    fn detects_gaps_forks_replays_and_reordering() {
+        let dir = TempDir::new("cliff_watch_chain_repo");
+        let repo = Repository::init(&dir).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let tree = repo.find_tree(repo.treebuilder(None).unwrap().write().unwrap()).unwrap();
+        let mut commits = Vec::new();
+        for i in 0..5 {
+            let parents: Vec<git2::Commit> = commits.last().map(|o| repo.find_commit(*o).unwrap()).into_iter().collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            commits.push(repo.commit(Some("HEAD"), &sig, &sig, &format!("c{}", i), &tree, &parents).unwrap());
+        }
+
+        let identity = DaemonIdentity::Software(crate::crypto::generate_keypair().0);
+        let mut state = ChainState::open(&dir.join("state"), "k").unwrap();
+        let tickets: Vec<SignedTicket> = (0..5).map(|_| issue(&mut state, &identity)).collect();
+        let entry = |c: usize, t: usize| ChainEntry::from_ticket(commits[c], &tickets[t]).unwrap();
+
+        let clean: Vec<ChainEntry> = (0..5).map(|i| entry(i, i)).collect();
+        assert!(check_chain(&repo, &clean).is_empty());
+
+        // Falta #3 y #2 se reutiliza: hueco + replay
+        let codes = |entries: &[ChainEntry]| check_chain(&repo, entries).iter().map(|i| i.kind.code()).collect::<Vec<_>>();
+        assert_eq!(codes(&[entry(0, 0), entry(1, 1), entry(2, 1), entry(4, 4)]), vec!["replay", "gap"]);
+
+        // Tickets intercambiados entre commits
+        assert_eq!(codes(&[entry(0, 0), entry(1, 2), entry(2, 1)]), vec!["reordered"]);
+
+        // Ticket #5 emitido con el reloj retrasado dos horas
+        let mut backdated = entry(4, 4);
+        backdated.issued_at -= 7200;
+        assert_eq!(codes(&[entry(3, 3), backdated]), vec!["clock_jump"]);
+
+        // Otro ticket con el mismo número y predecesor
+        let mut forked = ChainState::open(&dir.join("state"), "k").unwrap();
+        forked.seq = 1;
+        forked.last_hash = Some(ticket_hash(&tickets[0].encoded));
+        let rival = issue(&mut forked, &identity);
+        let rival_entry = ChainEntry::from_ticket(commits[2], &rival).unwrap();
+        assert_eq!(codes(&[entry(0, 0), entry(1, 1), rival_entry]), vec!["fork"]);
+    }
+}
@@ -0,0 +1,532 @@
+//! Verificación de rangos para CI y hooks de servidor
+//!
+//! [`verify_commits`] clasifica cada commit en un [`CommitStatus`], le aplica
+//! las reglas de `.cliff-watch/policy.toml` y revisa las cadenas de tickets de
+//! sus firmantes; lo usan `verify base..head` y `server-hook`. Además del
+//! texto y el JSON propios, el resultado se exporta en dos formatos que las
+//! plataformas de CI ya saben mostrar:
+//!
+//! - SARIF 2.1.0 (code scanning): un resultado por commit que falla, con los
+//!   archivos que modificó como ubicaciones, y uno por anomalía de cadena.
+//! - JUnit XML (informes de tests): un `testcase` por commit.
+
+use std::fmt::Write as _;
+
+use git2::{Commit, Oid, Repository};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::chain::{check_chain, ChainEntry, ChainIssue};
+use crate::policy::{Enforcement, PolicyScope, RuleHit};
+use crate::ticket::{verify_commit_proof, verify_commit_ticket, TicketMode};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/iodevs-net/cliff-watch";
+/// Regla SARIF de las anomalías de cadena (fork, replay, reloj...)
+const CHAIN_RULE: &str = "chain_integrity";
+
+/// Resultado de un commit en `verify base..head`, de menos a más grave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitStatus {
+    Verified,
+    /// Merge eximido con `--merges exempt`
+    Exempt,
+    /// Ticket válido emitido en Audit Mode, firmas por debajo del umbral de
+    /// trust.toml o requisitos de policy.toml sin cumplir
+    BelowThreshold,
+    /// Sin ticket
+    Unsigned,
+    /// Ticket inválido: firma, clave, binding, autor u hora
+    BadSignature,
+}
+
+impl CommitStatus {
+    /// Identificador estable (regla SARIF, tipo de fallo JUnit)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Verified => "verified",
+            Self::Exempt => "exempt",
+            Self::BelowThreshold => "below_threshold",
+            Self::Unsigned => "unsigned",
+            Self::BadSignature => "bad_signature",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Verified => "verified",
+            Self::Exempt => "exempt",
+            Self::BelowThreshold => "below threshold",
+            Self::Unsigned => "unsigned",
+            Self::BadSignature => "bad signature",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Verified => "✅",
+            Self::Exempt => "➖",
+            Self::BelowThreshold => "⚠️ ",
+            Self::Unsigned | Self::BadSignature => "❌",
+        }
+    }
+
+    /// Código de salida: 0 verificado, 2 bajo umbral, 3 sin firmar, 4 firma inválida (1: error de uso)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Verified | Self::Exempt => 0,
+            Self::BelowThreshold => 2,
+            Self::Unsigned => 3,
+            Self::BadSignature => 4,
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.exit_code() != 0
+    }
+
+    /// Resultado de un ticket que no verifica (`verify_commit_ticket`)
+    pub fn from_failure(reason: &str) -> Self {
+        match reason {
+            "no_ticket" => Self::Unsigned,
+            "threshold_not_met" => Self::BelowThreshold,
+            _ => Self::BadSignature,
+        }
+    }
+
+    /// Nivel SARIF: por debajo del umbral es un aviso, el resto un error
+    fn sarif_level(&self) -> &'static str {
+        match self {
+            Self::BelowThreshold => "warning",
+            _ => "error",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Verified => "Commit carries a valid cliff-watch ticket",
+            Self::Exempt => "Merge commit exempted from verification",
+            Self::BelowThreshold => "Ticket issued in audit mode, signatures below the trust.toml threshold or policy.toml requirements not met",
+            Self::Unsigned => "Commit has no cliff-watch ticket",
+            Self::BadSignature => "Ticket signature, key, binding, author or timestamp is invalid",
+        }
+    }
+}
+
+/// Veredicto de `verify` sobre un commit
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitOutcome {
+    pub commit: String,
+    pub status: CommitStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Posición del ticket en la cadena de su identidad
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub merge: bool,
+    #[serde(skip)]
+    pub summary: String,
+    /// Explicación registrada para un commit sin ticket (`Cliff-Watch-Gap`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap: Option<String>,
+    /// Archivos que modificó el commit (solo si falla o rompe una cadena)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Exigencia con la que se juzga el commit (ref y policy.toml)
+    pub mode: Enforcement,
+    /// Reglas de policy.toml que le aplicaron
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RuleHit>,
+}
+
+impl CommitOutcome {
+    fn short(&self) -> &str {
+        &self.commit[..self.commit.len().min(8)]
+    }
+
+    fn headline(&self) -> String {
+        let reason = self.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+        let gap = self.gap.as_deref().map(|g| format!(". Gap explained: {}", g)).unwrap_or_default();
+        let rules: String = self.rules.iter().map(|r| format!(". Policy {}", r.explain())).collect();
+        format!("Commit {} \"{}\": {}{}{}{}", self.short(), self.summary, self.status.label(), reason, gap, rules)
+    }
+
+    /// ¿Su política lo rechaza?
+    pub fn is_violation(&self) -> bool {
+        self.mode.rejects(self.status)
+    }
+}
+
+/// Verifica `oids` (del más antiguo al más reciente) y las cadenas de sus firmantes.
+///
+/// Cada commit se juzga con el trust.toml y el policy.toml de su padre, sobre
+/// la rama de `scope`. Con `exempt_merges`, los merges no necesitan ticket. Un
+/// ticket válido que no cumple los requisitos de la política queda por debajo
+/// del umbral (`policy_unmet`). Los commits que fallan o rompen una cadena
+/// llevan los archivos que modificaron.
+pub fn verify_commits(repo: &Repository, oids: &[Oid], exempt_merges: bool, scope: &PolicyScope) -> (Vec<CommitOutcome>, Vec<ChainIssue>) {
+    let mut outcomes = Vec::new();
+    let mut entries = Vec::new();
+    let mut history = crate::trust::HistoryCache::default();
+    for oid in oids {
+        let Ok(commit) = repo.find_commit(*oid) else {
+            continue;
+        };
+        let merge = commit.parent_count() > 1;
+        let mut outcome = CommitOutcome {
+            commit: oid.to_string(),
+            status: CommitStatus::Exempt,
+            signer: None,
+            score: None,
+            reason: None,
+            seq: None,
+            merge,
+            summary: commit.summary().unwrap_or("").to_string(),
+            gap: None,
+            files: Vec::new(),
+            mode: scope.configured.unwrap_or(scope.default),
+            rules: Vec::new(),
+        };
+        let mut decision = match crate::policy::decide_with(repo, &commit, scope, &mut history) {
+            Ok(decision) => decision,
+            Err(e) => {
+                // Una política ilegible no relaja nada
+                outcome.status = CommitStatus::BadSignature;
+                outcome.reason = Some(e);
+                outcomes.push(outcome);
+                continue;
+            }
+        };
+        outcome.mode = decision.mode;
+        if merge && exempt_merges {
+            outcome.rules = decision.hits;
+            outcome.reason = Some("merge".to_string());
+            outcomes.push(outcome);
+            continue;
+        }
+
+        let attestation = crate::notes::read_attestation(repo, &commit);
+        let verification = crate::trust::load_trust_at_with(repo, &commit, &mut history)
+            .and_then(|trust| verify_commit_ticket(&commit, &attestation.text, &trust))
+            // Si el ticket se comprometió a una prueba de rango, debe estar y ser válida
+            .and_then(|v| verify_commit_proof(&commit, &attestation.text, &v.ticket).map(|proven| (v, proven)));
+        match verification {
+            Ok((v, proven)) => {
+                let entry = ChainEntry::from_ticket(*oid, &v.ticket);
+                outcome.seq = entry.as_ref().map(|e| e.seq);
+                entries.extend(entry);
+                outcome.score = Some(v.ticket.payload.score);
+                outcome.signer = Some(v.signer);
+                outcome.status = match v.ticket.payload.mode {
+                    TicketMode::Audit => {
+                        outcome.reason = Some("audit_mode".to_string());
+                        CommitStatus::BelowThreshold
+                    }
+                    _ => CommitStatus::Verified,
+                };
+                if !decision.check(proven, Some(&v.signers)) && outcome.status == CommitStatus::Verified {
+                    outcome.status = CommitStatus::BelowThreshold;
+                    outcome.reason = Some("policy_unmet".to_string());
+                }
+            }
+            Err(reason) => {
+                outcome.status = CommitStatus::from_failure(&reason);
+                if outcome.status == CommitStatus::Unsigned {
+                    outcome.gap = crate::notes::read_gap(repo, *oid);
+                }
+                outcome.reason = Some(reason);
+            }
+        }
+        outcome.rules = decision.hits;
+        outcomes.push(outcome);
+    }
+
+    let issues = check_chain(repo, &entries);
+    for outcome in outcomes.iter_mut() {
+        if outcome.status.is_failure() || issues.iter().any(|i| i.commit.to_string() == outcome.commit) {
+            if let Ok(commit) = Oid::from_str(&outcome.commit).and_then(|oid| repo.find_commit(oid)) {
+                outcome.files = changed_files(repo, &commit);
+            }
+        }
+    }
+    (outcomes, issues)
+}
+
+/// Peor resultado que la política de cada commit rechaza: una cadena manipulada
+/// (fork, replay, reloj...) cuenta como firma inválida
+pub fn worst_status(outcomes: &[CommitOutcome], issues: &[ChainIssue]) -> CommitStatus {
+    let chain_broken = issues.iter().filter(|i| i.kind.is_fatal()).any(|i| {
+        let commit = i.commit.to_string();
+        outcomes.iter().find(|o| o.commit == commit).is_none_or(|o| o.mode.rejects_chain())
+    });
+    outcomes
+        .iter()
+        .filter(|o| o.is_violation())
+        .map(|o| o.status)
+        .chain(chain_broken.then_some(CommitStatus::BadSignature))
+        .max()
+        .unwrap_or(CommitStatus::Verified)
+}
+
+/// Rutas que modifica un commit respecto a su primer padre
+pub fn changed_files(repo: &Repository, commit: &Commit) -> Vec<String> {
+    let tree = commit.tree().ok();
+    let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+    let Ok(diff) = repo.diff_tree_to_tree(parent_tree.as_ref(), tree.as_ref(), None) else {
+        return Vec::new();
+    };
+    diff.deltas()
+        .filter_map(|d| d.new_file().path().or_else(|| d.old_file().path()).map(|p| p.to_string_lossy().to_string()))
+        .collect()
+}
+
+/// Informe SARIF 2.1.0 de una verificación
+pub fn to_sarif(outcomes: &[CommitOutcome], issues: &[ChainIssue]) -> Value {
+    let statuses = [CommitStatus::BelowThreshold, CommitStatus::Unsigned, CommitStatus::BadSignature];
+    let mut rules: Vec<Value> = statuses
+        .iter()
+        .map(|s| {
+            json!({
+                "id": s.code(),
+                "shortDescription": { "text": s.description() },
+                "defaultConfiguration": { "level": s.sarif_level() },
+            })
+        })
+        .collect();
+    rules.push(json!({
+        "id": CHAIN_RULE,
+        "shortDescription": { "text": "Ticket chain of a signing identity is forked, replayed, reordered or out of clock order" },
+        "defaultConfiguration": { "level": "error" },
+    }));
+    let rule_index = |id: &str| rules.iter().position(|r| r["id"] == id);
+
+    let locations = |files: &[String]| -> Vec<Value> {
+        files.iter().map(|f| json!({ "physicalLocation": { "artifactLocation": { "uri": f } } })).collect()
+    };
+
+    let mut results: Vec<Value> = outcomes
+        .iter()
+        .filter(|o| o.status.is_failure())
+        .map(|o| {
+            json!({
+                "ruleId": o.status.code(),
+                "ruleIndex": rule_index(o.status.code()),
+                // Lo que la política del commit tolera (audit, off) queda como nota
+                "level": if o.is_violation() { o.status.sarif_level() } else { "note" },
+                "message": { "text": o.headline() },
+                "locations": locations(&o.files),
+                "partialFingerprints": { "cliffWatchCommit/v1": format!("{}:{}", o.commit, o.status.code()) },
+                "properties": {
+                    "commit": o.commit,
+                    "signer": o.signer,
+                    "reason": o.reason,
+                    "gap": o.gap,
+                    "mode": o.mode,
+                    "rules": o.rules.iter().map(|r| r.explain()).collect::<Vec<_>>(),
+                },
+            })
+        })
+        .collect();
+
+    for issue in issues {
+        let commit = issue.commit.to_string();
+        let files = outcomes.iter().find(|o| o.commit == commit).map(|o| o.files.as_slice()).unwrap_or_default();
+        results.push(json!({
+            "ruleId": CHAIN_RULE,
+            "ruleIndex": rule_index(CHAIN_RULE),
+            "level": if issue.kind.is_fatal() { "error" } else { "warning" },
+            "message": {
+                "text": format!("Ticket chain of kid {}: {} at {} ({})", issue.key_id, issue.kind.code(), &commit[..8], issue.detail),
+            },
+            "locations": locations(files),
+            "partialFingerprints": { "cliffWatchCommit/v1": format!("{}:{}:{}", commit, issue.key_id, issue.kind.code()) },
+            "properties": { "commit": commit, "keyId": issue.key_id, "kind": issue.kind.code() },
+        }));
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cliff-watch",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// Informe JUnit XML: una suite `suite` con un `testcase` por commit.
+///
+/// Los commits que su política rechaza (o en los que se rompe una cadena) llevan
+/// `<failure>`; los merges eximidos, `<skipped>`; los fallos tolerados y las
+/// anomalías leves, `<system-out>`.
+pub fn to_junit(suite: &str, outcomes: &[CommitOutcome], issues: &[ChainIssue]) -> String {
+    let mut cases = String::new();
+    let mut failures = 0;
+    let mut skipped = 0;
+    for o in outcomes {
+        let chain: Vec<&ChainIssue> = issues.iter().filter(|i| i.commit.to_string() == o.commit).collect();
+        let chain_text: Vec<String> = chain
+            .iter()
+            .map(|i| format!("Ticket chain of kid {}: {} ({})", i.key_id, i.kind.code(), i.detail))
+            .collect();
+        let name = format!("{} {}", o.short(), o.summary);
+        let _ = writeln!(cases, "    <testcase classname=\"cliff-watch.verify\" name=\"{}\">", xml_escape(&name));
+
+        let chain_failure = chain.iter().any(|i| i.kind.is_fatal()) && o.mode.rejects_chain();
+        if o.is_violation() || chain_failure {
+            failures += 1;
+            let (kind, message) = if o.is_violation() {
+                (o.status.code(), o.reason.clone().unwrap_or_else(|| o.status.label().to_string()))
+            } else {
+                (CHAIN_RULE, chain_text.join("; "))
+            };
+            let mut body = vec![o.headline()];
+            body.extend(chain_text.iter().cloned());
+            if !o.files.is_empty() {
+                body.push(format!("Files: {}", o.files.join(", ")));
+            }
+            let _ = writeln!(
+                cases,
+                "      <failure type=\"{}\" message=\"{}\">{}</failure>",
+                kind,
+                xml_escape(&message),
+                xml_escape(&body.join("\n"))
+            );
+        } else if o.status == CommitStatus::Exempt {
+            skipped += 1;
+            let reason = o.reason.as_deref().unwrap_or("exempt");
+            let _ = writeln!(cases, "      <skipped message=\"{}\"/>", xml_escape(reason));
+        } else if o.status.is_failure() || !chain_text.is_empty() {
+            let mut out: Vec<String> = o.status.is_failure().then(|| format!("{} [policy {}]", o.headline(), o.mode.as_str())).into_iter().collect();
+            out.extend(chain_text.iter().cloned());
+            let _ = writeln!(cases, "      <system-out>{}</system-out>", xml_escape(&out.join("\n")));
+        }
+        cases.push_str("    </testcase>\n");
+    }
+
+    let counts = format!("tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\"", outcomes.len(), failures, skipped);
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"cliff-watch\" {counts}>\n  <testsuite name=\"{}\" {counts}>\n{}  </testsuite>\n</testsuites>\n",
+        xml_escape(suite),
+        cases
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // XML 1.0 no admite caracteres de control salvo tab y saltos de línea
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::ChainIssueKind;
+
+    fn outcome(commit: &str, status: CommitStatus, reason: Option<&str>, files: &[&str]) -> CommitOutcome {
+        CommitOutcome {
+            commit: commit.to_string(),
+            status,
+            signer: None,
+            score: None,
+            reason: reason.map(str::to_string),
+            seq: None,
+            merge: false,
+            summary: "feat: <x> & \"y\"".to_string(),
+            gap: None,
+            files: files.iter().map(|f| f.to_string()).collect(),
+            mode: Enforcement::Strict,
+            rules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn exports_failures_to_sarif_and_junit() {
+        let ok = "1111111111111111111111111111111111111111";
+        let bad = "2222222222222222222222222222222222222222";
+        let outcomes = [
+            outcome(ok, CommitStatus::Verified, None, &[]),
+            outcome(bad, CommitStatus::Unsigned, Some("no_ticket"), &["src/lib.rs", "README.md"]),
+            outcome("3333333333333333333333333333333333333333", CommitStatus::Exempt, Some("merge"), &[]),
+        ];
+        let issues = [ChainIssue {
+            kind: ChainIssueKind::Replay,
+            key_id: "abcd".to_string(),
+            commit: git2::Oid::from_str(ok).unwrap(),
+            detail: "seq 1 reused".to_string(),
+        }];
+
+        let sarif = to_sarif(&outcomes, &issues);
+        assert_eq!(sarif["version"], "2.1.0");
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "unsigned");
+        assert_eq!(results[0]["locations"][1]["physicalLocation"]["artifactLocation"]["uri"], "README.md");
+        let rules = &sarif["runs"][0]["tool"]["driver"]["rules"];
+        assert_eq!(rules[results[1]["ruleIndex"].as_u64().unwrap() as usize]["id"], CHAIN_RULE);
+
+        let junit = to_junit("cliff-watch verify main..HEAD", &outcomes, &issues);
+        assert!(junit.contains("tests=\"3\" failures=\"2\" errors=\"0\" skipped=\"1\""));
+        assert!(junit.contains("<failure type=\"unsigned\" message=\"no_ticket\">"));
+        assert!(junit.contains("<failure type=\"chain_integrity\""));
+        assert!(junit.contains("name=\"22222222 feat: &lt;x&gt; &amp; &quot;y&quot;\""));
+        assert!(junit.contains("Files: src/lib.rs, README.md"));
+        assert!(!junit.contains("<x>"));
+    }
+
+    #[test]
+    fn stripped_range_proof_is_a_bad_signature() {
+        use crate::crypto::zkp::{HumanityProof, HUMANITY_THRESHOLD_PERCENT};
+        use crate::crypto::{generate_keypair, key_id, DaemonIdentity};
+        use crate::ticket::{SignedTicket, TicketBinding, TicketPayload, PROOF_TRAILER, TICKET_TRAILER};
+
+        let dir = crate::testing::TempDir::new("cliff_watch_ci");
+        let repo = Repository::init(&dir).unwrap();
+        let (signing_key, verifying_key) = generate_keypair();
+        let identity = DaemonIdentity::Software(signing_key);
+        let mut trust = crate::trust::TrustConfig::default();
+        trust.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+        let blob = repo.blob(trust.to_toml().unwrap().as_bytes()).unwrap();
+        let mut builder = repo.treebuilder(None).unwrap();
+        builder.insert("trust.toml", blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+
+        let proof = HumanityProof::generate(82, HUMANITY_THRESHOLD_PERCENT, tree.id().to_string().as_bytes()).unwrap().to_trailer_value();
+        let binding = TicketBinding { tree: tree.id().to_string(), parent: None, author: "dev@example.com".into() };
+        let payload = TicketPayload::new(TicketMode::Valid, 5.0, binding, key_id(&verifying_key)).with_proof(&proof);
+        let ticket = SignedTicket::sign(payload, &identity).unwrap().to_trailer_value();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let with_proof = format!("feat: x\n\n{}: {}\n{}: {}\n", TICKET_TRAILER, ticket, PROOF_TRAILER, proof);
+        let stripped = format!("feat: x\n\n{}: {}\n", TICKET_TRAILER, ticket);
+        let oids: Vec<Oid> = [with_proof, stripped].iter().map(|m| repo.commit(None, &sig, &sig, m, &tree, &[]).unwrap()).collect();
+
+        let scope = PolicyScope { branch: None, configured: None, default: Enforcement::Strict };
+        let (outcomes, _) = verify_commits(&repo, &oids, false, &scope);
+        assert_eq!(outcomes[0].status, CommitStatus::Verified);
+        assert_eq!((outcomes[1].status, outcomes[1].reason.as_deref()), (CommitStatus::BadSignature, Some("missing_proof")));
+    }
+}
@@ -0,0 +1,96 @@
+//! Reloj de los tickets
+//!
+//! `ts` es el reloj de pared, que cualquiera con permisos en la máquina puede
+//! mover. Cada ticket añade además `boot=<id>;mono=<ms>`: el identificador
+//! aleatorio del arranque (`/proc/sys/kernel/random/boot_id`) y el tiempo
+//! monótono desde el arranque (`/proc/uptime`, incluye suspensión).
+//!
+//! - Entre dos tickets del mismo arranque, el reloj de pared debe avanzar lo
+//!   mismo que el monótono; si avanza menos, alguien lo retrasó.
+//! - Entre arranques distintos solo se exige que no retroceda.
+//!
+//! `verify` rechaza además los tickets cuyo `ts` dista más de
+//! `MAX_COMMIT_SKEW_SECS` de la hora del committer (`timestamp_skew`).
+
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Distancia máxima entre la emisión del ticket y la hora del committer
+pub const MAX_COMMIT_SKEW_SECS: i64 = 3600;
+
+/// Retroceso tolerado entre tickets (ajustes de NTP)
+pub const CLOCK_JUMP_TOLERANCE_SECS: i64 = 60;
+
+/// Arranque y tiempo monótono en el que se emitió un ticket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockStamp {
+    /// 128 bits aleatorios (hex) que cambian en cada arranque
+    pub boot_id: String,
+    /// Milisegundos desde el arranque
+    pub mono_ms: u64,
+}
+
+impl ClockStamp {
+    /// Sello actual. Sin `/proc` (no Linux), el "arranque" es el del proceso
+    pub fn now() -> Self {
+        match (read_boot_id(), read_uptime_ms()) {
+            (Some(boot_id), Some(mono_ms)) => Self { boot_id, mono_ms },
+            _ => {
+                static PROCESS: OnceLock<(String, Instant)> = OnceLock::new();
+                let (boot_id, start) = PROCESS.get_or_init(|| (hex::encode(rand::random::<[u8; 16]>()), Instant::now()));
+                Self { boot_id: boot_id.clone(), mono_ms: start.elapsed().as_millis() as u64 }
+            }
+        }
+    }
+}
+
+fn read_boot_id() -> Option<String> {
+    let id = std::fs::read_to_string("/proc/sys/kernel/random/boot_id").ok()?.trim().replace('-', "");
+    (id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit())).then_some(id)
+}
+
+fn read_uptime_ms() -> Option<u64> {
+    let uptime: f64 = std::fs::read_to_string("/proc/uptime").ok()?.split_whitespace().next()?.parse().ok()?;
+    Some((uptime * 1000.0) as u64)
+}
+
+/// Retroceso de reloj entre dos tickets consecutivos de una identidad, si lo hay.
+///
+/// Recibe la hora de pared y el sello (si lo lleva) de cada ticket, en orden de emisión.
+pub fn backwards_jump(prev_wall: i64, prev: Option<&ClockStamp>, next_wall: i64, next: Option<&ClockStamp>) -> Option<String> {
+    let wall_elapsed = next_wall - prev_wall;
+    match (prev, next) {
+        (Some(a), Some(b)) if a.boot_id == b.boot_id => {
+            if b.mono_ms < a.mono_ms {
+                return Some("monotonic clock went backwards within the same boot".to_string());
+            }
+            let mono_elapsed = ((b.mono_ms - a.mono_ms) / 1000) as i64;
+            let behind = mono_elapsed - wall_elapsed;
+            (behind > CLOCK_JUMP_TOLERANCE_SECS)
+                .then(|| format!("wall clock fell {}s behind the monotonic clock", behind))
+        }
+        _ => (wall_elapsed < -CLOCK_JUMP_TOLERANCE_SECS).then(|| format!("wall clock went back {}s", -wall_elapsed)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_backwards_jumps_within_and_across_boots() {
+        let stamp = |boot: &str, mono_ms: u64| ClockStamp { boot_id: boot.to_string(), mono_ms };
+        let now = ClockStamp::now();
+        assert_eq!(now.boot_id.len(), 32);
+        assert!(ClockStamp::now().mono_ms >= now.mono_ms);
+
+        // Mismo arranque: 10 min monótonos, 10 min de pared
+        assert!(backwards_jump(1000, Some(&stamp("a", 0)), 1600, Some(&stamp("a", 600_000))).is_none());
+        // Mismo arranque, reloj retrasado una hora (aunque la pared "avance")
+        assert!(backwards_jump(1000, Some(&stamp("a", 0)), 1010, Some(&stamp("a", 3_600_000))).is_some());
+        assert!(backwards_jump(1000, Some(&stamp("a", 5000)), 1010, Some(&stamp("a", 1000))).is_some());
+        // Otro arranque: solo cuenta que la pared no retroceda
+        assert!(backwards_jump(1000, Some(&stamp("a", 9_000_000)), 1010, Some(&stamp("b", 0))).is_none());
+        assert!(backwards_jump(1000, None, 100, Some(&stamp("b", 0))).is_some());
+    }
+}
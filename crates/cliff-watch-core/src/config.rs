@@ -9,6 +9,10 @@ use std::time::Duration;
 pub struct GovConfig {
     pub governance: GovernanceConfig,
     pub monitoring: MonitorConfigDto,
+    #[serde(default)]
+    pub identity: IdentityConfig,
+    #[serde(default)]
+    pub attestation: AttestationConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -18,6 +22,15 @@ pub struct GovernanceConfig {
     pub pareto_alpha_min: f64,
     pub cv_min: f64,
     pub audit_mode: bool,
+    /// Si la batería no alcanza (y no hay Audit Mode), ofrecer un reto de prueba de trabajo
+    #[serde(default)]
+    pub pow_fallback: bool,
+    /// Credencial de reputación (JSON) a nombre de este daemon que abarata la prueba de trabajo
+    #[serde(default)]
+    pub reputation_credential: Option<String>,
+    /// Emisores (`did:key`) cuyas credenciales de reputación se aceptan
+    #[serde(default)]
+    pub trusted_issuers: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -28,6 +41,39 @@ pub struct MonitorConfigDto {
     pub ignore_extensions: Vec<String>,
 }
 
+/// Identidad con la que el daemon firma los tickets
+#[derive(Debug, Deserialize, Clone)]
+pub struct IdentityConfig {
+    /// `file` (Ed25519 en ~/.config/cliff-watch/daemon.key), `tpm` (ECDSA-P256 persistente),
+    /// `ssh` (clave OpenSSH Ed25519) o `ssh-agent`
+    pub backend: String,
+    /// Handle persistente de la clave en el TPM
+    pub tpm_handle: u32,
+    /// Clave OpenSSH para los backends `ssh` (privada) y `ssh-agent` (pública)
+    #[serde(default)]
+    pub ssh_key: Option<String>,
+}
+
+impl IdentityConfig {
+    /// `ssh_key` con `~` expandido
+    pub fn ssh_key_path(&self) -> Option<PathBuf> {
+        self.ssh_key.as_deref().filter(|p| !p.is_empty()).map(crate::crypto::expand_home)
+    }
+}
+
+/// Dónde se guardan las atestaciones de los commits nuevos
+#[derive(Debug, Deserialize, Clone)]
+pub struct AttestationConfig {
+    /// `trailers` (mensaje del commit), `notes` (refs/notes/cliff-watch) o `both`
+    pub backend: String,
+}
+
+impl AttestationConfig {
+    pub fn backend(&self) -> Result<crate::notes::AttestationBackend, String> {
+        crate::notes::AttestationBackend::parse(&self.backend)
+    }
+}
+
 impl Default for GovernanceConfig {
     fn default() -> Self {
         Self {
@@ -36,6 +82,9 @@ impl Default for GovernanceConfig {
             pareto_alpha_min: 1.5,
             cv_min: 0.15,
             audit_mode: false,
+            pow_fallback: false,
+            reputation_credential: None,
+            trusted_issuers: Vec::new(),
         }
     }
 }
@@ -51,11 +100,29 @@ impl Default for MonitorConfigDto {
     }
 }
 
+impl Default for IdentityConfig {
+    fn default() -> Self {
+        Self {
+            backend: "file".to_string(),
+            tpm_handle: crate::crypto::tpm::DEFAULT_IDENTITY_HANDLE,
+            ssh_key: None,
+        }
+    }
+}
+
+impl Default for AttestationConfig {
+    fn default() -> Self {
+        Self { backend: "trailers".to_string() }
+    }
+}
+
 impl Default for GovConfig {
     fn default() -> Self {
         Self {
             governance: Default::default(),
             monitoring: Default::default(),
+            identity: Default::default(),
+            attestation: Default::default(),
         }
     }
 }
@@ -68,10 +135,14 @@ impl GovConfig {
             .set_default("governance.pareto_alpha_min", 1.5)?
             .set_default("governance.cv_min", 0.15)?
             .set_default("governance.audit_mode", false)?
+            .set_default("governance.pow_fallback", false)?
             .set_default("monitoring.watch_root", ".")?
             .set_default("monitoring.debounce_window_ms", 500)?
             .set_default("monitoring.ignore_top_level_dirs", vec![".git", "target", "node_modules"])?
             .set_default("monitoring.ignore_extensions", vec!["log", "lock"])?
+            .set_default("identity.backend", "file")?
+            .set_default("identity.tpm_handle", crate::crypto::tpm::DEFAULT_IDENTITY_HANDLE as i64)?
+            .set_default("attestation.backend", "trailers")?
             // Local config
             .add_source(File::with_name("cliff-watch").required(false))
             // Global config
@@ -83,6 +154,15 @@ impl GovConfig {
     }
 }
 
+/// Directorio de datos locales: `$XDG_DATA_HOME/cliff-watch`, o `~/.local/share/cliff-watch`
+pub fn data_dir() -> Result<PathBuf, String> {
+    if let Some(data) = std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(data).join("cliff-watch"));
+    }
+    let home = std::env::var("HOME").map_err(|_| "No env var HOME found")?;
+    Ok(PathBuf::from(home).join(".local").join("share").join("cliff-watch"))
+}
+
 // Conversion to internal MonitorConfig
 impl From<MonitorConfigDto> for MonitorConfig {
     fn from(dto: MonitorConfigDto) -> Self {
@@ -0,0 +1,401 @@
+//! Credencial verificable de reputación (W3C Verifiable Credentials 2.0)
+//!
+//! Resume los commits verificados de una identidad en un repositorio (número,
+//! modos de ticket, score medio y fechas) en un documento JSON firmado por el
+//! daemon emisor. Emisor y sujeto son `did:key`, de modo que otro repositorio
+//! la verifica sin acceso al original y puede usarla para confiar en la clave
+//! del sujeto o relajar sus exigencias con contribuidores probados.
+//!
+//! La prueba sigue Data Integrity con canonicalización JCS: `eddsa-jcs-2022`
+//! para claves Ed25519 y `ecdsa-jcs-2019` para P-256 (TPM). Se firma
+//! `SHA256(opciones de la prueba) || SHA256(credencial)`.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use git2::{Oid, Repository};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::crypto::did::{from_did_key, multibase_decode, multibase_encode, to_did_key, verification_method};
+use crate::crypto::{DaemonIdentity, PublicKey};
+use crate::trust::TrustConfig;
+
+/// Tipo propio de la credencial, junto a `VerifiableCredential`
+pub const CREDENTIAL_TYPE: &str = "CliffWatchReputationCredential";
+const VC_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+/// Vigencia de una credencial recién emitida
+pub const VALIDITY_DAYS: i64 = 365;
+
+/// Historial verificado de una identidad en un repositorio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationSummary {
+    /// URL del remoto `origin`, si lo hay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// Commit desde el que se recorrió el historial
+    pub head: String,
+    pub verified_commits: usize,
+    pub valid_commits: usize,
+    pub audit_commits: usize,
+    pub pow_commits: usize,
+    /// Score medio de los tickets (dos decimales)
+    pub average_score: f64,
+    pub first_commit: DateTime<Utc>,
+    pub last_commit: DateTime<Utc>,
+}
+
+/// Resume los commits de HEAD (hasta `limit`) cuyo ticket verifica y lo emitió `subject`.
+///
+/// Cada commit se juzga con el trust.toml de su padre (`trust::load_trust_at`),
+/// como en `verify`: una clave revocada no suma los commits posteriores.
+pub fn summarize(repo: &Repository, subject: &PublicKey, limit: usize) -> Result<ReputationSummary, String> {
+    let head = repo.head().and_then(|h| h.peel_to_commit()).map_err(|e| format!("No HEAD commit: {}", e))?;
+    let mut walk = repo.revwalk().map_err(|e| e.to_string())?;
+    walk.push(head.id()).map_err(|e| e.to_string())?;
+
+    let kid = subject.key_id();
+    let (mut verified, mut valid, mut audit, mut pow) = (0usize, 0usize, 0usize, 0usize);
+    let mut total_score = 0.0;
+    let mut dates: Vec<i64> = Vec::new();
+    // Los hijos de un mismo padre comparten trust.toml; uno ilegible no verifica nada
+    let mut trust_by_parent: HashMap<Oid, Option<TrustConfig>> = HashMap::new();
+    let mut history = crate::trust::HistoryCache::default();
+    for oid in walk.take(limit).flatten() {
+        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+        let root_trust;
+        let trust = match commit.parent_id(0) {
+            Ok(parent) => match trust_by_parent.entry(parent) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(crate::trust::load_trust_at_with(repo, &commit, &mut history).ok()),
+            },
+            Err(_) => {
+                root_trust = crate::trust::load_trust_at_with(repo, &commit, &mut history).ok();
+                &root_trust
+            }
+        };
+        let Some(trust) = trust else {
+            continue;
+        };
+        let attestation = crate::notes::read_attestation(repo, &commit);
+        let Ok(verification) = crate::ticket::verify_commit_ticket(&commit, &attestation.text, trust) else {
+            continue;
+        };
+        let payload = &verification.ticket.payload;
+        if payload.key_id != kid {
+            continue;
+        }
+        verified += 1;
+        total_score += payload.score;
+        dates.push(commit.time().seconds());
+        match payload.mode {
+            crate::ticket::TicketMode::Valid => valid += 1,
+            crate::ticket::TicketMode::Audit => audit += 1,
+            crate::ticket::TicketMode::Pow => pow += 1,
+        }
+    }
+    if verified == 0 {
+        return Err(format!("No verified commits by key {} in the last {} commits", kid, limit));
+    }
+
+    let date = |ts: i64| DateTime::from_timestamp(ts, 0).unwrap_or_default();
+    Ok(ReputationSummary {
+        repository: repo.find_remote("origin").ok().and_then(|r| r.url().map(str::to_string)),
+        head: head.id().to_string(),
+        verified_commits: verified,
+        valid_commits: valid,
+        audit_commits: audit,
+        pow_commits: pow,
+        average_score: (total_score / verified as f64 * 100.0).round() / 100.0,
+        first_commit: date(dates.iter().copied().min().unwrap_or_default()),
+        last_commit: date(dates.iter().copied().max().unwrap_or_default()),
+    })
+}
+
+/// Credencial aún sin prueba, a nombre de `issuer`
+pub fn unsigned_credential(issuer: &PublicKey, subject: &PublicKey, summary: &ReputationSummary, now: DateTime<Utc>) -> Value {
+    let mut credential_subject = serde_json::to_value(summary).unwrap_or_else(|_| json!({}));
+    credential_subject["id"] = json!(to_did_key(subject));
+    json!({
+        "@context": [VC_CONTEXT],
+        "type": ["VerifiableCredential", CREDENTIAL_TYPE],
+        "issuer": to_did_key(issuer),
+        "validFrom": timestamp(now),
+        "validUntil": timestamp(now + chrono::Duration::days(VALIDITY_DAYS)),
+        "credentialSubject": credential_subject,
+    })
+}
+
+/// Resume el historial de `subject` en `repo` y lo firma como `identity`.
+///
+/// Lo usa el daemon: el resumen sale del propio repositorio, nunca del
+/// cliente, para que la clave de los tickets no avale cifras inventadas.
+pub fn issue(repo: &Repository, subject: &PublicKey, identity: &DaemonIdentity, limit: usize, now: DateTime<Utc>) -> Result<String, String> {
+    let summary = summarize(repo, subject, limit)?;
+    let unsigned = unsigned_credential(&identity.public_key(), subject, &summary, now);
+    sign_credential(&unsigned.to_string(), identity, now)
+}
+
+/// Firma una credencial de reputación emitida a nombre de `identity`;
+/// rechaza cualquier otro documento para que la clave de los tickets no
+/// firme contenido arbitrario.
+pub fn sign_credential(unsigned: &str, identity: &DaemonIdentity, now: DateTime<Utc>) -> Result<String, String> {
+    let mut credential: Value = serde_json::from_str(unsigned).map_err(|e| format!("Invalid credential JSON: {}", e))?;
+    check_type(&credential)?;
+    let key = identity.public_key();
+    if credential["issuer"].as_str() != Some(to_did_key(&key).as_str()) {
+        return Err("Credential issuer is not this daemon".to_string());
+    }
+    if credential.get("proof").is_some() {
+        return Err("Credential is already signed".to_string());
+    }
+
+    let mut proof = json!({
+        "@context": credential["@context"].clone(),
+        "type": "DataIntegrityProof",
+        "cryptosuite": cryptosuite(&key),
+        "created": timestamp(now),
+        "verificationMethod": verification_method(&key),
+        "proofPurpose": "assertionMethod",
+    });
+    let signature = identity.sign(&signing_input(&credential, &proof))?;
+    proof["proofValue"] = json!(multibase_encode(&signature));
+    credential["proof"] = proof;
+    serde_json::to_string_pretty(&credential).map_err(|e| e.to_string())
+}
+
+/// Credencial con prueba válida
+#[derive(Debug, Clone)]
+pub struct VerifiedCredential {
+    pub issuer: PublicKey,
+    pub subject: PublicKey,
+    pub summary: ReputationSummary,
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+impl VerifiedCredential {
+    /// El sujeto firmó su propia credencial: solo prueba posesión de la clave
+    pub fn is_self_issued(&self) -> bool {
+        self.issuer == self.subject
+    }
+}
+
+/// Verifica la prueba y la vigencia de una credencial.
+///
+/// Errores: `unsigned_credential`, `unsupported_proof`, `verification_method_mismatch`,
+/// `bad_signature`, `not_yet_valid`, `expired`, o el motivo del formato inválido.
+pub fn verify_credential(document: &str, now: DateTime<Utc>) -> Result<VerifiedCredential, String> {
+    let mut credential: Value = serde_json::from_str(document).map_err(|e| format!("Invalid credential JSON: {}", e))?;
+    check_type(&credential)?;
+    let proof = credential
+        .as_object_mut()
+        .and_then(|c| c.remove("proof"))
+        .ok_or_else(|| "unsigned_credential".to_string())?;
+
+    let issuer = from_did_key(credential["issuer"].as_str().ok_or("Credential issuer missing")?)?;
+    if proof["type"] != "DataIntegrityProof" || proof["proofPurpose"] != "assertionMethod" || proof["cryptosuite"] != cryptosuite(&issuer) {
+        return Err("unsupported_proof".to_string());
+    }
+    let method = proof["verificationMethod"].as_str().ok_or("unsupported_proof")?;
+    if method.split('#').next() != credential["issuer"].as_str() || from_did_key(method)? != issuer {
+        return Err("verification_method_mismatch".to_string());
+    }
+
+    let mut proof_config = proof.clone();
+    let proof_value = proof_config
+        .as_object_mut()
+        .and_then(|p| p.remove("proofValue"))
+        .and_then(|v| v.as_str().map(str::to_string))
+        .ok_or("unsupported_proof")?;
+    let signature = multibase_decode(&proof_value)?;
+    if !issuer.verify(&signing_input(&credential, &proof_config), &signature) {
+        return Err("bad_signature".to_string());
+    }
+
+    let date = |field: &str| -> Result<Option<DateTime<Utc>>, String> {
+        credential[field]
+            .as_str()
+            .map(|s| DateTime::parse_from_rfc3339(s).map(|d| d.with_timezone(&Utc)).map_err(|_| format!("Invalid {}", field)))
+            .transpose()
+    };
+    if date("validFrom")?.is_some_and(|from| now < from) {
+        return Err("not_yet_valid".to_string());
+    }
+    let valid_until = date("validUntil")?;
+    if valid_until.is_some_and(|until| now > until) {
+        return Err("expired".to_string());
+    }
+
+    let subject = &credential["credentialSubject"];
+    Ok(VerifiedCredential {
+        issuer,
+        subject: from_did_key(subject["id"].as_str().ok_or("Credential subject missing")?)?,
+        summary: serde_json::from_value(subject.clone()).map_err(|e| format!("Invalid credential subject: {}", e))?,
+        valid_until,
+    })
+}
+
+/// Credencial que relaja la prueba de trabajo de `subject` (ver `pow::relax_for_history`).
+///
+/// Debe verificar, estar a nombre de `subject` y venir de uno de los emisores
+/// `trusted_issuers` (`did:key`); una autoemitida solo prueba posesión de la
+/// clave. Errores: los de `verify_credential`, `subject_mismatch`,
+/// `self_issued` o `untrusted_issuer`.
+pub fn verify_reputation(document: &str, subject: &PublicKey, trusted_issuers: &[String], now: DateTime<Utc>) -> Result<VerifiedCredential, String> {
+    let credential = verify_credential(document, now)?;
+    if credential.subject != *subject {
+        return Err("subject_mismatch".to_string());
+    }
+    if credential.is_self_issued() {
+        return Err("self_issued".to_string());
+    }
+    if !trusted_issuers.iter().any(|did| from_did_key(did).is_ok_and(|k| k == credential.issuer)) {
+        return Err("untrusted_issuer".to_string());
+    }
+    Ok(credential)
+}
+
+fn check_type(credential: &Value) -> Result<(), String> {
+    let types = credential["type"].as_array().map(|t| t.iter().filter_map(Value::as_str).collect::<Vec<_>>()).unwrap_or_default();
+    if !types.contains(&"VerifiableCredential") || !types.contains(&CREDENTIAL_TYPE) {
+        return Err(format!("Not a {}", CREDENTIAL_TYPE));
+    }
+    Ok(())
+}
+
+fn cryptosuite(key: &PublicKey) -> &'static str {
+    match key {
+        PublicKey::Ed25519(_) => "eddsa-jcs-2022",
+        PublicKey::P256(_) => "ecdsa-jcs-2019",
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// `SHA256(JCS(opciones)) || SHA256(JCS(documento))`
+fn signing_input(document: &Value, proof_config: &Value) -> Vec<u8> {
+    let mut input = crate::crypto::calculate_sha256(canonical_json(proof_config).as_bytes());
+    input.extend(crate::crypto::calculate_sha256(canonical_json(document).as_bytes()));
+    input
+}
+
+/// JSON Canonicalization Scheme (RFC 8785): claves ordenadas por unidades
+/// UTF-16, sin espacios y números en la forma más corta de ECMAScript
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            let members: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{}:{}", Value::String(k.clone()), canonical_json(&map[k])))
+                .collect();
+            format!("{{{}}}", members.join(","))
+        }
+        Value::Array(items) => format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        // ECMAScript escribe los flotantes enteros sin decimales (2.0 -> 2)
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", f as i64),
+            _ => n.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> ReputationSummary {
+        ReputationSummary {
+            repository: Some("https://example.com/repo.git".into()),
+            head: "1111111111111111111111111111111111111111".into(),
+            verified_commits: 12,
+            valid_commits: 10,
+            audit_commits: 1,
+            pow_commits: 1,
+            average_score: 72.0,
+            first_commit: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            last_commit: DateTime::from_timestamp(1_710_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn issued_credentials_verify_and_resist_tampering() {
+        let issuer = DaemonIdentity::Software(crate::crypto::generate_keypair().0);
+        let subject = PublicKey::Ed25519(crate::crypto::generate_keypair().1);
+        let now = DateTime::from_timestamp(1_720_000_000, 0).unwrap();
+
+        let unsigned = unsigned_credential(&issuer.public_key(), &subject, &summary(), now);
+        let signed = sign_credential(&unsigned.to_string(), &issuer, now).unwrap();
+        let verified = verify_credential(&signed, now).unwrap();
+        assert_eq!(verified.summary, summary());
+        assert_eq!(verified.subject, subject);
+        assert!(!verified.is_self_issued());
+
+        // Inflar el historial invalida la firma
+        let mut forged: Value = serde_json::from_str(&signed).unwrap();
+        forged["credentialSubject"]["verifiedCommits"] = json!(500);
+        assert_eq!(verify_credential(&forged.to_string(), now).unwrap_err(), "bad_signature");
+
+        let later = now + chrono::Duration::days(VALIDITY_DAYS + 1);
+        assert_eq!(verify_credential(&signed, later).unwrap_err(), "expired");
+
+        // El daemon no firma a nombre de otro emisor
+        let other = unsigned_credential(&subject, &subject, &summary(), now);
+        assert!(sign_credential(&other.to_string(), &issuer, now).is_err());
+        assert_eq!(canonical_json(&json!({"b": 2.0, "a": [1.5, "x"]})), r#"{"a":[1.5,"x"],"b":2}"#);
+    }
+
+    #[test]
+    fn summary_stops_counting_after_a_key_is_revoked() {
+        use crate::ticket::{SignedTicket, TicketBinding, TicketMode, TicketPayload, TICKET_TRAILER};
+
+        let dir = crate::testing::TempDir::new("cliff_watch_credential");
+        let repo = Repository::init(&dir).unwrap();
+        let identity = DaemonIdentity::Software(crate::crypto::generate_keypair().0);
+        let key = identity.public_key();
+        let mut trust = TrustConfig::default();
+        trust.add_key("dev", &key.to_hex(), None).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let commit = |trust: &TrustConfig, parent: Option<Oid>| {
+            let blob = repo.blob(trust.to_toml().unwrap().as_bytes()).unwrap();
+            let mut builder = repo.treebuilder(None).unwrap();
+            builder.insert("trust.toml", blob, 0o100644).unwrap();
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let binding = TicketBinding { tree: tree.id().to_string(), parent: parent.map(|p| p.to_string()), author: "dev@example.com".into() };
+            let ticket = SignedTicket::sign(TicketPayload::new(TicketMode::Valid, 5.0, binding, key.key_id()), &identity).unwrap();
+            let message = format!("feat: x\n\n{}: {}\n", TICKET_TRAILER, ticket.to_trailer_value());
+            let parents: Vec<git2::Commit> = parent.map(|p| repo.find_commit(p).unwrap()).into_iter().collect();
+            repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &parents.iter().collect::<Vec<_>>()).unwrap()
+        };
+
+        // Raíz (TOFU) y un hijo que aún confía en la clave y la revoca
+        let root = commit(&trust, None);
+        let revoking = commit(&TrustConfig::default(), Some(root));
+        assert_eq!(summarize(&repo, &key, 100).unwrap().verified_commits, 2);
+        commit(&TrustConfig::default(), Some(revoking));
+        assert_eq!(summarize(&repo, &key, 100).unwrap().verified_commits, 2);
+    }
+
+    #[test]
+    fn only_trusted_issuers_vouch_for_reputation() {
+        let issuer = DaemonIdentity::Software(crate::crypto::generate_keypair().0);
+        let subject = DaemonIdentity::Software(crate::crypto::generate_keypair().0);
+        let now = DateTime::from_timestamp(1_720_000_000, 0).unwrap();
+        let issue = |by: &DaemonIdentity| {
+            let unsigned = unsigned_credential(&by.public_key(), &subject.public_key(), &summary(), now);
+            sign_credential(&unsigned.to_string(), by, now).unwrap()
+        };
+        let trusted = [to_did_key(&issuer.public_key()), to_did_key(&subject.public_key())];
+
+        let vouched = verify_reputation(&issue(&issuer), &subject.public_key(), &trusted, now).unwrap();
+        assert_eq!(vouched.summary.valid_commits, 10);
+        assert_eq!(verify_reputation(&issue(&issuer), &issuer.public_key(), &trusted, now).unwrap_err(), "subject_mismatch");
+        assert_eq!(verify_reputation(&issue(&subject), &subject.public_key(), &trusted, now).unwrap_err(), "self_issued");
+        assert_eq!(verify_reputation(&issue(&issuer), &subject.public_key(), &trusted[1..], now).unwrap_err(), "untrusted_issuer");
+    }
+}
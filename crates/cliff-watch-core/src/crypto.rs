@@ -1,9 +1,12 @@
 pub mod zkp;
 pub mod tpm;
+pub mod keyfile;
+pub mod ssh;
+pub mod did;
 pub use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey};
 use rand::rngs::OsRng;
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::io::{Read, Write};
 #[cfg(unix)]
@@ -40,43 +43,231 @@ pub fn calculate_sha256(data: &[u8]) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
-/// Carga la identidad del daemon desde ~/.config/cliff-watch/daemon.key o crea una nueva
-pub fn load_or_create_identity() -> Result<SigningKey, String> {
-    let home = std::env::var("HOME").map_err(|_| "No env var HOME found")?;
-    let config_dir = PathBuf::from(home).join(".config").join("cliff-watch");
-    let key_path = config_dir.join("daemon.key");
+/// Identificador corto de una clave pública (primeros 8 bytes de su SHA256, en hex)
+pub fn key_id(verifying_key: &VerifyingKey) -> String {
+    PublicKey::Ed25519(*verifying_key).key_id()
+}
+
+/// Clave pública de una identidad firmante.
+///
+/// En hex, el esquema se deduce de la longitud: 32 bytes son Ed25519 y
+/// 33/65 bytes son un punto SEC1 de P-256 (claves residentes en TPM).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKey {
+    Ed25519(VerifyingKey),
+    P256(p256::ecdsa::VerifyingKey),
+}
+
+impl PublicKey {
+    /// Parsea una clave pública en hex (formato de trust.toml)
+    pub fn from_hex(key_hex: &str) -> Result<Self, String> {
+        let bytes = hex::decode(key_hex.trim()).map_err(|_| "Invalid public key encoding".to_string())?;
+        match bytes.len() {
+            32 => {
+                let bytes: [u8; 32] = bytes.try_into().map_err(|_| "Invalid Ed25519 key")?;
+                VerifyingKey::from_bytes(&bytes)
+                    .map(PublicKey::Ed25519)
+                    .map_err(|_| "Invalid Ed25519 key".to_string())
+            }
+            33 | 65 => p256::ecdsa::VerifyingKey::from_sec1_bytes(&bytes)
+                .map(PublicKey::P256)
+                .map_err(|_| "Invalid P-256 key".to_string()),
+            n => Err(format!("Unsupported public key length ({} bytes)", n)),
+        }
+    }
+
+    /// Bytes canónicos: Ed25519 crudo o P-256 SEC1 comprimido
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            PublicKey::Ed25519(key) => key.as_bytes().to_vec(),
+            PublicKey::P256(key) => key.to_encoded_point(true).as_bytes().to_vec(),
+        }
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    /// Identificador corto (primeros 8 bytes del SHA256 de los bytes canónicos, en hex)
+    pub fn key_id(&self) -> String {
+        hex::encode(&calculate_sha256(&self.to_bytes())[..8])
+    }
+
+    /// Nombre del esquema de firma
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            PublicKey::Ed25519(_) => "ed25519",
+            PublicKey::P256(_) => "ecdsa-p256",
+        }
+    }
 
-    if !config_dir.exists() {
-        fs::create_dir_all(&config_dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    /// Verifica una firma de 64 bytes (Ed25519, o `r || s` para ECDSA-P256 sobre SHA256)
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        match self {
+            PublicKey::Ed25519(key) => verify_signature(key, data, signature).unwrap_or(false),
+            PublicKey::P256(key) => p256::ecdsa::Signature::from_slice(signature)
+                .map(|sig| key.verify(data, &sig).is_ok())
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Identidad con la que el daemon firma los tickets
+pub enum DaemonIdentity {
+    /// Clave Ed25519 en `~/.config/cliff-watch/daemon.key`
+    Software(SigningKey),
+    /// Clave ECDSA-P256 persistida en el TPM
+    Tpm(tpm::TpmSigner),
+    /// Clave Ed25519 de ssh-agent (`SSH_AUTH_SOCK`)
+    SshAgent(ssh::SshAgentSigner),
+}
+
+impl DaemonIdentity {
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            DaemonIdentity::Software(key) => PublicKey::Ed25519(key.verifying_key()),
+            DaemonIdentity::Tpm(signer) => PublicKey::P256(*signer.public_key()),
+            DaemonIdentity::SshAgent(signer) => PublicKey::Ed25519(*signer.public_key()),
+        }
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            DaemonIdentity::Software(key) => sign_data(key, data),
+            DaemonIdentity::Tpm(signer) => signer.sign(data),
+            DaemonIdentity::SshAgent(signer) => signer.sign(data),
+        }
     }
 
+    /// Backend de la identidad (`file`, `tpm` o `ssh-agent`), tal como se configura
+    pub fn backend(&self) -> &'static str {
+        match self {
+            DaemonIdentity::Software(_) => "file",
+            DaemonIdentity::Tpm(_) => "tpm",
+            DaemonIdentity::SshAgent(_) => "ssh-agent",
+        }
+    }
+}
+
+/// Carga la identidad del daemon según la sección `[identity]` de la configuración.
+///
+/// `ssh` firma con la clave OpenSSH de `identity.ssh_key` (sin cifrar);
+/// `ssh-agent` delega en el agente, usando la clave pública `identity.ssh_key`
+/// (`.pub`) para elegir identidad o, sin ella, la primera Ed25519 cargada.
+pub fn load_daemon_identity(config: &crate::config::IdentityConfig) -> Result<DaemonIdentity, String> {
+    match config.backend.as_str() {
+        "file" => load_or_create_identity().map(DaemonIdentity::Software),
+        "tpm" => tpm::TpmSigner::load_or_create(config.tpm_handle).map(DaemonIdentity::Tpm),
+        "ssh" => {
+            let path = config.ssh_key_path().unwrap_or_else(|| expand_home("~/.ssh/id_ed25519"));
+            ssh::read_private_key(&path).map(DaemonIdentity::Software)
+        }
+        "ssh-agent" => {
+            let wanted = match config.ssh_key_path() {
+                Some(path) => {
+                    let path = if path.extension().is_some_and(|e| e == "pub") { path } else { path.with_extension("pub") };
+                    let line = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                    Some(ssh::parse_public_key(&line)?)
+                }
+                None => None,
+            };
+            ssh::SshAgentSigner::from_env(wanted.as_ref()).map(DaemonIdentity::SshAgent)
+        }
+        other => Err(format!("Unknown identity backend '{}' (expected 'file', 'tpm', 'ssh' or 'ssh-agent')", other)),
+    }
+}
+
+/// Expande un `~/` inicial con `$HOME`
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Ruta de la clave del daemon: ~/.config/cliff-watch/daemon.key
+pub fn daemon_key_path() -> Result<PathBuf, String> {
+    let home = std::env::var("HOME").map_err(|_| "No env var HOME found")?;
+    Ok(PathBuf::from(home).join(".config").join("cliff-watch").join("daemon.key"))
+}
+
+/// Carga la identidad del daemon desde ~/.config/cliff-watch/daemon.key o crea una nueva.
+///
+/// La passphrase se toma del entorno (ver `keyfile::passphrase_from_env`). Si existe,
+/// la clave se guarda cifrada (también una legacy en claro); una clave cifrada exige la passphrase.
+pub fn load_or_create_identity() -> Result<SigningKey, String> {
+    let passphrase = keyfile::passphrase_from_env()?;
+    load_or_create_identity_at(&daemon_key_path()?, passphrase.as_deref().map(|p| p.as_str()))
+}
+
+/// Igual que `load_or_create_identity`, con ruta y passphrase explícitas
+pub fn load_or_create_identity_at(key_path: &Path, passphrase: Option<&str>) -> Result<SigningKey, String> {
     if key_path.exists() {
-        let mut file = fs::File::open(&key_path).map_err(|e| format!("Failed to open key file: {}", e))?;
-        let mut bytes = Vec::new();
-        file.read_to_end(&mut bytes).map_err(|e| format!("Failed to read key file: {}", e))?;
-        
-        if bytes.len() != 32 {
-            return Err("Invalid key file size (expected 32 bytes)".to_string());
+        let signing_key = read_identity(key_path, passphrase)?;
+        // Una clave legacy en claro se cifra en cuanto hay passphrase disponible
+        if passphrase.is_some() && !identity_is_encrypted(key_path) {
+            write_identity(key_path, &signing_key, passphrase)?;
         }
+        return Ok(signing_key);
+    }
+
+    let (signing_key, _) = generate_keypair();
+    write_identity(key_path, &signing_key, passphrase)?;
+    Ok(signing_key)
+}
+
+/// Indica si el fichero de clave existe y está cifrado
+pub fn identity_is_encrypted(key_path: &Path) -> bool {
+    fs::read(key_path).map(|b| keyfile::is_encrypted(&b)).unwrap_or(false)
+}
+
+/// Lee una clave cifrada o cruda (legacy, 32 bytes)
+pub fn read_identity(key_path: &Path, passphrase: Option<&str>) -> Result<SigningKey, String> {
+    let mut file = fs::File::open(key_path).map_err(|e| format!("Failed to open key file: {}", e))?;
+    let mut bytes = zeroize::Zeroizing::new(Vec::new());
+    file.read_to_end(&mut bytes).map_err(|e| format!("Failed to read key file: {}", e))?;
+
+    if keyfile::is_encrypted(&bytes) {
+        let passphrase = passphrase.ok_or_else(|| format!(
+            "Daemon key is encrypted: set {} or {}, or start it with `cliff-watch on`",
+            keyfile::PASSPHRASE_ENV,
+            keyfile::PASSPHRASE_FD_ENV
+        ))?;
+        let seed = keyfile::open_seed(&bytes, passphrase)?;
+        return Ok(SigningKey::from_bytes(&seed));
+    }
 
-        let key_bytes: [u8; 32] = bytes.try_into().map_err(|_| "Failed to parse key bytes")?;
-        Ok(SigningKey::from_bytes(&key_bytes))
-    } else {
-        let (signing_key, _) = generate_keypair();
-        let bytes = signing_key.to_bytes();
-        
-        let mut file = fs::File::create(&key_path).map_err(|e| format!("Failed to create key file: {}", e))?;
-        
-        #[cfg(unix)]
-        {
-            let mut perms = file.metadata().map_err(|e| e.to_string())?.permissions();
-            perms.set_mode(0o600);
-            file.set_permissions(perms).map_err(|e| e.to_string())?;
+    if bytes.len() != 32 {
+        return Err("Invalid key file size (expected 32 bytes)".to_string());
+    }
+
+    let key_bytes: [u8; 32] = bytes.as_slice().try_into().map_err(|_| "Failed to parse key bytes")?;
+    Ok(SigningKey::from_bytes(&key_bytes))
+}
+
+/// Escribe la clave (cifrada si hay passphrase) con permisos 0600
+pub fn write_identity(key_path: &Path, signing_key: &SigningKey, passphrase: Option<&str>) -> Result<(), String> {
+    let bytes = match passphrase {
+        Some(p) => keyfile::seal_seed(&signing_key.to_bytes(), p, keyfile::KdfParams::default())?,
+        None => signing_key.to_bytes().to_vec(),
+    };
+
+    if let Some(dir) = key_path.parent() {
+        if !dir.exists() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
         }
+    }
+
+    let mut file = fs::File::create(key_path).map_err(|e| format!("Failed to create key file: {}", e))?;
 
-        file.write_all(&bytes).map_err(|e| format!("Failed to write key file: {}", e))?;
-        Ok(signing_key)
+    #[cfg(unix)]
+    {
+        let mut perms = file.metadata().map_err(|e| e.to_string())?.permissions();
+        perms.set_mode(0o600);
+        file.set_permissions(perms).map_err(|e| e.to_string())?;
     }
+
+    file.write_all(&bytes).map_err(|e| format!("Failed to write key file: {}", e))
 }
 
 #[cfg(test)]
@@ -92,6 +283,35 @@ mod tests {
         assert!(is_valid, "Signature should be valid");
     }
     
+    #[test]
+    fn p256_public_key_verifies_and_normalizes() {
+        use p256::ecdsa::signature::Signer as _;
+
+        let signing_key = p256::ecdsa::SigningKey::random(&mut OsRng);
+        let uncompressed = hex::encode(signing_key.verifying_key().to_encoded_point(false).as_bytes());
+        let public_key = PublicKey::from_hex(&uncompressed).unwrap();
+        assert_eq!(public_key.scheme(), "ecdsa-p256");
+        assert_eq!(PublicKey::from_hex(&public_key.to_hex()).unwrap().key_id(), public_key.key_id());
+
+        let signature: p256::ecdsa::Signature = signing_key.sign(b"ticket");
+        assert!(public_key.verify(b"ticket", &signature.to_bytes()));
+        assert!(!public_key.verify(b"forged", &signature.to_bytes()));
+    }
+
+    #[test]
+    fn encrypted_identity_requires_passphrase() {
+        let path = std::env::temp_dir().join(format!("daemon_key_{}", hex::encode(rand::random::<[u8; 8]>())));
+        let (created, _) = generate_keypair();
+        let fast = keyfile::KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+        fs::write(&path, keyfile::seal_seed(&created.to_bytes(), "s3cret", fast).unwrap()).unwrap();
+        assert!(identity_is_encrypted(&path));
+
+        assert!(read_identity(&path, None).is_err());
+        assert!(read_identity(&path, Some("wrong")).is_err());
+        assert_eq!(read_identity(&path, Some("s3cret")).unwrap().to_bytes(), created.to_bytes());
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn test_sha256() {
         let data = b"test";
@@ -0,0 +1,116 @@
+//! Identidades `did:key`
+//!
+//! La clave pública del daemon expresada como DID autocontenido
+//! (<https://w3c-ccg.github.io/did-method-key/>): multicodec de la clave en
+//! multibase base58btc. Ed25519 usa el código `0xed` (`did:key:z6Mk…`) y
+//! P-256 el `0x1200` con el punto comprimido (`did:key:zDn…`).
+
+use super::PublicKey;
+
+const DID_KEY_PREFIX: &str = "did:key:";
+/// Varint de los códigos multicodec `ed25519-pub` y `p256-pub`
+const ED25519_CODEC: [u8; 2] = [0xed, 0x01];
+const P256_CODEC: [u8; 2] = [0x80, 0x24];
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// `did:key` de una clave pública
+pub fn to_did_key(key: &PublicKey) -> String {
+    format!("{}{}", DID_KEY_PREFIX, multibase_key(key))
+}
+
+/// Método de verificación (`did:key:z…#z…`) con el que se firman credenciales
+pub fn verification_method(key: &PublicKey) -> String {
+    format!("{}#{}", to_did_key(key), multibase_key(key))
+}
+
+/// Clave pública de un `did:key` (admite el fragmento del método de verificación)
+pub fn from_did_key(did: &str) -> Result<PublicKey, String> {
+    let id = did.split('#').next().unwrap_or(did);
+    let multibase = id.strip_prefix(DID_KEY_PREFIX).ok_or_else(|| format!("Not a did:key identifier: {}", did))?;
+    let bytes = multibase_decode(multibase)?;
+    match (bytes.get(..2), bytes.get(2..)) {
+        (Some(codec), Some(key)) if codec == ED25519_CODEC && key.len() == 32 => PublicKey::from_hex(&hex::encode(key)),
+        (Some(codec), Some(key)) if codec == P256_CODEC && key.len() == 33 => PublicKey::from_hex(&hex::encode(key)),
+        _ => Err("Unsupported did:key type (expected Ed25519 or P-256)".to_string()),
+    }
+}
+
+fn multibase_key(key: &PublicKey) -> String {
+    let codec = match key {
+        PublicKey::Ed25519(_) => ED25519_CODEC,
+        PublicKey::P256(_) => P256_CODEC,
+    };
+    multibase_encode(&[&codec[..], &key.to_bytes()].concat())
+}
+
+/// Multibase base58btc (prefijo `z`)
+pub fn multibase_encode(bytes: &[u8]) -> String {
+    format!("z{}", base58_encode(bytes))
+}
+
+pub fn multibase_decode(value: &str) -> Result<Vec<u8>, String> {
+    let encoded = value.strip_prefix('z').ok_or("Unsupported multibase encoding (expected base58btc)")?;
+    base58_decode(encoded)
+}
+
+fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    // Dígitos en base 58, el menos significativo primero
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    std::iter::repeat_n('1', zeros)
+        .chain(digits.iter().rev().map(|d| BASE58_ALPHABET[*d as usize] as char))
+        .collect()
+}
+
+fn base58_decode(encoded: &str) -> Result<Vec<u8>, String> {
+    let zeros = encoded.bytes().take_while(|c| *c == b'1').count();
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in encoded.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| *a == c).ok_or("Invalid base58 character")? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    Ok(std::iter::repeat_n(0, zeros).chain(bytes.into_iter().rev()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn did_key_matches_the_spec_vectors() {
+        // Vectores de la especificación did:key
+        let did = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+        let key = from_did_key(did).unwrap();
+        assert_eq!(key.to_hex(), "3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29");
+        assert_eq!(to_did_key(&key), did);
+        assert_eq!(verification_method(&key), format!("{}#{}", did, &did[8..]));
+
+        // Vector P-256
+        let did = "did:key:zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169";
+        assert_eq!(to_did_key(&from_did_key(did).unwrap()), did);
+
+        assert_eq!(base58_decode(&base58_encode(&[0, 0, 1, 2, 255])).unwrap(), vec![0, 0, 1, 2, 255]);
+        assert!(from_did_key("did:web:example.com").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Formato cifrado de la clave del daemon en reposo
+//!
+//! ```text
+//! magic "CWKEY\0" | version u8 | m_cost u32 | t_cost u32 | p_cost u32 | salt[16] | nonce[24] | ciphertext
+//! ```
+//! Los enteros van en little-endian. La clave de cifrado se deriva de la
+//! passphrase con Argon2id y la semilla Ed25519 se sella con
+//! XChaCha20-Poly1305 usando la cabecera completa como datos asociados, de
+//! modo que alterar los parámetros del KDF invalida el fichero.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+/// Prefijo que distingue un fichero cifrado de la semilla cruda legacy (32 bytes)
+pub const KEYFILE_MAGIC: &[u8; 6] = b"CWKEY\0";
+
+/// Versión actual del formato
+pub const KEYFILE_VERSION: u8 = 1;
+
+/// Variable de entorno con la passphrase de la clave del daemon
+pub const PASSPHRASE_ENV: &str = "CLIFF_WATCH_KEY_PASSPHRASE";
+
+/// Variable de entorno con un descriptor de fichero del que leer la passphrase
+pub const PASSPHRASE_FD_ENV: &str = "CLIFF_WATCH_KEY_PASSPHRASE_FD";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = KEYFILE_MAGIC.len() + 1 + 12 + SALT_LEN + NONCE_LEN;
+
+/// Coste de Argon2id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memoria en KiB
+    pub m_cost: u32,
+    /// Iteraciones
+    pub t_cost: u32,
+    /// Paralelismo
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// 64 MiB, 3 pasadas, 1 hilo (recomendación OWASP para Argon2id)
+    fn default() -> Self {
+        Self { m_cost: 64 * 1024, t_cost: 3, p_cost: 1 }
+    }
+}
+
+/// Indica si los bytes tienen el formato cifrado
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(KEYFILE_MAGIC)
+}
+
+/// Cifra una semilla Ed25519 con una passphrase
+pub fn seal_seed(seed: &[u8; 32], passphrase: &str, params: KdfParams) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut out = Vec::with_capacity(HEADER_LEN + 48);
+    out.extend_from_slice(KEYFILE_MAGIC);
+    out.push(KEYFILE_VERSION);
+    out.extend_from_slice(&params.m_cost.to_le_bytes());
+    out.extend_from_slice(&params.t_cost.to_le_bytes());
+    out.extend_from_slice(&params.p_cost.to_le_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+
+    let key = derive_key(passphrase, &salt, params)?;
+    let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: seed, aad: &out })
+        .map_err(|_| "Key encryption failed".to_string())?;
+
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Descifra una semilla Ed25519 sellada con `seal_seed`
+pub fn open_seed(bytes: &[u8], passphrase: &str) -> Result<Zeroizing<[u8; 32]>, String> {
+    if !is_encrypted(bytes) || bytes.len() < HEADER_LEN {
+        return Err("Not an encrypted cliff-watch key file".to_string());
+    }
+    let version = bytes[KEYFILE_MAGIC.len()];
+    if version != KEYFILE_VERSION {
+        return Err(format!("Unsupported key file version {}", version));
+    }
+
+    let (header, ciphertext) = bytes.split_at(HEADER_LEN);
+    let field = |i: usize| {
+        let start = KEYFILE_MAGIC.len() + 1 + i * 4;
+        u32::from_le_bytes([header[start], header[start + 1], header[start + 2], header[start + 3]])
+    };
+    let params = KdfParams { m_cost: field(0), t_cost: field(1), p_cost: field(2) };
+    let salt = &header[KEYFILE_MAGIC.len() + 13..KEYFILE_MAGIC.len() + 13 + SALT_LEN];
+    let nonce = &header[HEADER_LEN - NONCE_LEN..];
+
+    let key = derive_key(passphrase, salt, params)?;
+    let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+    let seed = Zeroizing::new(
+        cipher
+            .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+            .map_err(|_| "Wrong passphrase or corrupted key file".to_string())?,
+    );
+
+    let mut out = Zeroizing::new([0u8; 32]);
+    if seed.len() != out.len() {
+        return Err("Invalid key size in key file".to_string());
+    }
+    out.copy_from_slice(&seed);
+    Ok(out)
+}
+
+/// Passphrase aportada por el entorno: `CLIFF_WATCH_KEY_PASSPHRASE` o el
+/// descriptor indicado en `CLIFF_WATCH_KEY_PASSPHRASE_FD` (primera línea).
+pub fn passphrase_from_env() -> Result<Option<Zeroizing<String>>, String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Some(Zeroizing::new(passphrase)));
+    }
+
+    let fd = match std::env::var(PASSPHRASE_FD_ENV) {
+        Ok(fd) => fd.trim().parse::<u32>().map_err(|_| format!("Invalid {}", PASSPHRASE_FD_ENV))?,
+        Err(_) => return Ok(None),
+    };
+
+    let content = Zeroizing::new(
+        std::fs::read_to_string(format!("/dev/fd/{}", fd))
+            .map_err(|e| format!("Failed to read passphrase from fd {}: {}", fd, e))?,
+    );
+    Ok(Some(Zeroizing::new(content.lines().next().unwrap_or("").to_string())))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: KdfParams) -> Result<Zeroizing<[u8; 32]>, String> {
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| format!("Invalid KDF parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parámetros mínimos para que los tests no paguen 64 MiB por derivación
+    const FAST: KdfParams = KdfParams { m_cost: 256, t_cost: 1, p_cost: 1 };
+
+    #[test]
+    fn seal_open_roundtrip() {
+        let seed = [7u8; 32];
+        let sealed = seal_seed(&seed, "correct horse", FAST).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(32).any(|w| w == seed), "The seed must not appear in clear");
+
+        assert_eq!(*open_seed(&sealed, "correct horse").unwrap(), seed);
+        assert!(open_seed(&sealed, "wrong").is_err());
+    }
+
+    #[test]
+    fn header_is_authenticated() {
+        let mut sealed = seal_seed(&[1u8; 32], "pw", FAST).unwrap();
+        // Cambiar t_cost en la cabecera debe romper la autenticación
+        sealed[KEYFILE_MAGIC.len() + 5] ^= 0x02;
+        assert!(open_seed(&sealed, "pw").is_err());
+    }
+}
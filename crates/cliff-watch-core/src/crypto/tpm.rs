@@ -1,9 +1,32 @@
 use tss_esapi::{Context, TctiNameConf};
-use tss_esapi::interface_types::resource_handles::Hierarchy;
-use tss_esapi::structures::SymmetricDefinition;
-use tss_esapi::attributes::SessionAttributesBuilder;
+use tss_esapi::constants::tss::{TPM2_RH_NULL, TPM2_ST_HASHCHECK};
+use tss_esapi::handles::{KeyHandle, ObjectHandle, PersistentTpmHandle, TpmHandle};
+use tss_esapi::interface_types::algorithm::HashingAlgorithm;
+use tss_esapi::interface_types::dynamic_handles::Persistent;
+use tss_esapi::interface_types::ecc::EccCurve;
+use tss_esapi::interface_types::resource_handles::{Hierarchy, Provision};
+use tss_esapi::interface_types::session_handles::AuthSession;
+use tss_esapi::structures::{Digest, EccScheme, HashScheme, HashcheckTicket, Signature, SignatureScheme};
+use tss_esapi::tss2_esys::TPMT_TK_HASHCHECK;
+use tss_esapi::utils::{create_unrestricted_signing_ecc_public, PublicKey as TpmPublicKey};
+use std::convert::TryFrom;
 use std::sync::{Arc, Mutex};
 
+/// Handle persistente por defecto para la identidad del daemon (rango del owner, 0x81000000-0x8100FFFF).
+///
+/// Se evita 0x81010001, reservado por TCG para la Endorsement Key.
+pub const DEFAULT_IDENTITY_HANDLE: u32 = 0x8100_0C1F;
+
+/// Abre un contexto ESAPI usando `TCTI`/`TPM2TOOLS_TCTI` si están definidas (p.ej. swtpm),
+/// o el dispositivo por defecto en caso contrario.
+fn open_context() -> Result<Context, String> {
+    let tcti = TctiNameConf::from_environment_variable()
+        .unwrap_or(TctiNameConf::Device(Default::default()));
+
+    Context::new(tcti)
+        .map_err(|e| format!("TPM Connection failed: {}. Asegúrate de que tpm2-abrmd esté corriendo.", e))
+}
+
 /// Abstracción para el chip TPM 2.0
 pub struct TpmWitness {
     context: Arc<Mutex<Context>>,
@@ -12,12 +35,8 @@ pub struct TpmWitness {
 impl TpmWitness {
     /// Intenta inicializar una conexión con el TPM
     pub fn new() -> Result<Self, String> {
-        let tcti = TctiNameConf::from_environment_variable()
-            .unwrap_or(TctiNameConf::Device(Default::default()));
-        
-        let context = Context::new(tcti)
-            .map_err(|e| format!("TPM Connection failed: {}. Asegúrate de que tpm2-abrmd esté corriendo.", e))?;
-            
+        let context = open_context()?;
+
         Ok(Self {
             context: Arc::new(Mutex::new(context)),
         })
@@ -26,21 +45,173 @@ impl TpmWitness {
     /// Genera un sello de hardware para un hash de commit
     pub fn sign_evidence(&self, _data: &[u8]) -> Result<Vec<u8>, String> {
         let mut context = self.context.lock().map_err(|_| "TPM Mutex poisoned")?;
-        
-        // En una implementación real v3.0, usaríamos una clave persistente del TPM
-        // Por ahora, simulamos el flujo de certificación para validar la ruta.
-        // TODO: Implementar persistencia de Primary Key en el TPM para Cliff-Watch
-        
-        // Fallback: Si el TPM está presente pero no configurado, usamos la API para 
-        // obtener entropía física real del chip para fortalecer el score.
+
+        // La firma de tickets vive en `TpmSigner`; aquí solo aportamos
+        // entropía física real del chip para fortalecer el score.
         let random_bytes = context.get_random(32)
             .map_err(|e| format!("TPM Random failed: {}", e))?;
-            
+
         Ok(random_bytes.to_vec())
     }
 }
 
+/// Identidad ECDSA-P256 residente en el TPM.
+///
+/// La clave es primaria bajo la jerarquía Owner y queda persistida en `handle`:
+/// la parte privada nunca sale del chip. Como `Context` no es `Send`, cada firma
+/// abre su propio contexto; solo se guardan el handle y la clave pública.
+#[derive(Debug, Clone)]
+pub struct TpmSigner {
+    handle: u32,
+    public_key: p256::ecdsa::VerifyingKey,
+}
+
+impl TpmSigner {
+    /// Carga la clave persistida en `handle`
+    pub fn load(handle: u32) -> Result<Self, String> {
+        let mut context = open_context()?;
+        let key_handle = load_persistent(&mut context, handle)?;
+        let public_key = read_public_key(&mut context, key_handle)?;
+        Ok(Self { handle, public_key })
+    }
+
+    /// Carga la clave persistida en `handle` o, si no existe, la crea y la persiste
+    pub fn load_or_create(handle: u32) -> Result<Self, String> {
+        if let Ok(signer) = Self::load(handle) {
+            return Ok(signer);
+        }
+
+        let mut context = open_context()?;
+        context.set_sessions((Some(AuthSession::Password), None, None));
+
+        let public = create_unrestricted_signing_ecc_public(
+            EccScheme::EcDsa(HashScheme::new(HashingAlgorithm::Sha256)),
+            EccCurve::NistP256,
+        ).map_err(|e| format!("TPM key template failed: {}", e))?;
+
+        let primary = context
+            .create_primary(Hierarchy::Owner, public, None, None, None, None)
+            .map_err(|e| format!("TPM CreatePrimary failed: {}", e))?;
+
+        let persistent = Persistent::Persistent(persistent_handle(handle)?);
+        let evicted = context.evict_control(Provision::Owner, ObjectHandle::from(primary.key_handle), persistent);
+        let _ = context.flush_context(ObjectHandle::from(primary.key_handle));
+        evicted.map_err(|e| format!("TPM EvictControl failed (handle 0x{:08x}): {}", handle, e))?;
+
+        Self::load(handle)
+    }
+
+    /// Handle persistente donde vive la clave
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Clave pública P-256 de la identidad
+    pub fn public_key(&self) -> &p256::ecdsa::VerifyingKey {
+        &self.public_key
+    }
+
+    /// Firma `data` (SHA256 + ECDSA-P256 en el TPM).
+    ///
+    /// Devuelve `r || s` (64 bytes) con `s` normalizado a la mitad baja del orden.
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut context = open_context()?;
+        context.set_sessions((Some(AuthSession::Password), None, None));
+        let key_handle = load_persistent(&mut context, self.handle)?;
+
+        let digest = Digest::try_from(super::calculate_sha256(data))
+            .map_err(|e| format!("TPM digest failed: {}", e))?;
+        let validation = HashcheckTicket::try_from(TPMT_TK_HASHCHECK {
+            tag: TPM2_ST_HASHCHECK,
+            hierarchy: TPM2_RH_NULL,
+            digest: Default::default(),
+        }).map_err(|e| format!("TPM hashcheck ticket failed: {}", e))?;
+
+        let signature = context
+            .sign(key_handle, digest, SignatureScheme::Null, validation)
+            .map_err(|e| format!("TPM Sign failed: {}", e))?;
+
+        let ecc = match signature {
+            Signature::EcDsa(ecc) => ecc,
+            _ => return Err("TPM returned a non-ECDSA signature".to_string()),
+        };
+
+        let r = left_pad_32(ecc.signature_r().value())?;
+        let s = left_pad_32(ecc.signature_s().value())?;
+        let signature = p256::ecdsa::Signature::from_scalars(r, s)
+            .map_err(|e| format!("Invalid TPM signature: {}", e))?;
+        let signature = signature.normalize_s().unwrap_or(signature);
+        Ok(signature.to_bytes().to_vec())
+    }
+}
+
+fn persistent_handle(handle: u32) -> Result<PersistentTpmHandle, String> {
+    PersistentTpmHandle::new(handle).map_err(|_| format!("Invalid persistent handle 0x{:08x}", handle))
+}
+
+fn load_persistent(context: &mut Context, handle: u32) -> Result<KeyHandle, String> {
+    let object = context
+        .tr_from_tpm_public(TpmHandle::Persistent(persistent_handle(handle)?))
+        .map_err(|e| format!("No TPM key at handle 0x{:08x}: {}", handle, e))?;
+    Ok(KeyHandle::from(object))
+}
+
+fn read_public_key(context: &mut Context, key_handle: KeyHandle) -> Result<p256::ecdsa::VerifyingKey, String> {
+    let (public, _, _) = context
+        .read_public(key_handle)
+        .map_err(|e| format!("TPM ReadPublic failed: {}", e))?;
+
+    match TpmPublicKey::try_from(public).map_err(|e| format!("TPM public key: {}", e))? {
+        TpmPublicKey::Ecc { x, y } => {
+            let mut sec1 = vec![0x04];
+            sec1.extend_from_slice(&left_pad_32(&x)?);
+            sec1.extend_from_slice(&left_pad_32(&y)?);
+            p256::ecdsa::VerifyingKey::from_sec1_bytes(&sec1)
+                .map_err(|e| format!("TPM key is not a P-256 point: {}", e))
+        }
+        TpmPublicKey::Rsa(_) => Err("TPM key at handle is RSA, expected ECDSA-P256".to_string()),
+    }
+}
+
+/// El TPM puede omitir ceros iniciales en los enteros; P-256 espera 32 bytes fijos
+fn left_pad_32(bytes: &[u8]) -> Result<p256::FieldBytes, String> {
+    if bytes.len() > 32 {
+        return Err("TPM integer longer than 32 bytes".to_string());
+    }
+    let mut out = p256::FieldBytes::default();
+    out[32 - bytes.len()..].copy_from_slice(bytes);
+    Ok(out)
+}
+
 /// Verifica si el hardware soporta el blindaje de Cliff-Watch
 pub fn is_tpm_available() -> bool {
     TpmWitness::new().is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Verifier;
+
+    #[test]
+    fn left_pad_restores_fixed_width() {
+        let padded = left_pad_32(&[0x01, 0x02]).unwrap();
+        assert_eq!(padded[30..], [0x01, 0x02]);
+        assert!(padded[..30].iter().all(|b| *b == 0));
+        assert!(left_pad_32(&[0u8; 33]).is_err());
+    }
+
+    /// Requiere un TPM (o swtpm): `TCTI=swtpm:port=2321 cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn persistent_key_signs_verifiable_tickets() {
+        let signer = TpmSigner::load_or_create(DEFAULT_IDENTITY_HANDLE).unwrap();
+        let reloaded = TpmSigner::load(DEFAULT_IDENTITY_HANDLE).unwrap();
+        assert_eq!(signer.public_key(), reloaded.public_key(), "La clave persistida debe sobrevivir a la recarga");
+
+        let signature = reloaded.sign(b"payload").unwrap();
+        let signature = p256::ecdsa::Signature::from_slice(&signature).unwrap();
+        assert!(signer.public_key().verify(b"payload", &signature).is_ok());
+        assert!(signer.public_key().verify(b"other", &signature).is_err());
+    }
+}
@@ -4,29 +4,89 @@ use rand::thread_rng;
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::CompressedRistretto;
 
+/// Umbral (en %) que el daemon prueba por defecto: Human Score >= 0.5
+pub const HUMANITY_THRESHOLD_PERCENT: u64 = 50;
+
+/// Versión del formato serializado de la prueba
+pub const PROOF_VERSION: u32 = 1;
+
+/// Máximo de pruebas que se agregan en una sola (capacidad de los generadores)
+pub const MAX_AGGREGATED_PROOFS: usize = 256;
+
+/// Apertura de un compromiso: el valor probado (score - umbral) y su blinding.
+///
+/// Permite agregar la prueba más tarde (ver `crate::certificate`). Revela el
+/// score, así que solo se guarda en la máquina del daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofOpening {
+    pub value: u64,
+    pub blinding: Scalar,
+}
+
+impl ProofOpening {
+    /// Formato: `value=<n>;blinding=<hex>`
+    pub fn encode(&self) -> String {
+        format!("value={};blinding={}", self.value, hex::encode(self.blinding.to_bytes()))
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, String> {
+        let mut value = None;
+        let mut blinding = None;
+        for part in encoded.trim().split(';') {
+            match part.split_once('=') {
+                Some(("value", v)) => value = v.parse().ok(),
+                Some(("blinding", v)) => {
+                    let bytes: [u8; 32] = hex::decode(v).ok().and_then(|b| b.try_into().ok()).ok_or("Invalid blinding encoding")?;
+                    blinding = Option::from(Scalar::from_canonical_bytes(bytes));
+                }
+                _ => return Err(format!("Malformed opening field '{}'", part)),
+            }
+        }
+        match (value, blinding) {
+            (Some(value), Some(blinding)) => Ok(Self { value, blinding }),
+            _ => Err("Incomplete proof opening".to_string()),
+        }
+    }
+
+    /// Compromiso de Pedersen que abre esta apertura
+    pub fn commitment(&self) -> CompressedRistretto {
+        PedersenGens::default().commit(Scalar::from(self.value), self.blinding).compress()
+    }
+}
+
 /// Representa una prueba de rango ZKP que demuestra que un Score de Humanidad
 /// está por encima de un umbral sin revelar el score exacto.
 pub struct HumanityProof {
     pub commitment: CompressedRistretto,
     pub proof: RangeProof,
+    /// Umbral probado (el verificador lo necesita para reconstruir el transcript)
+    pub threshold_percent: u64,
 }
 
 impl HumanityProof {
     /// Genera una prueba de rango para un score dado.
-    pub fn generate(score_percent: u64, threshold_percent: u64) -> Result<Self, String> {
+    ///
+    /// `context` separa el dominio del transcript (p.ej. el OID del árbol staged),
+    /// de modo que una prueba no pueda trasplantarse a otro commit.
+    pub fn generate(score_percent: u64, threshold_percent: u64, context: &[u8]) -> Result<Self, String> {
+        Self::generate_with_opening(score_percent, threshold_percent, context).map(|(proof, _)| proof)
+    }
+
+    /// Como `generate`, devolviendo además la apertura del compromiso
+    pub fn generate_with_opening(score_percent: u64, threshold_percent: u64, context: &[u8]) -> Result<(Self, ProofOpening), String> {
         let pc_gens = PedersenGens::default();
         let bp_gens = BulletproofGens::new(64, 1);
-        
+
         if score_percent < threshold_percent {
             return Err("Score below threshold, cannot generate proof".into());
         }
-        
+
         let secret_value = score_percent - threshold_percent;
         let mut rng = thread_rng();
-        let mut transcript = Transcript::new(b"cliff-watch-pohw");
-        
+        let mut transcript = Self::transcript(threshold_percent, context);
+
         let blinding = Scalar::random(&mut rng);
-        
+
         let (proof, commitment) = RangeProof::prove_single(
             &bp_gens,
             &pc_gens,
@@ -35,19 +95,23 @@ impl HumanityProof {
             &blinding,
             64,
         ).map_err(|e| format!("ZKP Proof generation failed: {}", e))?;
-        
-        Ok(Self {
-            commitment,
-            proof,
-        })
+
+        Ok((
+            Self {
+                commitment,
+                proof,
+                threshold_percent,
+            },
+            ProofOpening { value: secret_value, blinding },
+        ))
     }
 
-    /// Verifica si la prueba es válida.
-    pub fn verify(&self) -> Result<(), String> {
+    /// Verifica si la prueba es válida para el mismo `context` usado al generarla.
+    pub fn verify(&self, context: &[u8]) -> Result<(), String> {
         let pc_gens = PedersenGens::default();
         let bp_gens = BulletproofGens::new(64, 1);
-        let mut transcript = Transcript::new(b"cliff-watch-pohw");
-        
+        let mut transcript = Self::transcript(self.threshold_percent, context);
+
         self.proof.verify_single(
             &bp_gens,
             &pc_gens,
@@ -55,7 +119,127 @@ impl HumanityProof {
             &self.commitment,
             64,
         ).map_err(|e| format!("ZKP Verification failed: {}", e))?;
-        
+
         Ok(())
     }
+
+    /// Serializa la prueba para el trailer `Cliff-Watch-Proof`
+    ///
+    /// Formato: `v=1;threshold=<pct>;commitment=<hex>;proof=<hex>`
+    pub fn to_trailer_value(&self) -> String {
+        format!(
+            "v={};threshold={};commitment={};proof={}",
+            PROOF_VERSION,
+            self.threshold_percent,
+            hex::encode(self.commitment.as_bytes()),
+            hex::encode(self.proof.to_bytes()),
+        )
+    }
+
+    /// Parsea el valor de un trailer `Cliff-Watch-Proof`
+    pub fn parse_trailer_value(value: &str) -> Result<Self, String> {
+        let mut fields = std::collections::HashMap::new();
+        for part in value.trim().split(';') {
+            let (k, v) = part.split_once('=').ok_or_else(|| format!("Malformed proof field '{}'", part))?;
+            fields.insert(k, v);
+        }
+        let get = |k: &str| fields.get(k).copied().ok_or_else(|| format!("Proof field '{}' missing", k));
+
+        if get("v")? != PROOF_VERSION.to_string() {
+            return Err("Unsupported proof version".to_string());
+        }
+
+        let threshold_percent = get("threshold")?.parse().map_err(|_| "Invalid proof threshold".to_string())?;
+        let commitment_bytes = hex::decode(get("commitment")?).map_err(|_| "Invalid commitment encoding".to_string())?;
+        let commitment = CompressedRistretto::from_slice(&commitment_bytes).map_err(|_| "Invalid commitment length".to_string())?;
+        let proof_bytes = hex::decode(get("proof")?).map_err(|_| "Invalid proof encoding".to_string())?;
+        let proof = RangeProof::from_bytes(&proof_bytes).map_err(|e| format!("Invalid range proof: {}", e))?;
+
+        Ok(Self {
+            commitment,
+            proof,
+            threshold_percent,
+        })
+    }
+
+    fn transcript(threshold_percent: u64, context: &[u8]) -> Transcript {
+        let mut transcript = Transcript::new(b"cliff-watch-pohw");
+        transcript.append_u64(b"threshold", threshold_percent);
+        transcript.append_message(b"context", context);
+        transcript
+    }
+}
+
+/// Prueba agregada de que cada apertura es un valor de 64 bits (score >= umbral).
+///
+/// Las aperturas se rellenan con ceros hasta una potencia de dos; el
+/// verificador repite el relleno con el compromiso identidad.
+pub fn prove_aggregate(openings: &[ProofOpening], transcript: &mut Transcript) -> Result<RangeProof, String> {
+    let m = aggregate_size(openings.len())?;
+    let mut values: Vec<u64> = openings.iter().map(|o| o.value).collect();
+    let mut blindings: Vec<Scalar> = openings.iter().map(|o| o.blinding).collect();
+    values.resize(m, 0);
+    blindings.resize(m, Scalar::ZERO);
+
+    let (proof, _) = RangeProof::prove_multiple(&BulletproofGens::new(64, m), &PedersenGens::default(), transcript, &values, &blindings, 64)
+        .map_err(|e| format!("Aggregated proof generation failed: {}", e))?;
+    Ok(proof)
+}
+
+/// Verifica una prueba de `prove_aggregate` contra los compromisos originales
+pub fn verify_aggregate(proof: &RangeProof, commitments: &[CompressedRistretto], transcript: &mut Transcript) -> Result<(), String> {
+    use curve25519_dalek::traits::Identity;
+
+    let m = aggregate_size(commitments.len())?;
+    let mut padded = commitments.to_vec();
+    padded.resize(m, CompressedRistretto::identity());
+
+    proof
+        .verify_multiple(&BulletproofGens::new(64, m), &PedersenGens::default(), transcript, &padded, 64)
+        .map_err(|e| format!("Aggregated proof verification failed: {}", e))
+}
+
+fn aggregate_size(count: usize) -> Result<usize, String> {
+    match count {
+        0 => Err("Nothing to aggregate".to_string()),
+        n if n > MAX_AGGREGATED_PROOFS => Err(format!("At most {} proofs can be aggregated, got {}", MAX_AGGREGATED_PROOFS, n)),
+        n => Ok(n.next_power_of_two()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proof_roundtrip_is_bound_to_context() {
+        let proof = HumanityProof::generate(82, HUMANITY_THRESHOLD_PERCENT, b"tree-a").unwrap();
+        let parsed = HumanityProof::parse_trailer_value(&proof.to_trailer_value()).unwrap();
+
+        assert!(parsed.verify(b"tree-a").is_ok());
+        assert!(parsed.verify(b"tree-b").is_err(), "Proof must not transplant to another tree");
+    }
+
+    #[test]
+    fn aggregated_proof_covers_every_commitment() {
+        let (single, opening) = HumanityProof::generate_with_opening(71, HUMANITY_THRESHOLD_PERCENT, b"tree").unwrap();
+        assert_eq!(opening.commitment(), single.commitment);
+        assert_eq!(ProofOpening::decode(&opening.encode()).unwrap(), opening);
+
+        let openings: Vec<ProofOpening> = [60, 90, 50]
+            .iter()
+            .map(|s| HumanityProof::generate_with_opening(*s, HUMANITY_THRESHOLD_PERCENT, b"t").unwrap().1)
+            .collect();
+        let commitments: Vec<CompressedRistretto> = openings.iter().map(|o| o.commitment()).collect();
+        let proof = prove_aggregate(&openings, &mut Transcript::new(b"test")).unwrap();
+
+        assert!(verify_aggregate(&proof, &commitments, &mut Transcript::new(b"test")).is_ok());
+        assert!(verify_aggregate(&proof, &commitments[..2], &mut Transcript::new(b"test")).is_err());
+        assert!(verify_aggregate(&proof, &commitments, &mut Transcript::new(b"other")).is_err());
+    }
+
+    #[test]
+    fn below_threshold_cannot_prove() {
+        assert!(HumanityProof::generate(30, HUMANITY_THRESHOLD_PERCENT, b"tree").is_err());
+    }
 }
@@ -9,7 +9,7 @@ use std::time::{Duration, SystemTime};
 use serde::{Deserialize, Serialize};
 
 /// Métricas de foco acumuladas para certificación
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FocusMetrics {
     /// Minutos totales de foco activo en la sesión
     pub total_focus_mins: f64,
@@ -2,7 +2,7 @@ use git2::{Repository, Signature};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-use crate::focus_session::FocusMetrics;
+use crate::report::{SentinelReport, REPORT_TRAILER};
 
 /// Abre un repositorio Git en la ruta especificada
 pub fn open_repository(path: &Path) -> Result<Repository, String> {
@@ -34,44 +34,114 @@ pub fn has_trailer(commit: &git2::Commit, key: &str) -> Result<bool, String> {
 
 /// Instala los hooks de cliff-watch en el repositorio
 pub fn install_hooks(repo: &Repository, config: &crate::config::GovConfig) -> Result<(), String> {
+    let backend = config.attestation.backend()?;
     let hooks_dir = repo.path().join("hooks");
     if !hooks_dir.exists() {
         std::fs::create_dir_all(&hooks_dir).map_err(|e| e.to_string())?;
     }
+    let mut installed = Vec::new();
 
     // 1. Hook de preparación de mensaje (para añadir trailers firmados y certificación v2.0)
-    let prepare_hook_path = hooks_dir.join("prepare-commit-msg");
-    let prepare_hook_content = r#"#!/bin/bash
+    if backend.writes_trailers() {
+        let prepare_hook_path = hooks_dir.join("prepare-commit-msg");
+        // Con backend "both" los archivos quedan para el hook post-commit, que los vuelca a la nota
+        let keep_pending = if backend.writes_notes() { 1 } else { 0 };
+        let prepare_hook_content = format!(r#"#!/bin/bash
 # cliff-watch hook: Añade el ticket firmado y certificación v2.0
 GOV_DIR=".git/cliff-watch"
+KEEP_PENDING={keep_pending}
 
 # v1.0: PoHW Score
 TICKET_FILE="$GOV_DIR/latest_ticket"
 if [ -f "$TICKET_FILE" ]; then
     TICKET_DATA=$(cat "$TICKET_FILE")
     git interpret-trailers --in-place --trailer "cliff-watch-score: $TICKET_DATA" "$1"
-    rm "$TICKET_FILE"
+    [ "$KEEP_PENDING" = "1" ] || rm "$TICKET_FILE"
 fi
 
-# v2.0: Proof of Focus Witness
+# v3.0: Prueba de rango (Human Score >= umbral) ligada al árbol
+PROOF_FILE="$GOV_DIR/latest_proof"
+if [ -f "$PROOF_FILE" ]; then
+    PROOF_DATA=$(cat "$PROOF_FILE")
+    git interpret-trailers --in-place --trailer "Cliff-Watch-Proof: $PROOF_DATA" "$1"
+    [ "$KEEP_PENDING" = "1" ] || rm "$PROOF_FILE"
+fi
+
+# Head firmado del log de transparencia que incluye el ticket
+LOG_FILE="$GOV_DIR/latest_log"
+if [ -f "$LOG_FILE" ]; then
+    LOG_DATA=$(cat "$LOG_FILE")
+    git interpret-trailers --in-place --trailer "Cliff-Watch-Log: $LOG_DATA" "$1"
+    [ "$KEEP_PENDING" = "1" ] || rm "$LOG_FILE"
+fi
+
+# Sentinel Report sellado por el daemon
 WITNESS_FILE="$GOV_DIR/latest_witness"
 if [ -f "$WITNESS_FILE" ]; then
     WITNESS_DATA=$(cat "$WITNESS_FILE")
     git interpret-trailers --in-place --trailer "Cliff-Watch-Witness: $WITNESS_DATA" "$1"
-    rm "$WITNESS_FILE"
+    [ "$KEEP_PENDING" = "1" ] || rm "$WITNESS_FILE"
 fi
-"#;
-    std::fs::write(&prepare_hook_path, prepare_hook_content).map_err(|e| e.to_string())?;
+"#);
+        std::fs::write(&prepare_hook_path, prepare_hook_content).map_err(|e| e.to_string())?;
+        installed.push("prepare-commit-msg");
+    } else {
+        remove_hook(&hooks_dir, "prepare-commit-msg")?;
+    }
 
     // 2. Hook de pre-commit (Delegado al módulo UI)
     let pre_hook_path = hooks_dir.join("pre-commit");
     let pre_hook_content = crate::ui_templates::render_pre_commit_hook(config.governance.audit_mode);
     std::fs::write(&pre_hook_path, pre_hook_content).map_err(|e| e.to_string())?;
+    installed.push("pre-commit");
+
+    // 3. Hook post-commit: la atestación se guarda en refs/notes/cliff-watch
+    if backend.writes_notes() {
+        let post_hook_path = hooks_dir.join("post-commit");
+        let post_hook_content = r#"#!/bin/bash
+# cliff-watch hook: Guarda la atestación del commit en refs/notes/cliff-watch
+CLI_CMD="cliff-watch"
+if ! command -v $CLI_CMD &> /dev/null; then
+    CLI_CMD="cliff-watch-cli"
+    if ! command -v $CLI_CMD &> /dev/null; then
+        if [ -f "target/debug/cliff-watch-cli" ]; then CLI_CMD="./target/debug/cliff-watch-cli"
+        elif [ -f "target/debug/cliff-watch" ]; then CLI_CMD="./target/debug/cliff-watch"
+        else exit 0; fi
+    fi
+fi
+
+$CLI_CMD notes attach HEAD
+"#;
+        std::fs::write(&post_hook_path, post_hook_content).map_err(|e| e.to_string())?;
+        installed.push("post-commit");
+        crate::notes::enable_note_rewrite(repo)?;
+    } else {
+        remove_hook(&hooks_dir, "post-commit")?;
+    }
+
+    // 4. Hook pre-push: verifica los commits que se publican y registra los huecos sin ticket
+    let pre_push_path = hooks_dir.join("pre-push");
+    let pre_push_content = r#"#!/bin/bash
+# cliff-watch hook: Verifica los commits que se van a publicar
+CLI_CMD="cliff-watch"
+if ! command -v $CLI_CMD &> /dev/null; then
+    CLI_CMD="cliff-watch-cli"
+    if ! command -v $CLI_CMD &> /dev/null; then
+        if [ -f "target/debug/cliff-watch-cli" ]; then CLI_CMD="./target/debug/cliff-watch-cli"
+        elif [ -f "target/debug/cliff-watch" ]; then CLI_CMD="./target/debug/cliff-watch"
+        else exit 0; fi
+    fi
+fi
+
+exec $CLI_CMD pre-push "$@"
+"#;
+    std::fs::write(&pre_push_path, pre_push_content).map_err(|e| e.to_string())?;
+    installed.push("pre-push");
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        for hook in &["prepare-commit-msg", "pre-commit"] {
+        for hook in installed {
             let path = hooks_dir.join(hook);
             let mut perms = std::fs::metadata(&path).map_err(|e| e.to_string())?.permissions();
             perms.set_mode(0o755);
@@ -85,35 +155,109 @@ fi
 /// Elimina los hooks de cliff-watch del repositorio
 pub fn remove_hooks(repo: &Repository) -> Result<(), String> {
     let hooks_dir = repo.path().join("hooks");
-    for hook in &["prepare-commit-msg", "pre-commit"] {
-        let path = hooks_dir.join(hook);
-        if path.exists() {
-            // Solo borramos si el archivo contiene "cliff-watch" para no borrar hooks de terceros
-            if let Ok(content) = std::fs::read_to_string(&path) {
-                if content.contains("cliff-watch") {
-                    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
-                }
+    for hook in &["prepare-commit-msg", "pre-commit", "post-commit", "pre-push"] {
+        remove_hook(&hooks_dir, hook)?;
+    }
+    Ok(())
+}
+
+fn remove_hook(hooks_dir: &Path, hook: &str) -> Result<(), String> {
+    let path = hooks_dir.join(hook);
+    if path.exists() {
+        // Solo borramos si el archivo contiene "cliff-watch" para no borrar hooks de terceros
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            if content.contains("cliff-watch") {
+                std::fs::remove_file(&path).map_err(|e| e.to_string())?;
             }
         }
     }
     Ok(())
 }
 
+/// Abre el índice que Git usa para el commit en curso.
+///
+/// Respeta `GIT_INDEX_FILE`: `git commit -a` o `git commit <paths>` preparan
+/// un índice temporal y lo exportan a los hooks.
+fn open_staged_index(repo: &Repository) -> Result<git2::Index, String> {
+    match std::env::var_os("GIT_INDEX_FILE") {
+        Some(path) => git2::Index::open(Path::new(&path)).map_err(|e| format!("Failed to open index: {}", e)),
+        None => repo.index().map_err(|e| format!("Failed to open index: {}", e)),
+    }
+}
+
+/// Calcula el OID del árbol que producirá el índice staged
+pub fn get_staged_tree(repo: &Repository) -> Result<git2::Oid, String> {
+    let mut index = open_staged_index(repo)?;
+    index.write_tree_to(repo).map_err(|e| format!("Failed to write staged tree: {}", e))
+}
+
+/// Construye el binding del ticket (árbol staged, padre y autor) para el commit en curso
+pub fn get_ticket_binding(repo: &Repository) -> Result<crate::ticket::TicketBinding, String> {
+    let tree = get_staged_tree(repo)?;
+    let parent = repo.head().ok()
+        .and_then(|h| h.peel_to_commit().ok())
+        .map(|c| c.id().to_string());
+
+    // Git exporta GIT_AUTHOR_EMAIL cuando el autor se sobrescribe (--author, rebase, etc.)
+    let author = match std::env::var("GIT_AUTHOR_EMAIL") {
+        Ok(email) if !email.is_empty() => email,
+        _ => {
+            let sig = repo.signature().map_err(|e| format!("Failed to resolve author: {}", e))?;
+            sig.email().unwrap_or("").to_string()
+        }
+    };
+
+    Ok(crate::ticket::TicketBinding {
+        tree: tree.to_string(),
+        parent,
+        author,
+    })
+}
+
 /// Obtiene el diff de los archivos staged
 pub fn get_staged_diff(repo: &Repository) -> Result<String, String> {
     let mut opts = git2::DiffOptions::new();
+    let index = open_staged_index(repo)?;
     let head = repo.head().ok();
     let diff = match head {
         Some(h) => {
             let tree = h.peel_to_tree().map_err(|e| e.to_string())?;
-            repo.diff_tree_to_index(Some(&tree), None, Some(&mut opts))
+            repo.diff_tree_to_index(Some(&tree), Some(&index), Some(&mut opts))
         }
         None => {
             // Repositorio vacío, comparamos contra un árbol vacío
-            repo.diff_tree_to_index(None, None, Some(&mut opts))
+            repo.diff_tree_to_index(None, Some(&index), Some(&mut opts))
         }
     }.map_err(|e| e.to_string())?;
 
+    diff_to_text(&diff)
+}
+
+/// Diff entre el árbol de un commit y el de su padre (objeto que Git está firmando)
+pub fn get_tree_diff(repo: &Repository, tree: &str, parent: Option<&str>) -> Result<String, String> {
+    let find_tree = |oid: &str| {
+        git2::Oid::from_str(oid)
+            .and_then(|oid| repo.find_tree(oid))
+            .map_err(|e| format!("Unknown tree {}: {}", oid, e))
+    };
+    let tree = find_tree(tree)?;
+    let parent_tree = match parent {
+        Some(parent) => Some(
+            git2::Oid::from_str(parent)
+                .and_then(|oid| repo.find_commit(oid))
+                .and_then(|c| c.tree())
+                .map_err(|e| format!("Unknown parent {}: {}", parent, e))?,
+        ),
+        None => None,
+    };
+    let mut opts = git2::DiffOptions::new();
+    let diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))
+        .map_err(|e| e.to_string())?;
+    diff_to_text(&diff)
+}
+
+fn diff_to_text(diff: &git2::Diff) -> Result<String, String> {
     let mut diff_text = String::new();
     diff.print(git2::DiffFormat::Patch, |_, _, line: git2::DiffLine| {
         diff_text.push(line.origin());
@@ -127,109 +271,15 @@ pub fn get_staged_diff(repo: &Repository) -> Result<String, String> {
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TrustConfig {
-    keys: Vec<TrustedKey>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct TrustedKey {
-    alias: String,
-    public_key: String,
-    #[serde(default)]
-    role: String,
-}
-
-/// Registra una clave pública en el repositorio (preferentemente en trust.toml)
-pub fn register_public_key(repo: &git2::Repository, key_hex: &str, alias: &str) -> Result<(), String> {
-    // 1. Try to use trust.toml first (Distributed Trust)
-    let trust_toml_path = repo.workdir().map(|w| w.join("trust.toml"));
-    
-    if let Some(path) = trust_toml_path {
-        let mut config = if path.exists() {
-            let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-            toml::from_str::<TrustConfig>(&content).unwrap_or(TrustConfig { keys: vec![] })
-        } else {
-            TrustConfig { keys: vec![] }
-        };
-
-        // Check for duplicates
-        if !config.keys.iter().any(|k| k.alias == alias) {
-            config.keys.push(TrustedKey {
-                alias: alias.to_string(),
-                public_key: key_hex.to_string(),
-                role: "Contributor".to_string(),
-            });
-
-            let toml_string = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
-            std::fs::write(&path, toml_string).map_err(|e| e.to_string())?;
-            return Ok(());
-        } else {
-             // Update existing? For now, just skip or error.
-             return Err(format!("Alias '{}' already exists in trust.toml", alias));
-        }
-    }
-
-    // 2. Fallback to local .git/cliff-watch/trusted_keys (Legacy)
-    let gov_dir = repo.path().join("cliff-watch");
-    if !gov_dir.exists() {
-        std::fs::create_dir_all(&gov_dir).map_err(|e| e.to_string())?;
-    }
-    
-    let keys_file = gov_dir.join("trusted_keys");
-    let entry = format!("{}:{}\n", alias, key_hex);
-    
-    use std::io::Write;
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(keys_file)
-        .map_err(|e| e.to_string())?;
-        
-    file.write_all(entry.as_bytes()).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-/// Obtiene el mapa de claves públicas confiables (trust.toml + legacy)
-pub fn get_trusted_keys(repo: &git2::Repository) -> Result<std::collections::HashMap<String, String>, String> {
-    let mut keys = std::collections::HashMap::new();
-
-    // 1. Read trust.toml (Distributed)
-    if let Some(workdir) = repo.workdir() {
-        let trust_toml = workdir.join("trust.toml");
-        if trust_toml.exists() {
-            if let Ok(content) = std::fs::read_to_string(trust_toml) {
-                if let Ok(config) = toml::from_str::<TrustConfig>(&content) {
-                    for k in config.keys {
-                        keys.insert(k.alias, k.public_key);
-                    }
-                }
-            }
-        }
-    }
-
-    // 2. Read legacy .git/cliff-watch/trusted_keys (Local override)
-    let legacy_file = repo.path().join("cliff-watch").join("trusted_keys");
-    if legacy_file.exists() {
-        if let Ok(content) = std::fs::read_to_string(legacy_file) {
-            for line in content.lines() {
-                let parts: Vec<&str> = line.split(':').collect();
-                if parts.len() == 2 {
-                    keys.insert(parts[0].to_string(), parts[1].to_string());
-                }
-            }
-        }
-    }
-
-    Ok(keys)
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GovernanceEntry {
     pub commit: String,
     pub author: String,
     pub score: f64,
     pub timestamp: i64,
+    /// Modo del ticket (`valid`, `audit`, `pow`); None en tickets legacy
+    #[serde(default)]
+    pub mode: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -271,39 +321,42 @@ pub fn get_governance_history(repo: &git2::Repository, limit: usize) -> Result<V
                     author: cached_entry.author.clone(),
                     score: cached_entry.score,
                     timestamp: cached_entry.timestamp,
+                    mode: cached_entry.mode.clone(),
                 });
                 continue;
             }
 
             // 3. Parse if miss
             if let Ok(commit) = repo.find_commit(oid) {
-                let message = commit.message().unwrap_or("");
-                for line in message.lines() {
-                     if line.starts_with("cliff-watch-score:") {
-                        let score_part = line.replace("cliff-watch-score:", "").trim().to_string();
-                        let parts: Vec<&str> = score_part.split(":sig=").collect();
-                        if !parts.is_empty() {
-                            let score_str = parts[0].replace("score=", "");
-                             if let Ok(score) = score_str.parse::<f64>() {
-                                 let entry = GovernanceEntry {
-                                     commit: sha.clone(),
-                                     author: commit.author().name().unwrap_or("Unknown").to_string(),
-                                     score,
-                                     timestamp: commit.time().seconds(),
-                                 };
-                                 // Add to result
-                                 entries.push(GovernanceEntry {
-                                    commit: entry.commit.clone(),
-                                    author: entry.author.clone(),
-                                    score: entry.score,
-                                    timestamp: entry.timestamp,
-                                 });
-                                 // Add to cache
-                                 cache.entries.insert(sha.clone(), entry);
-                                 new_entries_found = true;
-                             }
-                        }
-                     }
+                // Trailers o refs/notes/cliff-watch: ambas fuentes cuentan
+                let attestation = crate::notes::read_attestation(repo, &commit);
+                for value in crate::ticket::extract_ticket_values(&attestation.text) {
+                    // v1: payload versionado; legacy: "score=<cost>:sig=<hex>"
+                    let (score, mode) = match crate::ticket::SignedTicket::parse_trailer_value(value) {
+                        Ok(ticket) => (Some(ticket.payload.score), Some(ticket.payload.mode.as_str().to_string())),
+                        Err(_) => (value.split(":sig=").next()
+                            .and_then(|s| s.replace("score=", "").parse::<f64>().ok()), None),
+                    };
+                    if let Some(score) = score {
+                        let entry = GovernanceEntry {
+                            commit: sha.clone(),
+                            author: commit.author().name().unwrap_or("Unknown").to_string(),
+                            score,
+                            timestamp: commit.time().seconds(),
+                            mode,
+                        };
+                        // Add to result
+                        entries.push(GovernanceEntry {
+                            commit: entry.commit.clone(),
+                            author: entry.author.clone(),
+                            score: entry.score,
+                            timestamp: entry.timestamp,
+                            mode: entry.mode.clone(),
+                        });
+                        // Add to cache
+                        cache.entries.insert(sha.clone(), entry);
+                        new_entries_found = true;
+                    }
                 }
             }
         }
@@ -323,129 +376,108 @@ pub fn get_governance_history(repo: &git2::Repository, limit: usize) -> Result<V
 }
 
 // =============================================================================
-// SECCIÓN: Cliff-Watch Witness (Certificación v2.0)
+// SECCIÓN: Cliff-Watch Witness (Sentinel Report)
 // =============================================================================
 
-/// Niveles de probabilidad de que el código fue escrito por un humano
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum HumanProbability {
-    /// Alta probabilidad: tiempo de foco significativo + ediciones
-    High,
-    /// Probabilidad media: algo de foco, pocas ediciones
-    Medium,
-    /// Probabilidad baja: copy-paste o asistente IA
-    Low,
-    /// Sin datos: daemon no detectó actividad
-    Unknown,
-}
-
-impl std::fmt::Display for HumanProbability {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            HumanProbability::High => write!(f, "High"),
-            HumanProbability::Medium => write!(f, "Medium"),
-            HumanProbability::Low => write!(f, "Low"),
-            HumanProbability::Unknown => write!(f, "Unknown"),
-        }
+/// Agrega el trailer Cliff-Watch-Witness (informe sellado) a un mensaje de commit
+///
+/// Si el mensaje ya tiene un trailer Cliff-Watch-Witness, no lo duplica.
+pub fn inject_witness_trailer(message: &str, report: &SentinelReport) -> String {
+    if message.contains(&format!("{}:", REPORT_TRAILER)) {
+        return message.to_string();
     }
+
+    add_trailer(message, REPORT_TRAILER, &report.to_trailer_value())
 }
 
-/// Datos del Witness para el trailer Cliff-Watch-Witness
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WitnessData {
-    /// Minutos totales de foco activo durante la sesión
-    pub focus_time_mins: f64,
-    /// Cantidad de ráfagas de edición detectadas
-    pub edit_bursts: usize,
-    /// Cantidad de archivos únicos tocados
-    pub files_touched: usize,
-    /// Probabilidad calculada de autoría humana
-    pub human_probability: HumanProbability,
-    /// Versión del protocolo de certificación
-    pub version: String,
+/// Estado del trailer Cliff-Watch-Witness de un commit
+#[derive(Debug, Clone, PartialEq)]
+pub enum WitnessStatus {
+    /// Informe sellado por la misma clave que firmó el ticket y ligado a su nonce
+    Authentic(Box<SentinelReport>),
+    /// Hay trailer, pero no resiste la verificación (código de razón)
+    Forged(String),
+    /// El commit no lleva trailer Cliff-Watch-Witness
+    Missing,
 }
 
-impl WitnessData {
-    /// Crea WitnessData desde FocusMetrics
-    pub fn from_metrics(metrics: &FocusMetrics) -> Self {
-        Self {
-            focus_time_mins: (metrics.total_focus_mins * 100.0).round() / 100.0,
-            edit_bursts: metrics.edit_burst_count,
-            files_touched: metrics.unique_files,
-            human_probability: calculate_human_probability(metrics),
-            version: "2.0".to_string(),
+impl WitnessStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            WitnessStatus::Authentic(_) => "authentic",
+            WitnessStatus::Forged(_) => "forged",
+            WitnessStatus::Missing => "missing",
         }
     }
-
-    /// Serializa a JSON compacto para el trailer
-    pub fn to_json(&self) -> String {
-        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
-    }
 }
 
-/// Calcula la probabilidad de que el código fue escrito por un humano
-/// basándose en las métricas de foco recolectadas.
-/// 
-/// ## Criterios
-/// - **High**: >= 5 min de foco O >= 10 edit bursts O >= 3 archivos
-/// - **Medium**: >= 1 min de foco O >= 3 edit bursts O >= 1 archivo
-/// - **Low**: < 1 min de foco Y < 3 edit bursts
-/// - **Unknown**: Sin métricas
-pub fn calculate_human_probability(metrics: &FocusMetrics) -> HumanProbability {
-    let score = metrics.total_focus_mins * 10.0 
-        + metrics.edit_burst_count as f64 * 5.0 
-        + metrics.unique_files as f64 * 3.0
-        + metrics.navigation_events as f64 * 1.0;
-
-    if score >= 50.0 {
-        HumanProbability::High
-    } else if score >= 15.0 {
-        HumanProbability::Medium
-    } else if score > 0.0 {
-        HumanProbability::Low
-    } else {
-        HumanProbability::Unknown
+/// Extrae y verifica el `SentinelReport` del trailer Cliff-Watch-Witness.
+///
+/// El informe debe estar firmado por la clave del ticket (`kid`), su contenido
+/// debe coincidir con el hash sellado y debe llevar el nonce del ticket.
+/// Razones de `Forged`: `malformed_witness`, `content_hash_mismatch`,
+/// `unknown_key`, `bad_signature` o `nonce_mismatch`.
+///
+/// `attestation` es el mensaje del commit o, en general, el texto que
+/// devuelve `notes::read_attestation`.
+pub fn extract_witness_data(
+    attestation: &str,
+    ticket: &crate::ticket::SignedTicket,
+    trust: &crate::trust::TrustConfig,
+) -> WitnessStatus {
+    use crate::report::hash::content_hash_matches;
+    use crate::report::sign::{verify_all_signatures, SignError};
+
+    let prefix = format!("{}:", REPORT_TRAILER);
+    let values: Vec<&str> = attestation.lines().filter_map(|line| line.strip_prefix(prefix.as_str())).collect();
+    if values.is_empty() {
+        return WitnessStatus::Missing;
     }
-}
 
-/// Genera un trailer Cliff-Watch-Witness para agregar al mensaje de commit
-/// 
-/// ## Formato
-/// ```text
-/// Cliff-Watch-Witness: {"focus_time_mins":5.25,"edit_bursts":12,"files_touched":3,"human_probability":"high","version":"2.0"}
-/// ```
-/// 
-/// ## Uso
-/// El hook `prepare-commit-msg` o la CLI pueden usar esta función para
-/// inyectar el trailer automáticamente.
-pub fn generate_witness_trailer(metrics: &FocusMetrics) -> String {
-    let witness = WitnessData::from_metrics(metrics);
-    format!("Cliff-Watch-Witness: {}", witness.to_json())
-}
+    let kid = ticket.payload.key_id.as_str();
+    let mut reason = "malformed_witness".to_string();
+    for value in values {
+        let report = match SentinelReport::parse_trailer_value(value) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
 
-/// Agrega el trailer Cliff-Watch-Witness a un mensaje de commit existente
-/// 
-/// Si el mensaje ya tiene un trailer Cliff-Watch-Witness, no lo duplica.
-pub fn inject_witness_trailer(message: &str, metrics: &FocusMetrics) -> String {
-    // No duplicar si ya existe
-    if message.contains("Cliff-Watch-Witness:") {
-        return message.to_string();
-    }
-    
-    add_trailer(message, "Cliff-Watch-Witness", &WitnessData::from_metrics(metrics).to_json())
-}
+        if !content_hash_matches(&report) {
+            reason = "content_hash_mismatch".to_string();
+            continue;
+        }
 
-/// Extrae los datos de Cliff-Watch-Witness de un mensaje de commit
-pub fn extract_witness_data(message: &str) -> Option<WitnessData> {
-    for line in message.lines() {
-        if let Some(json_str) = line.strip_prefix("Cliff-Watch-Witness: ") {
-            if let Ok(data) = serde_json::from_str::<WitnessData>(json_str) {
-                return Some(data);
+        // Solo cuenta la firma de la clave que firmó el ticket
+        let verified = verify_all_signatures(&report.integrity.content_hash, &report.integrity.signatures, |k| {
+            if k != kid {
+                return None;
+            }
+            trust
+                .keys_with_id(k)
+                .filter_map(|key| crate::crypto::PublicKey::from_hex(&key.public_key).ok())
+                .next()
+        });
+        match verified {
+            Ok(_) => {}
+            Err(SignError::UnknownKey) => {
+                reason = "unknown_key".to_string();
+                continue;
+            }
+            Err(_) => {
+                reason = "bad_signature".to_string();
+                continue;
             }
         }
+
+        if report.ticket_nonce.as_deref() != Some(ticket.payload.nonce.as_str()) {
+            reason = "nonce_mismatch".to_string();
+            continue;
+        }
+
+        return WitnessStatus::Authentic(Box::new(report));
     }
-    None
+
+    WitnessStatus::Forged(reason)
 }
 
 #[cfg(test)]
@@ -459,14 +491,56 @@ mod tests {
         assert!(new_message.contains("cliff-watch-score: 0.85"));
     }
     
+    #[test]
+    fn witness_must_be_signed_and_bound_to_the_ticket() {
+        use crate::report::{builder::SentinelBuilder, finalize::finalize_report, ObservationWindow};
+        use crate::ticket::{SignedTicket, TicketBinding, TicketMode, TicketPayload};
+
+        let (signing_key, verifying_key) = crate::crypto::generate_keypair();
+        let identity = crate::crypto::DaemonIdentity::Software(signing_key);
+        let mut trust = crate::trust::TrustConfig::default();
+        trust.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+
+        let binding = TicketBinding { tree: "t".into(), parent: None, author: "dev@example.com".into() };
+        let payload = TicketPayload::new(TicketMode::Valid, 1.0, binding, crate::crypto::key_id(&verifying_key));
+        let ticket = SignedTicket::sign(payload, &identity).unwrap();
+
+        let seal = |nonce: Option<String>| {
+            let report = SentinelBuilder::new()
+                .report_id("r")
+                .workspace_id("ws")
+                .observation(ObservationWindow::default())
+                .ticket_nonce(nonce)
+                .build()
+                .unwrap();
+            finalize_report(report, &identity, None).unwrap()
+        };
+        let with_witness = |report: &SentinelReport| add_trailer("msg", REPORT_TRAILER, &report.to_trailer_value());
+
+        let good = seal(Some(ticket.payload.nonce.clone()));
+        assert_eq!(extract_witness_data(&with_witness(&good), &ticket, &trust).label(), "authentic");
+        assert_eq!(extract_witness_data("msg", &ticket, &trust), WitnessStatus::Missing);
+
+        // Editar el JSON a mano rompe el hash sellado
+        let forged = with_witness(&good).replace("\"raw_event_count\":0", "\"raw_event_count\":9");
+        assert_eq!(extract_witness_data(&forged, &ticket, &trust), WitnessStatus::Forged("content_hash_mismatch".into()));
+
+        // Un informe legítimo de otro commit no sirve para este ticket
+        let other = seal(Some("another-nonce".into()));
+        assert_eq!(extract_witness_data(&with_witness(&other), &ticket, &trust), WitnessStatus::Forged("nonce_mismatch".into()));
+
+        // Re-sellar con otra clave no engaña al verificador
+        let (rogue_key, _) = crate::crypto::generate_keypair();
+        let rogue = finalize_report(good.clone(), &crate::crypto::DaemonIdentity::Software(rogue_key), None).unwrap();
+        assert_eq!(extract_witness_data(&with_witness(&rogue), &ticket, &trust), WitnessStatus::Forged("unknown_key".into()));
+
+        let legacy = add_trailer("msg", REPORT_TRAILER, r#"{"focus_time_mins":9.0,"human_probability":"high","version":"2.0"}"#);
+        assert_eq!(extract_witness_data(&legacy, &ticket, &trust), WitnessStatus::Forged("malformed_witness".into()));
+    }
+
     #[test]
     fn test_has_trailer() {
-        let dir = std::env::temp_dir();
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        let repo_path = dir.join(format!("test_repo_{}", timestamp));
+        let repo_path = crate::testing::TempDir::new("test_repo");
         let repo = Repository::init(&repo_path).unwrap();
         let signature = create_signature("Test User", "test@example.com").unwrap();
          
@@ -5,12 +5,28 @@ pub mod focus_session;
 pub mod crypto;
 pub mod entropy;
 pub mod git;
+pub mod ticket;
+pub mod clock;
+pub mod chain;
+pub mod ci;
+pub mod trust;
+pub mod report;
+pub mod pow;
+pub mod notes;
+pub mod translog;
+pub mod certificate;
+pub mod signing;
+pub mod server_hook;
+pub mod policy;
+pub mod credential;
 pub mod monitor;
 pub mod mouse_sentinel;
 pub mod stats;
 pub mod complexity;
 pub mod config;
 pub mod ui_templates;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 
 use sha2::{Digest, Sha256};
 use ed25519_dalek::SigningKey;
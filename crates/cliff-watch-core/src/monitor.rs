@@ -72,7 +72,7 @@ pub enum OverflowPolicy {
     DropNewest,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct MonitorStatsSnapshot {
     pub raw_dropped_overflow: u64,
     pub out_dropped_overflow: u64,
@@ -81,6 +81,24 @@ pub struct MonitorStatsSnapshot {
     pub debounced: u64,
 }
 
+impl MonitorStatsSnapshot {
+    /// Contadores acumulados desde `earlier` (saturando si el monitor se reinició).
+    pub fn since(&self, earlier: &MonitorStatsSnapshot) -> MonitorStatsSnapshot {
+        MonitorStatsSnapshot {
+            raw_dropped_overflow: self.raw_dropped_overflow.saturating_sub(earlier.raw_dropped_overflow),
+            out_dropped_overflow: self.out_dropped_overflow.saturating_sub(earlier.out_dropped_overflow),
+            watcher_errors: self.watcher_errors.saturating_sub(earlier.watcher_errors),
+            emitted: self.emitted.saturating_sub(earlier.emitted),
+            debounced: self.debounced.saturating_sub(earlier.debounced),
+        }
+    }
+
+    /// Eventos crudos observados antes del filtrado (emitidos + descartados).
+    pub fn raw_event_count(&self) -> u64 {
+        self.emitted + self.debounced + self.raw_dropped_overflow + self.out_dropped_overflow
+    }
+}
+
 #[derive(Debug, Default)]
 struct MonitorStats {
     raw_dropped_overflow: AtomicU64,
@@ -181,8 +199,19 @@ impl Debouncer {
     }
 }
 
+/// Vista en vivo de los contadores de un `FileMonitor` en ejecución.
+#[derive(Debug, Clone, Default)]
+pub struct MonitorStatsHandle(Arc<MonitorStats>);
+
+impl MonitorStatsHandle {
+    pub fn snapshot(&self) -> MonitorStatsSnapshot {
+        self.0.snapshot()
+    }
+}
+
 pub struct FileMonitor {
     cfg: MonitorConfig,
+    stats: Arc<MonitorStats>,
 }
 
 impl FileMonitor {
@@ -190,7 +219,12 @@ impl FileMonitor {
         if !cfg.watch_root.exists() {
             return Err(MonitorError::InvalidWatchRoot(cfg.watch_root));
         }
-        Ok(Self { cfg })
+        Ok(Self { cfg, stats: Arc::new(MonitorStats::default()) })
+    }
+
+    /// Handle para leer los contadores mientras `run` está activo.
+    pub fn stats_handle(&self) -> MonitorStatsHandle {
+        MonitorStatsHandle(Arc::clone(&self.stats))
     }
 
     pub async fn run(
@@ -202,7 +236,7 @@ impl FileMonitor {
         let ignore_top = Arc::new(self.cfg.ignore_top_level_dirs.clone());
         let ignore_ext = Arc::new(self.cfg.ignore_extensions.clone());
 
-        let stats = Arc::new(MonitorStats::default());
+        let stats = Arc::clone(&self.stats);
         let (raw_tx, mut raw_rx) = mpsc::channel::<RawEvent>(self.cfg.raw_queue_capacity);
 
         let stop = Arc::new(AtomicBool::new(false));
@@ -47,7 +47,7 @@ pub enum MouseSentinelError {
     DegenerateMotion,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct KinematicMetrics {
     /// Log-Dimensional Less Jerk - métrica de suavidad de movimiento
     pub ldlj: f64,
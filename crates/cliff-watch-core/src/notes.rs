@@ -0,0 +1,311 @@
+//! Atestaciones en git notes (`refs/notes/cliff-watch`)
+//!
+//! Alternativa a los trailers: la atestación (ticket, prueba de rango, head
+//! del log de transparencia y Sentinel Report) se guarda como nota del
+//! commit, sin tocar su mensaje. Así `git commit --amend` no invalida nada y un equipo puede adoptar
+//! cliff-watch sin reescribir historia.
+//!
+//! El cuerpo de la nota usa el mismo formato `Clave: valor` que los
+//! trailers, de modo que los verificadores leen ambas fuentes por igual.
+//!
+//! La nota también guarda, en líneas `Cliff-Watch-Gap`, por qué un commit se
+//! hizo sin ticket (lo registra el hook pre-push o `notes gap`).
+
+use git2::{Commit, Oid, Repository};
+
+use crate::report::REPORT_TRAILER;
+use crate::ticket::{PROOF_TRAILER, TICKET_TRAILER};
+use crate::translog::LOG_TRAILER;
+
+/// Referencia de notas donde viajan las atestaciones
+pub const NOTES_REF: &str = "refs/notes/cliff-watch";
+
+/// Línea de la nota que explica por qué un commit no lleva ticket
+pub const GAP_KEY: &str = "Cliff-Watch-Gap";
+
+/// Archivos pendientes en `.git/cliff-watch/` y el trailer que representan
+const PENDING_FILES: [(&str, &str); 4] = [
+    ("latest_ticket", TICKET_TRAILER),
+    ("latest_proof", PROOF_TRAILER),
+    ("latest_log", LOG_TRAILER),
+    ("latest_witness", REPORT_TRAILER),
+];
+
+/// Dónde se guardan las atestaciones de los commits nuevos (`[attestation] backend`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttestationBackend {
+    Trailers,
+    Notes,
+    Both,
+}
+
+impl AttestationBackend {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "trailers" => Ok(Self::Trailers),
+            "notes" => Ok(Self::Notes),
+            "both" => Ok(Self::Both),
+            other => Err(format!("Unknown attestation backend '{}' (expected trailers, notes or both)", other)),
+        }
+    }
+
+    pub fn writes_trailers(&self) -> bool {
+        matches!(self, Self::Trailers | Self::Both)
+    }
+
+    pub fn writes_notes(&self) -> bool {
+        matches!(self, Self::Notes | Self::Both)
+    }
+}
+
+/// Origen de la atestación de un commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttestationSource {
+    None,
+    Trailers,
+    Notes,
+    Both,
+    /// Solo en la firma del commit (shim de `gpg.program`)
+    Signature,
+}
+
+impl AttestationSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Trailers => "trailers",
+            Self::Notes => "notes",
+            Self::Both => "trailers+notes",
+            Self::Signature => "signature",
+        }
+    }
+}
+
+/// Texto de atestación de un commit: mensaje y nota concatenados
+#[derive(Debug, Clone)]
+pub struct Attestation {
+    pub text: String,
+    pub source: AttestationSource,
+}
+
+/// Lee la nota cliff-watch de un objeto, si existe
+pub fn read_note(repo: &Repository, oid: Oid) -> Option<String> {
+    repo.find_note(Some(NOTES_REF), oid)
+        .ok()
+        .and_then(|note| note.message().map(str::to_string))
+}
+
+/// Escribe (o reemplaza) la nota cliff-watch de un objeto
+pub fn write_note(repo: &Repository, oid: Oid, body: &str) -> Result<Oid, String> {
+    let signature = repo
+        .signature()
+        .or_else(|_| git2::Signature::now("cliff-watch", "cliff-watch@localhost"))
+        .map_err(|e| format!("Failed to create note signature: {}", e))?;
+    repo.note(&signature, &signature, Some(NOTES_REF), oid, body, true)
+        .map_err(|e| format!("Failed to write note: {}", e))
+}
+
+/// Añade una línea `Clave: valor` a la nota de `oid` (la crea si no existe).
+///
+/// Devuelve `false` si la nota ya contenía esa línea.
+pub fn append_to_note(repo: &Repository, oid: Oid, key: &str, value: &str) -> Result<bool, String> {
+    let line = format!("{}: {}", key, value.trim());
+    let existing = read_note(repo, oid).unwrap_or_default();
+    if existing.lines().any(|l| l.trim() == line) {
+        return Ok(false);
+    }
+    let body = if existing.trim().is_empty() {
+        format!("{}\n", line)
+    } else {
+        format!("{}\n{}\n", existing.trim_end(), line)
+    };
+    write_note(repo, oid, &body)?;
+    Ok(true)
+}
+
+/// Explicación registrada para un commit sin ticket, si la hay (la más reciente)
+pub fn read_gap(repo: &Repository, oid: Oid) -> Option<String> {
+    read_note(repo, oid)?
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(GAP_KEY).and_then(|rest| rest.strip_prefix(':')))
+        .map(|reason| reason.trim().to_string())
+}
+
+/// Registra a posteriori por qué `oid` no lleva ticket (`--no-verify`, daemon parado...).
+///
+/// Es documental: el commit sigue contando como `unsigned` en `verify`.
+pub fn record_gap(repo: &Repository, oid: Oid, reason: &str) -> Result<bool, String> {
+    let reason = reason.split_whitespace().collect::<Vec<_>>().join(" ");
+    if reason.is_empty() {
+        return Err("Empty gap reason".to_string());
+    }
+    append_to_note(repo, oid, GAP_KEY, &reason)
+}
+
+/// Reúne la atestación de un commit desde sus trailers, su nota y su firma
+pub fn read_attestation(repo: &Repository, commit: &Commit) -> Attestation {
+    let message = commit.message().unwrap_or("");
+    let note = read_note(repo, commit.id());
+    let signed = crate::signing::read_signature_attestation(repo, commit.id());
+
+    let in_message = !crate::ticket::extract_ticket_values(message).is_empty();
+    let in_note = note.as_deref().is_some_and(|n| !crate::ticket::extract_ticket_values(n).is_empty());
+    let source = match (in_message, in_note) {
+        (true, true) => AttestationSource::Both,
+        (true, false) => AttestationSource::Trailers,
+        (false, true) => AttestationSource::Notes,
+        (false, false) if signed.is_some() => AttestationSource::Signature,
+        (false, false) => AttestationSource::None,
+    };
+
+    let mut text = match note {
+        Some(note) => format!("{}\n{}", message.trim_end(), note),
+        None => message.to_string(),
+    };
+    if let Some(signed) = signed {
+        text = format!("{}\n{}\n", text.trim_end(), signed);
+    }
+    Attestation { text, source }
+}
+
+/// Convierte los archivos pendientes de `.git/cliff-watch/` en la nota de `oid`.
+///
+/// Lo invoca el hook post-commit. Los archivos se consumen; devuelve `false`
+/// si no había nada que adjuntar.
+pub fn attach_pending(repo: &Repository, oid: Oid) -> Result<bool, String> {
+    let gov_dir = repo.path().join("cliff-watch");
+    let mut lines = Vec::new();
+    let mut consumed = Vec::new();
+    for (file, key) in PENDING_FILES {
+        let path = gov_dir.join(file);
+        if let Ok(value) = std::fs::read_to_string(&path) {
+            lines.push(format!("{}: {}", key, value.trim()));
+            consumed.push(path);
+        }
+    }
+    if lines.is_empty() {
+        return Ok(false);
+    }
+
+    write_note(repo, oid, &format!("{}\n", lines.join("\n")))?;
+    for path in consumed {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(true)
+}
+
+/// Configura Git para que `--amend` y `rebase` copien la nota al commit reescrito
+pub fn enable_note_rewrite(repo: &Repository) -> Result<(), String> {
+    let mut config = repo.config().map_err(|e| e.to_string())?;
+    config
+        .set_multivar("notes.rewriteRef", &format!("^{}$", NOTES_REF), NOTES_REF)
+        .map_err(|e| format!("Failed to configure notes.rewriteRef: {}", e))
+}
+
+/// Publica las notas en un remoto (sin forzar: un historial divergente se rechaza)
+pub fn push_notes(repo: &Repository, remote: &str) -> Result<(), String> {
+    let mut remote = repo.find_remote(remote).map_err(|e| format!("Unknown remote '{}': {}", remote, e))?;
+    let refspec = format!("{}:{}", NOTES_REF, NOTES_REF);
+    remote.push(&[refspec.as_str()], None).map_err(|e| format!("Failed to push notes: {}", e))
+}
+
+/// Trae las notas de un remoto. Solo avanza en fast-forward para no perder notas locales.
+pub fn fetch_notes(repo: &Repository, remote: &str) -> Result<(), String> {
+    let mut remote = repo.find_remote(remote).map_err(|e| format!("Unknown remote '{}': {}", remote, e))?;
+    let refspec = format!("{}:{}", NOTES_REF, NOTES_REF);
+    remote
+        .fetch(&[refspec.as_str()], None, None)
+        .map_err(|e| format!("Failed to fetch notes: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn temp_repo(tag: &str) -> (TempDir, Repository) {
+        let path = TempDir::new(&format!("cliff_watch_notes_{}", tag));
+        let repo = Repository::init(&path).unwrap();
+        (path, repo)
+    }
+
+    fn empty_commit(repo: &Repository, message: &str) -> Oid {
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let tree = repo.find_tree(repo.treebuilder(None).unwrap().write().unwrap()).unwrap();
+        let parents: Vec<Commit> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+        let parents: Vec<&Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn pending_files_become_a_note() {
+        let (_dir, repo) = temp_repo("attach");
+        let oid = empty_commit(&repo, "feat: x");
+        assert!(!attach_pending(&repo, oid).unwrap());
+
+        let gov_dir = repo.path().join("cliff-watch");
+        std::fs::create_dir_all(&gov_dir).unwrap();
+        std::fs::write(gov_dir.join("latest_ticket"), "v=1;mode=valid:sig=00").unwrap();
+        assert!(attach_pending(&repo, oid).unwrap());
+        assert!(!gov_dir.join("latest_ticket").exists());
+
+        let commit = repo.find_commit(oid).unwrap();
+        let attestation = read_attestation(&repo, &commit);
+        assert_eq!(attestation.source, AttestationSource::Notes);
+        assert_eq!(crate::ticket::extract_ticket_values(&attestation.text), vec!["v=1;mode=valid:sig=00"]);
+        assert_eq!(commit.message(), Some("feat: x"), "El mensaje no se toca");
+
+        assert!(append_to_note(&repo, oid, "Cliff-Watch-Cosign", "kid=ab:sig=cd").unwrap());
+        assert!(!append_to_note(&repo, oid, "Cliff-Watch-Cosign", "kid=ab:sig=cd").unwrap());
+        assert_eq!(read_note(&repo, oid).unwrap().lines().count(), 2);
+
+        let gap = empty_commit(&repo, "fix: hecho con --no-verify");
+        assert!(read_gap(&repo, gap).is_none());
+        assert!(record_gap(&repo, gap, "  ").is_err());
+        assert!(record_gap(&repo, gap, "daemon parado\ndurante el viaje").unwrap());
+        assert_eq!(read_gap(&repo, gap).as_deref(), Some("daemon parado durante el viaje"));
+    }
+
+    #[test]
+    fn tickets_in_notes_verify_like_trailers() {
+        use crate::ticket::{verify_commit_ticket, SignedTicket, TicketBinding, TicketMode, TicketPayload, TICKET_TRAILER};
+
+        let (_dir, repo) = temp_repo("verify");
+        let (signing_key, verifying_key) = crate::crypto::generate_keypair();
+        let identity = crate::crypto::DaemonIdentity::Software(signing_key);
+        let mut trust = crate::trust::TrustConfig::default();
+        trust.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+
+        let oid = empty_commit(&repo, "feat: z");
+        let commit = repo.find_commit(oid).unwrap();
+        let binding = TicketBinding { tree: commit.tree_id().to_string(), parent: None, author: "dev@example.com".into() };
+        let payload = TicketPayload::new(TicketMode::Valid, 4.0, binding, crate::crypto::key_id(&verifying_key));
+        let ticket = SignedTicket::sign(payload, &identity).unwrap();
+
+        assert_eq!(verify_commit_ticket(&commit, &read_attestation(&repo, &commit).text, &trust).unwrap_err(), "no_ticket");
+        write_note(&repo, oid, &format!("{}: {}\n", TICKET_TRAILER, ticket.to_trailer_value())).unwrap();
+        let attestation = read_attestation(&repo, &commit);
+        assert_eq!(verify_commit_ticket(&commit, &attestation.text, &trust).unwrap().signer, "dev");
+    }
+
+    #[test]
+    fn notes_travel_through_a_local_remote() {
+        let (origin_path, origin) = temp_repo("origin");
+        let oid = empty_commit(&origin, "feat: y");
+        write_note(&origin, oid, "cliff-watch-score: v=1:sig=00\n").unwrap();
+
+        let (_clone_dir, clone) = temp_repo("clone");
+        clone.remote("origin", origin_path.to_str().unwrap()).unwrap();
+        assert!(read_note(&clone, oid).is_none());
+        fetch_notes(&clone, "origin").unwrap();
+        assert_eq!(read_note(&clone, oid).as_deref(), Some("cliff-watch-score: v=1:sig=00\n"));
+
+        // Y de vuelta: las notas del clon llegan a un remoto compartido
+        let bare_path = TempDir::new("cliff_watch_notes_bare");
+        let bare = Repository::init_bare(&bare_path).unwrap();
+        clone.remote("shared", bare_path.to_str().unwrap()).unwrap();
+        push_notes(&clone, "shared").unwrap();
+        assert!(read_note(&bare, oid).is_some());
+    }
+}
@@ -0,0 +1,500 @@
+//! Política como código (`.cliff-watch/policy.toml`)
+//!
+//! Reglas acotadas por rutas y ramas que endurecen o relajan la exigencia
+//! global:
+//!
+//! ```toml
+//! [[rule]]
+//! name = "crypto"
+//! paths = ["src/crypto/**"]
+//! min_human_score = 0.7
+//! cosign = "Maintainer"
+//!
+//! [[rule]]
+//! name = "docs"
+//! paths = ["docs/**"]
+//! mode = "audit"
+//!
+//! [[rule]]
+//! branches = ["main"]
+//! mode = "strict"
+//!
+//! [[rule]]
+//! branches = ["feature/*"]
+//! mode = "audit"
+//! ```
+//!
+//! - Una regla aplica si encaja alguna de sus `branches` (todas si no tiene)
+//!   y alguno de los archivos del commit con sus `paths` (todos si no tiene).
+//!   En los patrones, `*` y `?` no cruzan `/` y `**` abarca directorios.
+//! - `mode` (strict, signed, audit, off): las reglas sin `paths` fijan la
+//!   exigencia de la rama, salvo que la configuración de Git del servidor la
+//!   fije (ver `server_hook`). Las reglas con `paths` fijan la de sus archivos
+//!   (nunca por debajo de la configuración de Git) y el commit se juzga con la
+//!   más exigente. Entre reglas que encajan, gana la última, como en
+//!   `.gitattributes`.
+//! - `min_human_score` y `cosign` se acumulan: el commit debe probar (prueba de
+//!   rango) el score más alto exigido y llevar co-firma de cada rol pedido.
+//!
+//! Un commit se juzga con el policy.toml de su padre: un commit no puede
+//! relajar su propia política. A diferencia de trust.toml no hay TOFU: el
+//! commit que introduce policy.toml se juzga sin reglas.
+
+use git2::{Commit, Repository};
+use serde::{Deserialize, Serialize};
+
+use crate::ci::CommitStatus;
+use crate::ticket::{TicketBinding, TicketSigner};
+use crate::trust::HistoryCache;
+
+/// Ruta de la política dentro del repositorio
+pub const POLICY_PATH: &str = ".cliff-watch/policy.toml";
+
+const BRANCH_PREFIX: &str = "refs/heads/";
+/// Archivos que se citan por regla al explicarla
+const MAX_LISTED_FILES: usize = 3;
+
+/// Nivel de exigencia de una ref o un commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Enforcement {
+    Strict,
+    Signed,
+    Audit,
+    Off,
+}
+
+impl Enforcement {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "signed" => Ok(Self::Signed),
+            "audit" => Ok(Self::Audit),
+            "off" => Ok(Self::Off),
+            other => Err(format!("Unknown policy '{}' (expected strict, signed, audit or off)", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Signed => "signed",
+            Self::Audit => "audit",
+            Self::Off => "off",
+        }
+    }
+
+    /// ¿Rechaza un commit con este resultado?
+    pub fn rejects(&self, status: CommitStatus) -> bool {
+        match self {
+            Self::Strict => status.is_failure(),
+            Self::Signed => status >= CommitStatus::Unsigned,
+            Self::Audit | Self::Off => false,
+        }
+    }
+
+    /// ¿Rechaza una cadena de tickets manipulada?
+    pub fn rejects_chain(&self) -> bool {
+        matches!(self, Self::Strict | Self::Signed)
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::Audit => 1,
+            Self::Signed => 2,
+            Self::Strict => 3,
+        }
+    }
+}
+
+/// Regla de policy.toml
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Score humano (0..1) que debe probar la prueba de rango del ticket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_human_score: Option<f64>,
+    /// Rol de trust.toml del que se exige una co-firma
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cosign: Option<String>,
+}
+
+impl PolicyRule {
+    fn enforcement(&self) -> Option<Enforcement> {
+        self.mode.as_deref().and_then(|m| Enforcement::parse(m).ok())
+    }
+
+    fn applies_to_branch(&self, branch: Option<&str>) -> bool {
+        self.branches.is_empty() || branch.is_some_and(|b| self.branches.iter().any(|p| branch_matches(p, b)))
+    }
+
+    fn effects(&self) -> Vec<String> {
+        let mut effects: Vec<String> = self.enforcement().map(|m| format!("mode {}", m.as_str())).into_iter().collect();
+        effects.extend(self.min_human_score.map(|s| format!("human score ≥ {:.2}", s)));
+        effects.extend(self.cosign.as_ref().map(|r| format!("{} co-signature", r)));
+        effects
+    }
+}
+
+/// Contenido de policy.toml
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    #[serde(default, rename = "rule", skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PolicyRule>,
+}
+
+/// Dónde se aplica la política y con qué exigencia de partida
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyScope {
+    /// Rama de destino (`main` o `refs/heads/main`), si se conoce
+    pub branch: Option<String>,
+    /// Exigencia fijada fuera del árbol (configuración de Git del servidor);
+    /// tiene prioridad sobre el `mode` de las reglas de rama
+    pub configured: Option<Enforcement>,
+    /// Exigencia sin regla ni configuración
+    pub default: Enforcement,
+}
+
+impl PolicyScope {
+    /// Hooks locales y `verify`: la rama indicada o la actual, `strict` por defecto
+    pub fn local(repo: &Repository, branch: Option<String>) -> Self {
+        Self { branch: branch.or_else(|| current_branch(repo)), configured: None, default: Enforcement::Strict }
+    }
+}
+
+/// Regla que aplicó a un commit y por qué
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleHit {
+    pub rule: String,
+    /// Archivos (o la rama) que la activaron
+    pub matched: Vec<String>,
+    pub effects: Vec<String>,
+    /// Requisitos de la regla que el commit no cumple
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unmet: Vec<String>,
+    #[serde(skip)]
+    min_human_score: Option<f64>,
+    #[serde(skip)]
+    cosign: Option<String>,
+}
+
+impl RuleHit {
+    /// `crypto [src/crypto/aes.rs]: human score ≥ 0.70, Maintainer co-signature ✗ ...`
+    pub fn explain(&self) -> String {
+        let mut matched: Vec<&str> = self.matched.iter().take(MAX_LISTED_FILES).map(String::as_str).collect();
+        let more = self.matched.len().saturating_sub(MAX_LISTED_FILES);
+        let more = format!("+{}", more);
+        if self.matched.len() > MAX_LISTED_FILES {
+            matched.push(&more);
+        }
+        let unmet = if self.unmet.is_empty() { String::new() } else { format!(" ✗ {}", self.unmet.join("; ")) };
+        format!("{} [{}]: {}{}", self.rule, matched.join(", "), self.effects.join(", "), unmet)
+    }
+}
+
+/// Lo que la política exige a un commit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolicyDecision {
+    pub mode: Enforcement,
+    /// De dónde sale `mode`: una regla, la configuración de Git o el valor por defecto
+    pub mode_source: String,
+    pub hits: Vec<RuleHit>,
+}
+
+impl PolicyDecision {
+    /// Umbral (%) más alto que exigen las reglas a la prueba de rango
+    pub fn required_percent(&self) -> Option<u64> {
+        self.hits.iter().filter_map(|h| h.min_human_score).map(score_percent).max()
+    }
+
+    /// Roles de los que las reglas exigen co-firma
+    pub fn cosign_roles(&self) -> Vec<&str> {
+        let mut roles: Vec<&str> = self.hits.iter().filter_map(|h| h.cosign.as_deref()).collect();
+        roles.dedup();
+        roles
+    }
+
+    /// Comprueba los requisitos de las reglas y anota en cada regla los incumplidos.
+    ///
+    /// `proven_threshold` es el umbral (%) de la prueba de rango del ticket y
+    /// `signers` el emisor seguido de los co-firmantes válidos; None si aún no
+    /// se pueden mirar (pre-commit, antes de `cosign`). Devuelve si se cumple todo.
+    pub fn check(&mut self, proven_threshold: Option<u64>, signers: Option<&[TicketSigner]>) -> bool {
+        for hit in self.hits.iter_mut() {
+            hit.unmet.clear();
+            if let Some(score) = hit.min_human_score {
+                match proven_threshold {
+                    Some(proven) if proven >= score_percent(score) => {}
+                    Some(proven) => hit.unmet.push(format!("range proof only shows human score ≥ {:.2}", proven as f64 / 100.0)),
+                    None => hit.unmet.push("no humanity range proof".to_string()),
+                }
+            }
+            if let (Some(role), Some(signers)) = (&hit.cosign, signers) {
+                let issuer = signers.first().map(|s| s.alias.as_str());
+                let cosigned = signers.iter().skip(1).any(|s| s.role.eq_ignore_ascii_case(role) && Some(s.alias.as_str()) != issuer);
+                if !cosigned {
+                    hit.unmet.push(format!("no {} co-signature", role));
+                }
+            }
+        }
+        self.hits.iter().all(|h| h.unmet.is_empty())
+    }
+
+    /// Requisitos incumplidos, con la regla que los pide
+    pub fn unmet(&self) -> Vec<String> {
+        self.hits.iter().flat_map(|h| h.unmet.iter().map(move |u| format!("{}: {}", h.rule, u))).collect()
+    }
+}
+
+impl PolicyFile {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let policy: Self = toml::from_str(content).map_err(|e| format!("Invalid policy.toml: {}", e))?;
+        for (i, rule) in policy.rules.iter().enumerate() {
+            let invalid = |e: String| format!("Invalid policy.toml: {}: {}", rule_label(rule, i), e);
+            if let Some(mode) = &rule.mode {
+                Enforcement::parse(mode).map_err(invalid)?;
+            }
+            if let Some(score) = rule.min_human_score {
+                if !(0.0..=1.0).contains(&score) {
+                    return Err(invalid(format!("min_human_score {} out of range (0..1)", score)));
+                }
+            }
+            if rule.cosign.as_deref().is_some_and(|r| r.trim().is_empty()) {
+                return Err(invalid("empty cosign role".to_string()));
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Reglas que aplican a un commit sobre `scope.branch` que modifica `files`
+    pub fn evaluate(&self, scope: &PolicyScope, files: &[String]) -> PolicyDecision {
+        let branch = scope.branch.as_deref();
+        let mut hits = Vec::new();
+        let mut base = (scope.default, "default".to_string());
+        // Exigencia de cada archivo fijada por reglas de rutas (gana la última)
+        let mut file_modes: Vec<Option<(Enforcement, String)>> = vec![None; files.len()];
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !rule.applies_to_branch(branch) {
+                continue;
+            }
+            let label = rule_label(rule, i);
+            let matched: Vec<String> = if rule.paths.is_empty() {
+                vec![branch.map(|b| format!("branch {}", short_branch(b))).unwrap_or_else(|| "all commits".to_string())]
+            } else {
+                let indexes: Vec<usize> = (0..files.len()).filter(|f| rule.paths.iter().any(|p| glob_match(p, &files[*f]))).collect();
+                if indexes.is_empty() {
+                    continue;
+                }
+                if let Some(mode) = rule.enforcement() {
+                    for f in &indexes {
+                        file_modes[*f] = Some((mode, label.clone()));
+                    }
+                }
+                indexes.iter().map(|f| files[*f].clone()).collect()
+            };
+            if let (true, Some(mode)) = (rule.paths.is_empty(), rule.enforcement()) {
+                base = (mode, format!("rule {}", label));
+            }
+            hits.push(RuleHit {
+                rule: label,
+                matched,
+                effects: rule.effects(),
+                unmet: Vec::new(),
+                min_human_score: rule.min_human_score,
+                cosign: rule.cosign.clone(),
+            });
+        }
+        if let Some(configured) = scope.configured {
+            base = (configured, "git config".to_string());
+        }
+
+        // La configuración de Git es un suelo: las reglas de rutas solo la endurecen
+        let floor = scope.configured.map_or(0, |c| c.rank());
+        let (mode, mode_source) = file_modes
+            .into_iter()
+            .map(|m| match m {
+                Some((mode, rule)) if mode.rank() >= floor => (mode, format!("rule {}", rule)),
+                _ => base.clone(),
+            })
+            .max_by_key(|(mode, _)| mode.rank())
+            .unwrap_or(base);
+        PolicyDecision { mode, mode_source, hits }
+    }
+}
+
+fn rule_label(rule: &PolicyRule, index: usize) -> String {
+    rule.name.clone().unwrap_or_else(|| format!("rule #{}", index + 1))
+}
+
+fn score_percent(score: f64) -> u64 {
+    (score * 100.0).round() as u64
+}
+
+fn short_branch(branch: &str) -> &str {
+    branch.strip_prefix(BRANCH_PREFIX).unwrap_or(branch)
+}
+
+/// Patrón de rama: corto (`feature/*`) o ref completa (`refs/heads/feature/*`)
+fn branch_matches(pattern: &str, branch: &str) -> bool {
+    if pattern.starts_with("refs/") {
+        let full = if branch.starts_with("refs/") { branch.to_string() } else { format!("{}{}", BRANCH_PREFIX, branch) };
+        glob_match(pattern, &full)
+    } else {
+        glob_match(pattern, short_branch(branch))
+    }
+}
+
+/// `*` y `?` no cruzan `/`; `**` encaja con cualquier número de directorios
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_matches('/').split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| match_segments(rest, &path[i..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path_rest)) => match_segment(segment.as_bytes(), name.as_bytes()) && match_segments(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| match_segment(rest, &name[i..])),
+        Some((b'?', rest)) => !name.is_empty() && match_segment(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_segment(rest, &name[1..]),
+    }
+}
+
+/// policy.toml con el que se juzga `commit`: el de su padre (sin reglas si no lo tiene)
+pub fn policy_at(repo: &Repository, commit: &Commit, cache: &mut HistoryCache) -> Result<PolicyFile, String> {
+    match crate::trust::file_in_history(repo, commit.parent(0).ok(), POLICY_PATH, cache)? {
+        Some((source, content)) => PolicyFile::parse(&content).map_err(|e| format!("{} (from {})", e, source)),
+        None => Ok(PolicyFile::default()),
+    }
+}
+
+/// Política de un commit ya hecho
+pub fn decide(repo: &Repository, commit: &Commit, scope: &PolicyScope) -> Result<PolicyDecision, String> {
+    decide_with(repo, commit, scope, &mut HistoryCache::default())
+}
+
+/// `decide` dentro de un recorrido, compartiendo `cache` entre commits
+pub fn decide_with(repo: &Repository, commit: &Commit, scope: &PolicyScope, cache: &mut HistoryCache) -> Result<PolicyDecision, String> {
+    let files = crate::ci::changed_files(repo, commit);
+    Ok(policy_at(repo, commit, cache)?.evaluate(scope, &files))
+}
+
+/// Política del commit en curso (pre-commit, shim de firma): el árbol y el
+/// padre de `binding`, con el policy.toml del padre
+pub fn decide_pending(repo: &Repository, binding: &TicketBinding, scope: &PolicyScope) -> Result<PolicyDecision, String> {
+    let tree = git2::Oid::from_str(&binding.tree).and_then(|oid| repo.find_tree(oid)).map_err(|e| e.to_string())?;
+    let parent = match &binding.parent {
+        Some(parent) => Some(git2::Oid::from_str(parent).and_then(|oid| repo.find_commit(oid)).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let parent_tree = parent.as_ref().map(|p| p.tree()).transpose().map_err(|e| e.to_string())?;
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None).map_err(|e| e.to_string())?;
+    let files: Vec<String> = diff
+        .deltas()
+        .filter_map(|d| d.new_file().path().or_else(|| d.old_file().path()).map(|p| p.to_string_lossy().to_string()))
+        .collect();
+
+    let policy = match crate::trust::file_in_history(repo, parent, POLICY_PATH, &mut HistoryCache::default())? {
+        Some((_, content)) => PolicyFile::parse(&content)?,
+        None => PolicyFile::default(),
+    };
+    Ok(policy.evaluate(scope, &files))
+}
+
+/// Rama a la que apunta HEAD (`refs/heads/...`), también sin commits
+pub fn current_branch(repo: &Repository) -> Option<String> {
+    repo.find_reference("HEAD").ok()?.symbolic_target().filter(|t| t.starts_with(BRANCH_PREFIX)).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+[[rule]]
+name = "crypto"
+paths = ["src/crypto/**"]
+min_human_score = 0.7
+cosign = "Maintainer"
+
+[[rule]]
+name = "docs"
+paths = ["docs/**", "*.md"]
+mode = "audit"
+
+[[rule]]
+name = "main"
+branches = ["main"]
+mode = "strict"
+
+[[rule]]
+branches = ["feature/*"]
+mode = "audit"
+"#;
+
+    fn scope(branch: &str) -> PolicyScope {
+        PolicyScope { branch: Some(branch.to_string()), configured: None, default: Enforcement::Strict }
+    }
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn scopes_rules_by_path_and_branch() {
+        let policy = PolicyFile::parse(POLICY).unwrap();
+        assert!(PolicyFile::parse("[[rule]]\nmode = \"lenient\"").is_err());
+        assert!(PolicyFile::parse("[[rule]]\nmin_human_score = 70").is_err());
+        assert!(glob_match("src/**/*.rs", "src/a/b/c.rs") && glob_match("src/**", "src/x") && !glob_match("src/*", "src/a/b"));
+
+        // Solo documentación en main: la regla de rutas relaja la de la rama
+        let docs = policy.evaluate(&scope("refs/heads/main"), &files(&["docs/guide/intro.md", "README.md"]));
+        assert_eq!(docs.mode, Enforcement::Audit);
+        assert_eq!(docs.hits.iter().map(|h| h.rule.as_str()).collect::<Vec<_>>(), ["docs", "main"]);
+        // Con código, manda la más exigente
+        let mixed = policy.evaluate(&scope("main"), &files(&["docs/a.md", "src/lib.rs"]));
+        assert_eq!((mixed.mode, mixed.mode_source.as_str()), (Enforcement::Strict, "rule main"));
+        let feature = policy.evaluate(&scope("feature/x"), &files(&["src/lib.rs"]));
+        assert_eq!((feature.mode, feature.hits[0].rule.as_str()), (Enforcement::Audit, "rule #4"));
+        // La configuración de Git prevalece sobre las reglas de rama
+        let configured = PolicyScope { configured: Some(Enforcement::Signed), ..scope("feature/x") };
+        assert_eq!(policy.evaluate(&configured, &files(&["src/lib.rs"])).mode, Enforcement::Signed);
+        // ... y es un suelo para las reglas de rutas
+        let docs_configured = PolicyScope { configured: Some(Enforcement::Signed), ..scope("main") };
+        let docs = policy.evaluate(&docs_configured, &files(&["docs/a.md"]));
+        assert_eq!((docs.mode, docs.mode_source.as_str()), (Enforcement::Signed, "git config"));
+
+        let mut crypto = policy.evaluate(&scope("main"), &files(&["src/crypto/aes.rs"]));
+        assert_eq!(crypto.required_percent(), Some(70));
+        assert_eq!(crypto.cosign_roles(), ["Maintainer"]);
+        let signer = |alias: &str, role: &str| TicketSigner { alias: alias.into(), role: role.into(), key_id: String::new(), emails: Vec::new() };
+        assert!(!crypto.check(Some(50), Some(&[signer("dev", "Contributor")])));
+        assert_eq!(crypto.unmet().len(), 2);
+        assert!(crypto.hits[0].explain().starts_with("crypto [src/crypto/aes.rs]: human score ≥ 0.70, Maintainer co-signature ✗"));
+        // Pre-commit: la co-firma aún no se puede mirar
+        assert!(crypto.check(Some(70), None));
+        assert!(crypto.check(Some(82), Some(&[signer("dev", "Contributor"), signer("lead", "Maintainer")])));
+    }
+}
@@ -0,0 +1,202 @@
+//! Prueba de trabajo adaptativa (fallback de la batería de atención)
+//!
+//! Cuando la batería no cubre el costo entrópico de un commit y el daemon
+//! tiene `governance.pow_fallback` activo, en lugar de rechazar el commit
+//! emite un reto SHA256 ligado al árbol staged, padre y autor. La dificultad
+//! crece con el déficit: es fricción de gobernanza, no minería.
+//!
+//! La semilla se deriva del binding y de una sal aleatoria, así que cualquier
+//! verificador puede recomputarla desde el ticket sin consultar al daemon.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::ticket::TicketBinding;
+
+/// Separador de dominio de la semilla
+const POW_DOMAIN: &[u8] = b"cliff-watch-pow/v1";
+
+/// Dificultad mínima (bits a cero iniciales) para cualquier déficit
+pub const MIN_DIFFICULTY: u8 = 8;
+
+/// Dificultad máxima: ~16M hashes esperados
+pub const MAX_DIFFICULTY: u8 = 24;
+
+/// Commits `valid` (según una credencial de reputación de un emisor de
+/// confianza) a partir de los que una identidad cuenta como probada
+pub const PROVEN_COMMITS: usize = 16;
+
+/// Reto emitido por el daemon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PowChallenge {
+    /// Sal aleatoria (hex) que, junto al binding, determina la semilla
+    pub salt: String,
+    /// Semilla (hex) sobre la que se busca el nonce
+    pub seed: String,
+    /// Bits a cero iniciales exigidos
+    pub difficulty: u8,
+    /// Déficit de energía que motivó el reto
+    pub deficit: f64,
+}
+
+impl PowChallenge {
+    /// Hashes esperados para resolver el reto
+    pub fn expected_attempts(&self) -> u64 {
+        1u64 << self.difficulty.min(63)
+    }
+}
+
+/// Dificultad proporcional al déficit: `MIN + ⌈2·log2(1 + déficit)⌉`, acotada
+pub fn difficulty_from_deficit(deficit: f64) -> u8 {
+    if !deficit.is_finite() || deficit <= 0.0 {
+        return MIN_DIFFICULTY;
+    }
+    let extra = (2.0 * (1.0 + deficit).log2()).ceil();
+    (MIN_DIFFICULTY as f64 + extra).min(MAX_DIFFICULTY as f64) as u8
+}
+
+/// Dificultad para un contribuidor con `verified_commits` commits verificados:
+/// un bit menos desde `PROVEN_COMMITS` y otro por cada vez que se duplica,
+/// sin bajar de `MIN_DIFFICULTY`
+pub fn relax_for_history(difficulty: u8, verified_commits: usize) -> u8 {
+    if verified_commits < PROVEN_COMMITS {
+        return difficulty;
+    }
+    let relief = (verified_commits / PROVEN_COMMITS).ilog2() + 1;
+    difficulty.saturating_sub(relief.min(u8::MAX as u32) as u8).max(MIN_DIFFICULTY)
+}
+
+/// Semilla ligada al commit en curso
+pub fn challenge_seed(binding: &TicketBinding, salt: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(POW_DOMAIN);
+    for field in [binding.tree.as_str(), binding.parent.as_deref().unwrap_or("none"), binding.author.as_str()] {
+        hasher.update(field.as_bytes());
+        hasher.update([0u8]);
+    }
+    hasher.update(salt);
+    hasher.finalize().into()
+}
+
+/// Crea un reto con sal aleatoria para un binding y un déficit
+pub fn create_challenge(binding: &TicketBinding, deficit: f64) -> PowChallenge {
+    let salt: [u8; 16] = rand::random();
+    PowChallenge {
+        salt: hex::encode(salt),
+        seed: hex::encode(challenge_seed(binding, &salt)),
+        difficulty: difficulty_from_deficit(deficit),
+        deficit,
+    }
+}
+
+pub fn compute_hash(seed: &[u8; 32], nonce: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(seed);
+    hasher.update(nonce.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Indica si `hash` empieza por al menos `difficulty` bits a cero
+pub fn meets_difficulty(hash: &[u8; 32], difficulty: u8) -> bool {
+    let mut remaining = difficulty as u32;
+    for byte in hash {
+        if remaining == 0 {
+            return true;
+        }
+        let leading = byte.leading_zeros();
+        if leading < remaining.min(8) {
+            return false;
+        }
+        remaining = remaining.saturating_sub(8);
+    }
+    remaining == 0
+}
+
+/// Verifica una solución recomputando la semilla desde el binding.
+///
+/// Devuelve el hash ganador.
+pub fn verify_solution(binding: &TicketBinding, salt_hex: &str, difficulty: u8, nonce: u64) -> Result<[u8; 32], String> {
+    let salt = hex::decode(salt_hex).map_err(|_| "Invalid proof-of-work salt".to_string())?;
+    let hash = compute_hash(&challenge_seed(binding, &salt), nonce);
+    if meets_difficulty(&hash, difficulty) {
+        Ok(hash)
+    } else {
+        Err("Proof of work does not meet the difficulty".to_string())
+    }
+}
+
+/// Busca un nonce válido. `progress` recibe los intentos acumulados cada 65536 hashes.
+pub fn solve(challenge: &PowChallenge, mut progress: impl FnMut(u64)) -> Result<u64, String> {
+    let seed: [u8; 32] = hex::decode(&challenge.seed)
+        .ok()
+        .and_then(|s| s.try_into().ok())
+        .ok_or("Invalid proof-of-work seed")?;
+
+    for nonce in 0..u64::MAX {
+        if meets_difficulty(&compute_hash(&seed, nonce), challenge.difficulty) {
+            progress(nonce + 1);
+            return Ok(nonce);
+        }
+        if nonce & 0xFFFF == 0xFFFF {
+            progress(nonce + 1);
+        }
+    }
+    Err("Proof-of-work nonce space exhausted".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding() -> TicketBinding {
+        TicketBinding {
+            tree: "4b825dc642cb6eb9a060e54bf8d69288fbee4904".to_string(),
+            parent: None,
+            author: "dev@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn difficulty_scales_with_deficit() {
+        assert_eq!(difficulty_from_deficit(0.0), MIN_DIFFICULTY);
+        assert!(difficulty_from_deficit(5.0) < difficulty_from_deficit(50.0));
+        assert_eq!(difficulty_from_deficit(1e9), MAX_DIFFICULTY);
+        assert_eq!(difficulty_from_deficit(f64::NAN), MIN_DIFFICULTY);
+    }
+
+    #[test]
+    fn proven_contributors_get_easier_challenges() {
+        assert_eq!(relax_for_history(20, 0), 20);
+        assert_eq!(relax_for_history(20, PROVEN_COMMITS - 1), 20);
+        assert_eq!(relax_for_history(20, PROVEN_COMMITS), 19);
+        assert_eq!(relax_for_history(20, PROVEN_COMMITS * 4), 17);
+        assert_eq!(relax_for_history(MAX_DIFFICULTY, usize::MAX), MIN_DIFFICULTY);
+        assert_eq!(relax_for_history(MIN_DIFFICULTY, PROVEN_COMMITS * 8), MIN_DIFFICULTY);
+    }
+
+    #[test]
+    fn leading_zero_bits() {
+        let mut hash = [0xFFu8; 32];
+        assert!(meets_difficulty(&hash, 0));
+        assert!(!meets_difficulty(&hash, 1));
+        hash[0] = 0x00;
+        hash[1] = 0x1F;
+        assert!(meets_difficulty(&hash, 11));
+        assert!(!meets_difficulty(&hash, 12));
+    }
+
+    #[test]
+    fn solution_is_bound_to_the_commit() {
+        let mut challenge = create_challenge(&binding(), 1.0);
+        challenge.difficulty = 10;
+        let nonce = solve(&challenge, |_| {}).unwrap();
+        assert!(verify_solution(&binding(), &challenge.salt, 10, nonce).is_ok());
+
+        // Otro árbol u otra sal producen otra semilla: el nonce no es reutilizable
+        let salt = hex::decode(&challenge.salt).unwrap();
+        let mut other = binding();
+        other.tree = "0000000000000000000000000000000000000000".to_string();
+        assert_ne!(challenge_seed(&other, &salt), challenge_seed(&binding(), &salt));
+        assert_ne!(challenge_seed(&binding(), b"other-salt"), challenge_seed(&binding(), &salt));
+    }
+}
@@ -5,6 +5,11 @@
 
 use serde::{Serialize, Deserialize};
 
+use crate::pow::PowChallenge;
+use crate::report::SentinelReport;
+use crate::ticket::TicketBinding;
+use crate::trust::TrustedKey;
+
 /// Peticiones que el CLI envía al Daemon
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
@@ -12,12 +17,53 @@ pub enum Request {
     GetStatus,
     /// Solicita las métricas cinemáticas actuales
     GetMetrics,
-    /// Solicita un ticket de atención para pagar un costo entrópico
-    GetTicket { cost: f64 },
+    /// Solicita un ticket de atención para pagar un costo entrópico,
+    /// ligado al árbol staged, padre y autor del commit en curso.
+    /// `threshold`: umbral (%) que debe probar la prueba de rango si el score
+    /// lo alcanza (policy.toml `min_human_score`); por defecto, el de `zkp`
+    GetTicket {
+        cost: f64,
+        binding: TicketBinding,
+        #[serde(default)]
+        threshold: Option<u64>,
+    },
+    /// Canjea la solución de un reto `PowChallenge` por un ticket `mode=pow`
+    RedeemPow {
+        cost: f64,
+        binding: TicketBinding,
+        salt: String,
+        nonce: u64,
+        #[serde(default)]
+        threshold: Option<u64>,
+    },
     /// Prueba de conexión
     Ping,
-    /// Solicita datos del Witness para certificación v2.0
-    GetWitness { reset: bool },
+    /// Solicita el Sentinel Report sellado del commit en curso;
+    /// `reset` abre una nueva ventana de observación
+    GetReport { reset: bool },
+    /// Co-firma el payload de un ticket ajeno (pair programming, aval de maintainer)
+    Cosign { payload: String },
+    /// Identidad del daemon (clave pública y `did:key`)
+    GetIdentity,
+    /// Credencial de reputación de `subject` (hex) sobre los últimos `limit`
+    /// commits de `repo`, resumidos y firmados por el daemon (ver `credential::issue`)
+    IssueCredential { repo: String, subject: String, limit: usize },
+    /// Aval de Maintainer sobre una entrada de trust.toml (ver `trust::endorse`).
+    /// `config` es el trust.toml destino y `roots` las raíces fijadas (hex): el
+    /// daemon solo firma si es Maintainer efectivo en él
+    Endorse {
+        key: TrustedKey,
+        config: String,
+        #[serde(default)]
+        roots: Vec<String>,
+    },
+    /// Firma de Maintainer sobre trust.toml completo (TOML, sin `signature`),
+    /// con las mismas condiciones que `Endorse`
+    SignTrust {
+        config: String,
+        #[serde(default)]
+        roots: Vec<String>,
+    },
 }
 
 /// Respuestas que el Daemon envía al CLI
@@ -40,6 +86,7 @@ pub enum Response {
         focus_time_mins: f64,
         edit_bursts: usize,
         is_focused: bool,
+        /// Prueba de rango serializada (`HumanityProof::to_trailer_value`)
         zkp_proof: Option<String>,
         #[serde(default)]
         score_history: Vec<f64>,
@@ -49,11 +96,33 @@ pub enum Response {
         success: bool,
         signature: Option<Vec<u8>>,
         message: String,
+        /// Payload canónico cubierto por `signature` (ver `ticket::TicketPayload`)
+        #[serde(default)]
+        payload: Option<String>,
+        /// Trailer `Cliff-Watch-Proof` (prueba de rango) comprometido en el payload
+        #[serde(default)]
+        proof: Option<String>,
+        /// Trailer `Cliff-Watch-Log`: head firmado del log que incluye el ticket
+        #[serde(default)]
+        log_head: Option<String>,
+    },
+    /// La batería no cubre el costo: el déficit puede pagarse con prueba de trabajo
+    PowChallenge {
+        challenge: PowChallenge,
+        message: String,
     },
-    /// Datos del Witness para el commit
-    Witness {
-        data: String, // JSON serializado de WitnessData
+    /// Sentinel Report sellado para el trailer Cliff-Watch-Witness
+    Report {
+        report: Box<SentinelReport>,
     },
+    /// Co-firma lista para el trailer `Cliff-Watch-Cosign`
+    Cosignature { value: String },
+    /// Identidad con la que firma el daemon
+    Identity { public_key: String, did: String, backend: String },
+    /// Credencial verificable firmada (JSON)
+    Credential { document: String },
+    /// Aval o firma de trust.toml (`kid=…;at=…:sig=…`)
+    TrustSignature { value: String },
     /// Respuesta a Ping
     Pong,
     /// Error en la operación
@@ -0,0 +1,291 @@
+//! Sentinel Report — contrato de atestación por commit
+//!
+//! Contenedor inmutable, serializable y firmable de todo lo que el daemon
+//! observó entre dos commits. Sustituye al antiguo JSON `WitnessData`: el
+//! trailer `Cliff-Watch-Witness` transporta ahora un `SentinelReport` sellado.
+//!
+//! El informe lleva el nonce del ticket del mismo commit dentro del contenido
+//! firmado, así que no puede copiarse a otro commit ni escribirse a mano.
+//!
+//! El pipeline vive en submódulos:
+//! - [`collector`]: vuelca el estado vivo del `GitMonitor`/`FileMonitor` en un `MetricsBundle`.
+//! - [`builder`]: ensambla el informe validando sus invariantes estructurales.
+//! - [`hash`]: hash canónico del contenido (excluye el sobre de integridad).
+//! - [`sign`]: firma/verificación del hash con la identidad del daemon.
+//! - [`finalize`]: sella el sobre de integridad.
+//!
+//! Este módulo no contiene heurísticas ni política: solo el esquema.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::focus_session::FocusMetrics;
+use crate::monitor::MonitorStatsSnapshot;
+use crate::mouse_sentinel::KinematicMetrics;
+
+pub mod builder;
+pub mod collector;
+pub mod finalize;
+pub mod hash;
+pub mod sign;
+
+/// Versión del esquema. Incrementar solo ante cambios semánticos incompatibles.
+pub const SENTINEL_REPORT_VERSION: u32 = 1;
+
+/// Clave del trailer que transporta el informe en el mensaje de commit
+pub const REPORT_TRAILER: &str = "Cliff-Watch-Witness";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentinelReport {
+    /// Versión del esquema del informe
+    pub version: u32,
+    /// Identificador opaco y resistente a colisiones
+    pub report_id: String,
+    /// Instante en que se congelaron las métricas (UNIX epoch, µs)
+    pub metrics_finalized_at_us: u128,
+    /// Identificador del workspace observado (hash de la raíz vigilada)
+    pub workspace_id: String,
+    /// Nonce del ticket emitido en esta ventana (liga el informe a su commit)
+    #[serde(default)]
+    pub ticket_nonce: Option<String>,
+    /// Ventana de observación cubierta por el informe
+    pub observation: ObservationWindow,
+    /// Grupos de métricas recolectadas
+    pub metrics: MetricsBundle,
+    /// Metadatos de integridad (hash de contenido y firmas)
+    pub integrity: IntegrityEnvelope,
+}
+
+impl SentinelReport {
+    /// Crea un informe sin sellar. El instante de finalización se fija aquí
+    /// para que el llamante no pueda manipularlo.
+    pub fn new(
+        report_id: impl Into<String>,
+        workspace_id: impl Into<String>,
+        observation: ObservationWindow,
+        metrics: MetricsBundle,
+        integrity: IntegrityEnvelope,
+    ) -> Self {
+        Self {
+            version: SENTINEL_REPORT_VERSION,
+            report_id: report_id.into(),
+            metrics_finalized_at_us: now_us(),
+            workspace_id: workspace_id.into(),
+            ticket_nonce: None,
+            observation,
+            metrics,
+            integrity,
+        }
+    }
+
+    /// Invariantes de esquema que todo informe válido debe cumplir (no es política).
+    pub fn is_structurally_sound(&self) -> bool {
+        self.version == SENTINEL_REPORT_VERSION
+            && self.observation.start_us <= self.observation.end_us
+            && self.observation.raw_event_count >= self.observation.emitted_event_count
+    }
+
+    /// Serializa a JSON compacto (una sola línea) para el trailer
+    pub fn to_trailer_value(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Parsea el valor del trailer `Cliff-Watch-Witness`
+    pub fn parse_trailer_value(value: &str) -> Result<Self, String> {
+        serde_json::from_str(value.trim()).map_err(|e| format!("Invalid Sentinel Report: {}", e))
+    }
+
+    /// Probabilidad de autoría humana derivada de las métricas de foco
+    pub fn human_probability(&self) -> HumanProbability {
+        self.metrics
+            .focus
+            .as_ref()
+            .map(calculate_human_probability)
+            .unwrap_or(HumanProbability::Unknown)
+    }
+}
+
+/// Ventana de observación del informe
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObservationWindow {
+    /// Inicio de la ventana: informe anterior o arranque del daemon (µs)
+    pub start_us: u128,
+    /// Fin de la ventana: momento de emisión (µs)
+    pub end_us: u128,
+    /// Eventos de bajo nivel observados (antes de filtrar)
+    pub raw_event_count: u64,
+    /// Eventos semánticos emitidos tras debounce y descarte
+    pub emitted_event_count: u64,
+}
+
+/// Señales del `GitMonitor` sobre la batería de atención
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttentionMetrics {
+    /// Nivel de la batería de atención en el momento del informe
+    pub battery_level: f64,
+    /// Acoplamiento edición/navegación
+    pub coupling: f64,
+    /// Human Score (misma fórmula que `GetMetrics`)
+    pub human_score: f64,
+}
+
+/// Métricas estructurales del código editado
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructuralMetrics {
+    /// NCD del código (compresión Zstd, anti-paste)
+    pub ncd: f64,
+}
+
+/// Grupos de métricas. Los ausentes se omiten; `extensions` mantiene orden determinista.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsBundle {
+    #[serde(default)]
+    pub focus: Option<FocusMetrics>,
+    #[serde(default)]
+    pub kinematic: Option<KinematicMetrics>,
+    #[serde(default)]
+    pub structural: Option<StructuralMetrics>,
+    #[serde(default)]
+    pub attention: Option<AttentionMetrics>,
+    #[serde(default)]
+    pub monitor: Option<MonitorStatsSnapshot>,
+    /// Punto de extensión para métricas futuras (con espacio de nombres)
+    #[serde(default)]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+/// Sobre de integridad: lo único que el hash de contenido no cubre
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityEnvelope {
+    /// SHA256 (hex) de la forma canónica del informe sin este sobre
+    pub content_hash: String,
+    /// Firmas del `content_hash`, indexadas por key id
+    pub signatures: BTreeMap<String, String>,
+    /// Prueba de trabajo opcional
+    #[serde(default)]
+    pub proof_of_work: Option<ProofOfWork>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofOfWork {
+    /// Dificultad en bits a cero iniciales
+    pub difficulty: u8,
+    /// Nonce que satisface la dificultad
+    pub nonce: u64,
+    /// Hash resultante (hex)
+    pub hash: String,
+}
+
+/// Niveles de probabilidad de que el código fue escrito por un humano
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HumanProbability {
+    /// Alta probabilidad: tiempo de foco significativo + ediciones
+    High,
+    /// Probabilidad media: algo de foco, pocas ediciones
+    Medium,
+    /// Probabilidad baja: copy-paste o asistente IA
+    Low,
+    /// Sin datos: daemon no detectó actividad
+    Unknown,
+}
+
+impl std::fmt::Display for HumanProbability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HumanProbability::High => write!(f, "High"),
+            HumanProbability::Medium => write!(f, "Medium"),
+            HumanProbability::Low => write!(f, "Low"),
+            HumanProbability::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Calcula la probabilidad de que el código fue escrito por un humano
+/// basándose en las métricas de foco recolectadas.
+///
+/// ## Criterios
+/// - **High**: >= 5 min de foco O >= 10 edit bursts O >= 3 archivos
+/// - **Medium**: >= 1 min de foco O >= 3 edit bursts O >= 1 archivo
+/// - **Low**: < 1 min de foco Y < 3 edit bursts
+/// - **Unknown**: Sin métricas
+pub fn calculate_human_probability(metrics: &FocusMetrics) -> HumanProbability {
+    let score = metrics.total_focus_mins * 10.0
+        + metrics.edit_burst_count as f64 * 5.0
+        + metrics.unique_files as f64 * 3.0
+        + metrics.navigation_events as f64 * 1.0;
+
+    if score >= 50.0 {
+        HumanProbability::High
+    } else if score >= 15.0 {
+        HumanProbability::Medium
+    } else if score > 0.0 {
+        HumanProbability::Low
+    } else {
+        HumanProbability::Unknown
+    }
+}
+
+/// Identificador estable de un workspace: SHA256 (16 hex) de su ruta canónica
+pub fn workspace_id(root: &std::path::Path) -> String {
+    let canonical = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    hex::encode(&crate::crypto::calculate_sha256(canonical.to_string_lossy().as_bytes())[..8])
+}
+
+/// Identificador aleatorio de 128 bits (hex)
+pub fn new_report_id() -> String {
+    use rand::RngCore;
+    let mut id = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut id);
+    hex::encode(id)
+}
+
+#[inline]
+pub fn now_us() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_roundtrips_through_trailer_value() {
+        let metrics = MetricsBundle {
+            focus: Some(FocusMetrics { total_focus_mins: 6.0, edit_burst_count: 4, ..Default::default() }),
+            monitor: Some(MonitorStatsSnapshot { emitted: 5, debounced: 3, ..Default::default() }),
+            ..Default::default()
+        };
+
+        let report = SentinelReport::new(
+            new_report_id(),
+            "ws",
+            ObservationWindow { start_us: 1, end_us: 2, raw_event_count: 8, emitted_event_count: 5 },
+            metrics,
+            IntegrityEnvelope::default(),
+        );
+
+        let value = report.to_trailer_value();
+        assert!(!value.contains('\n'), "El trailer debe ocupar una sola línea");
+        let back = SentinelReport::parse_trailer_value(&value).unwrap();
+        assert_eq!(back, report);
+        assert!(back.is_structurally_sound());
+        assert_eq!(back.human_probability(), HumanProbability::High);
+    }
+
+    #[test]
+    fn human_probability_levels() {
+        let none = FocusMetrics::default();
+        assert_eq!(calculate_human_probability(&none), HumanProbability::Unknown);
+
+        let low = FocusMetrics { navigation_events: 3, ..Default::default() };
+        assert_eq!(calculate_human_probability(&low), HumanProbability::Low);
+
+        let medium = FocusMetrics { edit_burst_count: 3, ..Default::default() };
+        assert_eq!(calculate_human_probability(&medium), HumanProbability::Medium);
+    }
+}
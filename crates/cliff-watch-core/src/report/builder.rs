@@ -0,0 +1,103 @@
+//! Ensamblado determinista de `SentinelReport`
+//!
+//! Solo recoge métricas ya calculadas y valida invariantes: no calcula,
+//! no hashea ni firma (ver `finalize`).
+
+use super::{IntegrityEnvelope, MetricsBundle, ObservationWindow, SentinelReport};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BuilderError {
+    #[error("missing report_id")]
+    MissingReportId,
+
+    #[error("missing workspace_id")]
+    MissingWorkspaceId,
+
+    #[error("missing observation window")]
+    MissingObservation,
+
+    #[error("observation window is structurally invalid")]
+    InvalidObservation,
+}
+
+/// Builder de `SentinelReport`. El sobre de integridad queda vacío hasta `finalize`.
+#[derive(Debug, Default)]
+pub struct SentinelBuilder {
+    report_id: Option<String>,
+    workspace_id: Option<String>,
+    observation: Option<ObservationWindow>,
+    ticket_nonce: Option<String>,
+    metrics: MetricsBundle,
+}
+
+impl SentinelBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report_id(mut self, id: impl Into<String>) -> Self {
+        self.report_id = Some(id.into());
+        self
+    }
+
+    pub fn workspace_id(mut self, id: impl Into<String>) -> Self {
+        self.workspace_id = Some(id.into());
+        self
+    }
+
+    pub fn observation(mut self, observation: ObservationWindow) -> Self {
+        self.observation = Some(observation);
+        self
+    }
+
+    /// Liga el informe al ticket emitido en la misma ventana
+    pub fn ticket_nonce(mut self, nonce: Option<String>) -> Self {
+        self.ticket_nonce = nonce;
+        self
+    }
+
+    pub fn metrics(mut self, metrics: MetricsBundle) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Emite un informe estructuralmente válido, sin sellar
+    pub fn build(self) -> Result<SentinelReport, BuilderError> {
+        let report_id = self.report_id.ok_or(BuilderError::MissingReportId)?;
+        let workspace_id = self.workspace_id.ok_or(BuilderError::MissingWorkspaceId)?;
+        let observation = self.observation.ok_or(BuilderError::MissingObservation)?;
+
+        let mut report = SentinelReport::new(report_id, workspace_id, observation, self.metrics, IntegrityEnvelope::default());
+        report.ticket_nonce = self.ticket_nonce;
+        if !report.is_structurally_sound() {
+            return Err(BuilderError::InvalidObservation);
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_and_validates_reports() {
+        let report = SentinelBuilder::new()
+            .report_id("r-001")
+            .workspace_id("ws-123")
+            .observation(ObservationWindow { start_us: 1, end_us: 10, raw_event_count: 10, emitted_event_count: 5 })
+            .build()
+            .unwrap();
+        assert_eq!(report.report_id, "r-001");
+        assert!(report.metrics_finalized_at_us > 0);
+        assert!(report.integrity.signatures.is_empty());
+
+        let inverted = SentinelBuilder::new()
+            .report_id("r-002")
+            .workspace_id("ws-123")
+            .observation(ObservationWindow { start_us: 10, end_us: 1, raw_event_count: 0, emitted_event_count: 0 })
+            .build();
+        assert!(matches!(inverted, Err(BuilderError::InvalidObservation)));
+        assert!(matches!(SentinelBuilder::new().build(), Err(BuilderError::MissingReportId)));
+    }
+}
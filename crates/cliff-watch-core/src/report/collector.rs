@@ -0,0 +1,103 @@
+//! Recolección de métricas para el `SentinelReport`
+//!
+//! Traduce el estado vivo del daemon (`GitMonitor` y contadores del
+//! `FileMonitor`) a un `MetricsBundle` y a una `ObservationWindow`.
+//! No aplica umbrales ni política.
+
+use crate::focus_session::FocusMetrics;
+use crate::monitor::MonitorStatsSnapshot;
+use crate::mouse_sentinel::KinematicMetrics;
+
+use super::{AttentionMetrics, MetricsBundle, ObservationWindow, StructuralMetrics};
+
+/// Entradas disponibles en el momento del informe
+#[derive(Debug, Default)]
+pub struct CollectionInput<'a> {
+    pub focus: Option<&'a FocusMetrics>,
+    pub kinematic: Option<&'a KinematicMetrics>,
+    pub code_ncd: Option<f64>,
+    pub attention: Option<AttentionMetrics>,
+    pub monitor: Option<MonitorStatsSnapshot>,
+}
+
+/// Vuelca las entradas en un `MetricsBundle`.
+///
+/// Los valores no finitos no sobreviven a JSON (serde los escribe como `null`),
+/// así que un grupo con NaN/inf se omite en lugar de producir un informe ilegible.
+pub fn collect_metrics(input: CollectionInput) -> MetricsBundle {
+    let kinematic = input.kinematic.filter(|k| {
+        [k.ldlj, k.velocity_entropy, k.curvature_entropy, k.throughput, k.burstiness, k.ncd]
+            .iter()
+            .all(|v| v.is_finite())
+    });
+
+    MetricsBundle {
+        focus: input.focus.filter(|f| f.total_focus_mins.is_finite()).cloned(),
+        kinematic: kinematic.cloned(),
+        structural: input.code_ncd.filter(|n| n.is_finite()).map(|ncd| StructuralMetrics { ncd }),
+        attention: input
+            .attention
+            .filter(|a| a.battery_level.is_finite() && a.coupling.is_finite() && a.human_score.is_finite()),
+        monitor: input.monitor,
+        extensions: Default::default(),
+    }
+}
+
+/// Punto de partida de la ventana de observación en curso.
+///
+/// Los contadores del daemon son acumulativos; el cursor guarda su valor al
+/// emitir el informe anterior para que cada informe cubra solo su ventana.
+#[derive(Debug, Clone)]
+pub struct ObservationCursor {
+    start_us: u128,
+    input_events: u64,
+    monitor: MonitorStatsSnapshot,
+}
+
+impl ObservationCursor {
+    pub fn new(start_us: u128, input_events: u64, monitor: MonitorStatsSnapshot) -> Self {
+        Self { start_us, input_events, monitor }
+    }
+
+    /// Ventana desde el cursor hasta `end_us` y contadores del monitor de esa ventana
+    pub fn window(&self, end_us: u128, input_events: u64, monitor: &MonitorStatsSnapshot) -> (ObservationWindow, MonitorStatsSnapshot) {
+        let delta = monitor.since(&self.monitor);
+        let window = ObservationWindow {
+            start_us: self.start_us.min(end_us),
+            end_us,
+            raw_event_count: delta.raw_event_count() + input_events.saturating_sub(self.input_events),
+            emitted_event_count: delta.emitted,
+        };
+        (window, delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_reports_only_the_current_window() {
+        let first = MonitorStatsSnapshot { emitted: 4, debounced: 2, ..Default::default() };
+        let cursor = ObservationCursor::new(100, 10, first);
+
+        let now = MonitorStatsSnapshot { emitted: 7, debounced: 3, raw_dropped_overflow: 1, ..Default::default() };
+        let (window, delta) = cursor.window(200, 15, &now);
+
+        assert_eq!(delta.emitted, 3);
+        assert_eq!(window.emitted_event_count, 3);
+        assert_eq!(window.raw_event_count, 3 + 1 + 1 + 5);
+        assert_eq!((window.start_us, window.end_us), (100, 200));
+    }
+
+    #[test]
+    fn non_finite_groups_are_dropped() {
+        let bundle = collect_metrics(CollectionInput {
+            code_ncd: Some(f64::NAN),
+            attention: Some(AttentionMetrics { battery_level: 1.0, coupling: 0.5, human_score: 0.9 }),
+            ..Default::default()
+        });
+        assert!(bundle.structural.is_none());
+        assert!(bundle.attention.is_some());
+    }
+}
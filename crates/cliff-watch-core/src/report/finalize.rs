@@ -0,0 +1,95 @@
+//! Sellado del `SentinelReport`
+//!
+//! Flujo: forma canónica sin integridad → SHA256 → firma del hash →
+//! sobre de integridad. Este paso sella la verdad; no la juzga.
+
+use std::collections::BTreeMap;
+
+use super::hash::{hash_report, HashError};
+use super::sign::{sign_content_hash, SignError};
+use super::{IntegrityEnvelope, ProofOfWork, SentinelReport};
+use crate::crypto::DaemonIdentity;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FinalizeError {
+    #[error("report fails structural invariants")]
+    Unsound,
+
+    #[error(transparent)]
+    Hash(#[from] HashError),
+
+    #[error(transparent)]
+    Signing(#[from] SignError),
+}
+
+/// Sella el informe: calcula su content hash y lo firma con la identidad del daemon.
+///
+/// `pow` es la prueba de trabajo (ya verificada) con la que se pagó el ticket
+/// de la ventana, si la hubo. Cualquier sobre previo se descarta.
+pub fn finalize_report(
+    mut report: SentinelReport,
+    identity: &DaemonIdentity,
+    pow: Option<ProofOfWork>,
+) -> Result<SentinelReport, FinalizeError> {
+    if !report.is_structurally_sound() {
+        return Err(FinalizeError::Unsound);
+    }
+
+    let content_hash = hash_report(&report)?;
+    let (kid, signature) = sign_content_hash(identity, &content_hash)?;
+
+    let mut signatures = BTreeMap::new();
+    signatures.insert(kid, signature);
+
+    report.integrity = IntegrityEnvelope {
+        content_hash,
+        signatures,
+        proof_of_work: pow,
+    };
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::hash::content_hash_matches;
+    use crate::report::sign::verify_all_signatures;
+    use crate::report::{MetricsBundle, ObservationWindow};
+    use ed25519_dalek::SigningKey;
+
+    fn report() -> SentinelReport {
+        SentinelReport::new(
+            "r-001",
+            "ws",
+            ObservationWindow { start_us: 1, end_us: 2, raw_event_count: 10, emitted_event_count: 5 },
+            MetricsBundle::default(),
+            IntegrityEnvelope::default(),
+        )
+    }
+
+    #[test]
+    fn finalized_report_verifies_and_detects_tampering() {
+        let identity = DaemonIdentity::Software(SigningKey::generate(&mut rand::rngs::OsRng));
+        let public_key = identity.public_key();
+
+        let sealed = finalize_report(report(), &identity, None).unwrap();
+        assert!(content_hash_matches(&sealed));
+        assert!(verify_all_signatures(&sealed.integrity.content_hash, &sealed.integrity.signatures, |_| Some(public_key.clone())).is_ok());
+
+        // Sobrevive al trailer
+        let parsed = SentinelReport::parse_trailer_value(&sealed.to_trailer_value()).unwrap();
+        assert!(content_hash_matches(&parsed));
+
+        let mut tampered = parsed;
+        tampered.observation.raw_event_count = 99;
+        assert!(!content_hash_matches(&tampered));
+    }
+
+    #[test]
+    fn refuses_unsound_reports() {
+        let identity = DaemonIdentity::Software(SigningKey::generate(&mut rand::rngs::OsRng));
+        let mut unsound = report();
+        unsound.observation.emitted_event_count = 11;
+        assert!(matches!(finalize_report(unsound, &identity, None), Err(FinalizeError::Unsound)));
+    }
+}
@@ -0,0 +1,80 @@
+//! Hash canónico del contenido de un `SentinelReport`
+//!
+//! La forma canónica es el JSON serde del informe sin el sobre de integridad:
+//! el orden de campos es el de los structs y `extensions` es un `BTreeMap`,
+//! así que la serialización es determinista.
+
+use serde::Serialize;
+
+use super::{MetricsBundle, ObservationWindow, SentinelReport};
+
+#[derive(Debug, thiserror::Error)]
+pub enum HashError {
+    #[error("serialization failed")]
+    SerializationFailed,
+}
+
+/// Vista canónica y hasheable de un `SentinelReport`
+#[derive(Serialize)]
+struct CanonicalReportView<'a> {
+    version: u32,
+    report_id: &'a str,
+    metrics_finalized_at_us: u128,
+    workspace_id: &'a str,
+    ticket_nonce: Option<&'a str>,
+    observation: &'a ObservationWindow,
+    metrics: &'a MetricsBundle,
+}
+
+impl<'a> From<&'a SentinelReport> for CanonicalReportView<'a> {
+    fn from(r: &'a SentinelReport) -> Self {
+        Self {
+            version: r.version,
+            report_id: &r.report_id,
+            metrics_finalized_at_us: r.metrics_finalized_at_us,
+            workspace_id: &r.workspace_id,
+            ticket_nonce: r.ticket_nonce.as_deref(),
+            observation: &r.observation,
+            metrics: &r.metrics,
+        }
+    }
+}
+
+/// Bytes canónicos del informe (excluye `integrity`)
+pub fn canonical_bytes(report: &SentinelReport) -> Result<Vec<u8>, HashError> {
+    serde_json::to_vec(&CanonicalReportView::from(report)).map_err(|_| HashError::SerializationFailed)
+}
+
+/// SHA256 (hex) de la forma canónica del informe
+pub fn hash_report(report: &SentinelReport) -> Result<String, HashError> {
+    Ok(hex::encode(crate::crypto::calculate_sha256(&canonical_bytes(report)?)))
+}
+
+/// Indica si `integrity.content_hash` corresponde al contenido actual
+pub fn content_hash_matches(report: &SentinelReport) -> bool {
+    hash_report(report).is_ok_and(|h| h == report.integrity.content_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::IntegrityEnvelope;
+
+    #[test]
+    fn hash_ignores_integrity_and_tracks_content() {
+        let mut report = SentinelReport::new(
+            "r-001",
+            "ws",
+            ObservationWindow { start_us: 1, end_us: 2, raw_event_count: 3, emitted_event_count: 1 },
+            MetricsBundle::default(),
+            IntegrityEnvelope::default(),
+        );
+        let h = hash_report(&report).unwrap();
+
+        report.integrity.signatures.insert("kid".into(), "sig".into());
+        assert_eq!(hash_report(&report).unwrap(), h, "Las firmas no forman parte del contenido");
+
+        report.observation.emitted_event_count = 2;
+        assert_ne!(hash_report(&report).unwrap(), h);
+    }
+}
@@ -0,0 +1,92 @@
+//! Firmas del `SentinelReport`
+//!
+//! Se firma el `content_hash` en hex (no el informe entero): el hash ya
+//! compromete todo el contenido canónico. Las firmas se indexan por key id,
+//! igual que el campo `kid` de los tickets.
+
+use std::collections::BTreeMap;
+
+use crate::crypto::{DaemonIdentity, PublicKey};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignError {
+    #[error("signing failed: {0}")]
+    Signing(String),
+
+    #[error("signature verification failed")]
+    InvalidSignature,
+
+    #[error("no signature from a known key")]
+    UnknownKey,
+}
+
+/// Firma un content hash con la identidad del daemon. Devuelve `(kid, firma hex)`.
+pub fn sign_content_hash(identity: &DaemonIdentity, content_hash: &str) -> Result<(String, String), SignError> {
+    let signature = identity.sign(content_hash.as_bytes()).map_err(SignError::Signing)?;
+    Ok((identity.public_key().key_id(), hex::encode(signature)))
+}
+
+/// Verifica una firma concreta de un content hash
+pub fn verify_signature(public_key: &PublicKey, content_hash: &str, signature_hex: &str) -> Result<(), SignError> {
+    let signature = hex::decode(signature_hex).map_err(|_| SignError::InvalidSignature)?;
+    if public_key.verify(content_hash.as_bytes(), &signature) {
+        Ok(())
+    } else {
+        Err(SignError::InvalidSignature)
+    }
+}
+
+/// Verifica las firmas cuyo kid resuelve `resolve` y devuelve los kids válidos.
+///
+/// Falla en la primera firma inválida de una clave conocida; las firmas de
+/// claves desconocidas se ignoran, y si no queda ninguna conocida es `UnknownKey`.
+pub fn verify_all_signatures<F>(
+    content_hash: &str,
+    signatures: &BTreeMap<String, String>,
+    mut resolve: F,
+) -> Result<Vec<String>, SignError>
+where
+    F: FnMut(&str) -> Option<PublicKey>,
+{
+    let mut verified = Vec::new();
+    for (kid, signature) in signatures {
+        if let Some(public_key) = resolve(kid) {
+            verify_signature(&public_key, content_hash, signature)?;
+            verified.push(kid.clone());
+        }
+    }
+
+    if verified.is_empty() {
+        return Err(SignError::UnknownKey);
+    }
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+
+    #[test]
+    fn sign_and_verify_roundtrip() {
+        let identity = DaemonIdentity::Software(SigningKey::generate(&mut rand::rngs::OsRng));
+        let public_key = identity.public_key();
+
+        let (kid, signature) = sign_content_hash(&identity, "abc123").unwrap();
+        assert_eq!(kid, public_key.key_id());
+        assert!(verify_signature(&public_key, "abc123", &signature).is_ok());
+        assert!(verify_signature(&public_key, "abc124", &signature).is_err());
+
+        let mut signatures = BTreeMap::new();
+        signatures.insert(kid.clone(), signature);
+        let resolved = public_key.clone();
+        assert_eq!(
+            verify_all_signatures("abc123", &signatures, |k| (k == kid).then(|| resolved.clone())).unwrap(),
+            vec![kid]
+        );
+        assert!(matches!(
+            verify_all_signatures("abc123", &signatures, |_| None),
+            Err(SignError::UnknownKey)
+        ));
+    }
+}
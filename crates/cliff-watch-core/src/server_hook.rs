@@ -0,0 +1,370 @@
+//! Hooks del servidor (`pre-receive` / `update`)
+//!
+//! Los hooks locales son orientativos: `git commit --no-verify` los salta. En
+//! el repositorio (bare) que recibe los pushes, `cliff-watch server-hook
+//! pre-receive` verifica los commits nuevos de cada ref con el trust.toml de
+//! su padre y rechaza el push si alguno incumple la política de la ref.
+//!
+//! La política vive en la configuración de Git del servidor, nunca en el árbol
+//! empujado, para que un push no pueda relajarla:
+//!
+//! ```text
+//! [cliff-watch "refs/heads/main"]
+//!     policy = strict
+//! [cliff-watch "refs/heads/feature/*"]
+//!     policy = audit
+//!     merges = exempt
+//!     firstParent = true
+//! ```
+//!
+//! - `strict`: todo commit necesita un ticket válido por encima del umbral
+//! - `signed`: basta un ticket válido (se aceptan Audit Mode y umbral no alcanzado)
+//! - `audit`: informa pero nunca rechaza
+//! - `off`: no verifica
+//!
+//! Sin patrón que encaje, las ramas usan `cliff-watch.policy` y, si tampoco
+//! está, las reglas de rama de `.cliff-watch/policy.toml` (`strict` por
+//! defecto); el resto de refs (tags, notas) no se verifican. Las reglas de
+//! rutas de policy.toml ajustan después la exigencia de cada commit (ver `policy`).
+//!
+//! El hook `pre-push` local aplica las mismas políticas (de la configuración
+//! del clon) a las refs remotas que se actualizan, pero por defecto solo
+//! avisa (`audit`): el que bloquea de verdad es el servidor.
+
+use git2::{Oid, Repository};
+
+use crate::chain::ChainIssue;
+use crate::ci::{verify_commits, CommitOutcome};
+use crate::policy::{Enforcement, PolicyScope};
+
+/// Hooks que puede instalar `server-hook install`
+pub const SERVER_HOOKS: [&str; 2] = ["pre-receive", "update"];
+
+const CONFIG_SECTION: &str = "cliff-watch";
+const BRANCH_PREFIX: &str = "refs/heads/";
+/// Política de las ramas sin patrón en el servidor y en `pre-push`
+pub const SERVER_DEFAULT: Enforcement = Enforcement::Strict;
+pub const PRE_PUSH_DEFAULT: Enforcement = Enforcement::Audit;
+
+/// Política aplicable a una ref
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefPolicy {
+    /// Patrón de la configuración que la definió (None: valor por defecto)
+    pub pattern: Option<String>,
+    /// La exigencia sale de la configuración (patrón o `cliff-watch.policy`)
+    /// y no del valor por defecto
+    pub configured: bool,
+    pub enforcement: Enforcement,
+    pub exempt_merges: bool,
+    pub first_parent: bool,
+}
+
+impl RefPolicy {
+    /// Política de `refname` según la configuración del repositorio.
+    ///
+    /// Gana el patrón exacto; si no, el patrón con comodines más largo. Las
+    /// ramas sin patrón usan `cliff-watch.policy` o, en su defecto, `branch_default`.
+    pub fn for_ref(repo: &Repository, refname: &str, branch_default: Enforcement) -> Result<Self, String> {
+        let config = repo.config().map_err(|e| e.to_string())?;
+        let mut patterns: Vec<String> = Vec::new();
+        let mut entries = config.entries(None).map_err(|e| e.to_string())?;
+        while let Some(entry) = entries.next() {
+            let Some(name) = entry.map_err(|e| e.to_string())?.name().map(str::to_string) else {
+                continue;
+            };
+            let Some(pattern) = name.strip_prefix(CONFIG_SECTION).and_then(|n| n.strip_prefix('.')).and_then(|n| n.rsplit_once('.')).map(|(p, _)| p)
+            else {
+                continue;
+            };
+            if ref_matches(pattern, refname) && !patterns.iter().any(|p| p == pattern) {
+                patterns.push(pattern.to_string());
+            }
+        }
+        let best = patterns.into_iter().max_by_key(|p| (p == refname, p.len()));
+
+        let get = |key: &str| match &best {
+            Some(pattern) => config.get_string(&format!("{}.{}.{}", CONFIG_SECTION, pattern, key)).ok(),
+            None => None,
+        };
+        let (enforcement, configured) = match get("policy") {
+            Some(value) => (Enforcement::parse(&value)?, true),
+            None if refname.starts_with(BRANCH_PREFIX) => match config.get_string(&format!("{}.policy", CONFIG_SECTION)) {
+                Ok(value) => (Enforcement::parse(&value)?, true),
+                Err(_) => (branch_default, false),
+            },
+            None => (Enforcement::Off, false),
+        };
+        let exempt_merges = match get("merges").as_deref() {
+            None | Some("verify") => false,
+            Some("exempt") => true,
+            Some(other) => return Err(format!("Unknown merges setting '{}' (expected verify or exempt)", other)),
+        };
+        let first_parent = get("firstParent").map(|v| matches!(v.as_str(), "true" | "yes" | "on" | "1")).unwrap_or(false);
+        Ok(Self { pattern: best, configured, enforcement, exempt_merges, first_parent })
+    }
+}
+
+/// `*` encaja con cualquier secuencia (también `/`)
+fn ref_matches(pattern: &str, refname: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == refname,
+        Some((prefix, rest)) => {
+            let Some(tail) = refname.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=tail.len()).filter(|i| tail.is_char_boundary(*i)).any(|i| ref_matches(rest, &tail[i..]))
+        }
+    }
+}
+
+/// Actualización de una ref recibida por el hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefUpdate {
+    pub old: Oid,
+    pub new: Oid,
+    pub refname: String,
+}
+
+impl RefUpdate {
+    pub fn new(old: &str, new: &str, refname: &str) -> Result<Self, String> {
+        let oid = |value: &str| Oid::from_str(value).map_err(|e| format!("Invalid object id '{}': {}", value, e));
+        Ok(Self { old: oid(old)?, new: oid(new)?, refname: refname.to_string() })
+    }
+
+    pub fn is_delete(&self) -> bool {
+        self.new.is_zero()
+    }
+}
+
+/// Líneas `<ref local> <sha local> <ref remota> <sha remota>` que Git entrega a `pre-push`
+pub fn parse_pre_push(input: &str) -> Result<Vec<RefUpdate>, String> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [_, local, remote_ref, remote] => RefUpdate::new(remote, local, remote_ref),
+            _ => Err(format!("Invalid pre-push line '{}'", line)),
+        })
+        .collect()
+}
+
+/// Líneas `<old> <new> <ref>` que Git entrega a `pre-receive` por stdin
+pub fn parse_updates(input: &str) -> Result<Vec<RefUpdate>, String> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [old, new, refname] => RefUpdate::new(old, new, refname),
+            _ => Err(format!("Invalid pre-receive line '{}'", line)),
+        })
+        .collect()
+}
+
+/// Veredicto sobre una ref
+#[derive(Debug)]
+pub struct RefVerdict {
+    pub update: RefUpdate,
+    pub policy: RefPolicy,
+    pub outcomes: Vec<CommitOutcome>,
+    pub issues: Vec<ChainIssue>,
+}
+
+impl RefVerdict {
+    /// Commits que su política rechaza
+    pub fn violations(&self) -> Vec<&CommitOutcome> {
+        self.outcomes.iter().filter(|o| o.mode.rejects(o.status)).collect()
+    }
+
+    /// Anomalías de cadena que la política del commit en que aparecen rechaza
+    pub fn chain_violations(&self) -> Vec<&ChainIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.kind.is_fatal())
+            .filter(|i| {
+                let mode = self.outcomes.iter().find(|o| o.commit == i.commit.to_string()).map(|o| o.mode);
+                mode.unwrap_or(self.policy.enforcement).rejects_chain()
+            })
+            .collect()
+    }
+
+    pub fn accepted(&self) -> bool {
+        self.violations().is_empty() && self.chain_violations().is_empty()
+    }
+}
+
+/// Abre el repositorio desde el entorno del hook.
+///
+/// Durante `pre-receive` los objetos recibidos están en cuarentena
+/// (`GIT_OBJECT_DIRECTORY` / `GIT_ALTERNATE_OBJECT_DIRECTORIES`): solo se ven
+/// respetando esas variables.
+pub fn open_receiving_repository() -> Result<Repository, String> {
+    Repository::open_from_env().map_err(|e| format!("Failed to open repository: {}", e))
+}
+
+/// Commits que introduce `update` (del más antiguo al más reciente).
+///
+/// Si la ref es nueva (o su valor anterior no está en el repositorio), solo
+/// aporta lo que no alcanzan todavía las refs bajo `known` (`refs/heads/` en
+/// el servidor, `refs/remotes/<remoto>/` en `pre-push`).
+pub fn incoming_commits(repo: &Repository, update: &RefUpdate, first_parent: bool, known: &str) -> Result<Vec<Oid>, String> {
+    if update.is_delete() {
+        return Ok(Vec::new());
+    }
+    let mut walk = repo.revwalk().map_err(|e| e.to_string())?;
+    walk.push(update.new).map_err(|e| format!("{}: {}", update.refname, e))?;
+    if update.old.is_zero() || walk.hide(update.old).is_err() {
+        for reference in repo.references_glob(&format!("{}*", known)).map_err(|e| e.to_string())?.flatten() {
+            if let Ok(commit) = reference.peel_to_commit() {
+                let _ = walk.hide(commit.id());
+            }
+        }
+    }
+    if first_parent {
+        walk.simplify_first_parent().map_err(|e| e.to_string())?;
+    }
+    let mut oids: Vec<Oid> = walk.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+    oids.reverse();
+    Ok(oids)
+}
+
+/// Verifica en el servidor una actualización de ref con su política
+pub fn check_update(repo: &Repository, update: &RefUpdate) -> Result<RefVerdict, String> {
+    // Solo ramas: las tags no se verifican (Off por defecto) y no pueden blanquear commits
+    check(repo, update, SERVER_DEFAULT, "refs/heads/")
+}
+
+/// Verifica en `pre-push` los commits que se publican en `remote`
+pub fn check_push(repo: &Repository, remote: &str, update: &RefUpdate) -> Result<RefVerdict, String> {
+    check(repo, update, PRE_PUSH_DEFAULT, &format!("refs/remotes/{}/", remote))
+}
+
+fn check(repo: &Repository, update: &RefUpdate, branch_default: Enforcement, known: &str) -> Result<RefVerdict, String> {
+    let policy = RefPolicy::for_ref(repo, &update.refname, branch_default)?;
+    let (outcomes, issues) = if policy.enforcement == Enforcement::Off {
+        (Vec::new(), Vec::new())
+    } else {
+        let oids = incoming_commits(repo, update, policy.first_parent, known)?;
+        let scope = PolicyScope {
+            branch: Some(update.refname.clone()),
+            configured: policy.configured.then_some(policy.enforcement),
+            default: policy.enforcement,
+        };
+        verify_commits(repo, &oids, policy.exempt_merges, &scope)
+    };
+    Ok(RefVerdict { update: update.clone(), policy, outcomes, issues })
+}
+
+/// Instala `hook` (`pre-receive` o `update`) en el repositorio receptor.
+///
+/// Si el servidor no encuentra el binario, el hook rechaza el push.
+pub fn install_server_hook(repo: &Repository, hook: &str, exe: &std::path::Path) -> Result<std::path::PathBuf, String> {
+    if !SERVER_HOOKS.contains(&hook) {
+        return Err(format!("Unknown server hook '{}' (expected pre-receive or update)", hook));
+    }
+    let hooks_dir = repo.path().join("hooks");
+    std::fs::create_dir_all(&hooks_dir).map_err(|e| e.to_string())?;
+    let path = hooks_dir.join(hook);
+    let args = if hook == "update" { " \"$@\"" } else { "" };
+    let content = format!(
+        r#"#!/bin/sh
+# cliff-watch server hook: verifica los commits recibidos contra trust.toml y la política de cada ref
+CLI_CMD="{exe}"
+[ -x "$CLI_CMD" ] || CLI_CMD="cliff-watch"
+command -v "$CLI_CMD" >/dev/null 2>&1 || CLI_CMD="cliff-watch-cli"
+if ! command -v "$CLI_CMD" >/dev/null 2>&1; then
+    echo "cliff-watch: binary not found on the server, rejecting push" >&2
+    exit 1
+fi
+exec "$CLI_CMD" server-hook {hook}{args}
+"#,
+        exe = exe.display()
+    );
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::CommitStatus;
+
+    #[test]
+    fn applies_the_most_specific_ref_policy_to_incoming_commits() {
+        let dir = crate::testing::TempDir::new("cliff_watch_server_hook");
+        let repo = Repository::init_bare(&dir).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let tree = repo.find_tree(repo.treebuilder(None).unwrap().write().unwrap()).unwrap();
+        let base = repo.commit(Some("refs/heads/main"), &sig, &sig, "base", &tree, &[]).unwrap();
+        let base_commit = repo.find_commit(base).unwrap();
+        // Commit sin ticket (p. ej. hecho con --no-verify), aún sin ref
+        let unsigned = repo.commit(None, &sig, &sig, "feat: x", &tree, &[&base_commit]).unwrap();
+
+        let mut config = repo.config().unwrap();
+        config.set_str("cliff-watch.refs/heads/feature/*.policy", "audit").unwrap();
+        config.set_str("cliff-watch.refs/heads/feature/strict.policy", "signed").unwrap();
+        config.set_str("cliff-watch.refs/heads/feature/*.merges", "exempt").unwrap();
+
+        let zero = Oid::zero().to_string();
+        let input = format!("{} {} refs/heads/main\n{} {} refs/heads/feature/x\n", base, unsigned, zero, unsigned);
+        let updates = parse_updates(&input).unwrap();
+        assert!(parse_updates("abc refs/heads/main").is_err());
+
+        let main = check_update(&repo, &updates[0]).unwrap();
+        assert_eq!(main.policy.enforcement, Enforcement::Strict);
+        assert_eq!(main.outcomes.len(), 1);
+        assert_eq!(main.violations()[0].status, CommitStatus::Unsigned);
+        assert!(!main.accepted());
+
+        let feature = check_update(&repo, &updates[1]).unwrap();
+        assert_eq!(feature.policy.pattern.as_deref(), Some("refs/heads/feature/*"));
+        assert!(feature.policy.exempt_merges);
+        // Ref nueva: `base` ya está en main
+        assert_eq!(feature.outcomes.len(), 1);
+        assert!(feature.accepted());
+
+        // Una tag ya publicada no cuenta como verificada para una rama nueva
+        repo.reference("refs/tags/sneak", unsigned, false, "tag").unwrap();
+        let laundered = RefUpdate::new(&zero, &unsigned.to_string(), "refs/heads/release").unwrap();
+        let release = check_update(&repo, &laundered).unwrap();
+        assert_eq!(release.outcomes.len(), 1);
+        assert!(!release.accepted());
+
+        let policy = |r: &str| RefPolicy::for_ref(&repo, r, SERVER_DEFAULT).unwrap().enforcement;
+        assert_eq!(policy("refs/heads/feature/strict"), Enforcement::Signed);
+        assert_eq!(policy("refs/tags/v1"), Enforcement::Off);
+        // pre-push: sin refs/remotes/origin/* todo es nuevo, y por defecto solo avisa
+        let push = parse_pre_push(&format!("refs/heads/dev {} refs/heads/dev {}\n", unsigned, zero)).unwrap();
+        let outgoing = check_push(&repo, "origin", &push[0]).unwrap();
+        assert_eq!(outgoing.policy.enforcement, PRE_PUSH_DEFAULT);
+        assert_eq!(outgoing.outcomes.len(), 2);
+        assert!(outgoing.accepted());
+        // Un push no puede relajar la rama: ni el policy.toml que él mismo introduce...
+        let mut builder = repo.treebuilder(None).unwrap();
+        let relaxed = repo.blob(b"[[rule]]\npaths = [\"**\"]\nmode = \"off\"\n").unwrap();
+        let mut subdir = repo.treebuilder(None).unwrap();
+        subdir.insert("policy.toml", relaxed, 0o100644).unwrap();
+        builder.insert(".cliff-watch", subdir.write().unwrap(), 0o040000).unwrap();
+        let relaxed_tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let introduces = repo.commit(None, &sig, &sig, "chore: relax", &relaxed_tree, &[&base_commit]).unwrap();
+        let verdict = check_update(&repo, &RefUpdate::new(&base.to_string(), &introduces.to_string(), "refs/heads/main").unwrap()).unwrap();
+        assert_eq!((verdict.outcomes[0].status, verdict.outcomes[0].mode), (CommitStatus::Unsigned, Enforcement::Strict));
+        assert!(!verdict.accepted());
+        // ... ni uno ya publicado por debajo de la configuración del servidor
+        let after = repo.commit(None, &sig, &sig, "feat: y", &relaxed_tree, &[&repo.find_commit(introduces).unwrap()]).unwrap();
+        config.set_str("cliff-watch.refs/heads/main.policy", "strict").unwrap();
+        let verdict = check_update(&repo, &RefUpdate::new(&introduces.to_string(), &after.to_string(), "refs/heads/main").unwrap()).unwrap();
+        assert_eq!(verdict.outcomes[0].mode, Enforcement::Strict);
+        assert!(!verdict.accepted());
+
+        config.set_str("cliff-watch.policy", "signed").unwrap();
+        assert_eq!(policy("refs/heads/dev"), Enforcement::Signed);
+
+        let delete = RefUpdate::new(&base.to_string(), &zero, "refs/heads/main").unwrap();
+        assert!(check_update(&repo, &delete).unwrap().accepted());
+    }
+}
@@ -0,0 +1,299 @@
+//! Shim de firma para `gpg.program` / `gpg.ssh.program`
+//!
+//! Los hooks se saltan con `git commit --no-verify`; la firma del commit no.
+//! Con el shim configurado como programa de firma, Git le entrega el objeto
+//! commit completo y el shim solo llama al firmante real (gpg o ssh-keygen)
+//! tras obtener del daemon un ticket ligado a ese árbol, padre y autor.
+//!
+//! El ticket viaja dentro de la propia firma, en el formato `Clave: valor` de
+//! los trailers:
+//!
+//! - OpenPGP: cabeceras `Comment:` del bloque ASCII-armor
+//! - SSH: líneas tras `-----END SSH SIGNATURE-----`, que ssh-keygen ignora
+//!
+//! Si el mensaje (o los archivos pendientes del hook) ya traen un ticket para
+//! ese mismo commit, el shim firma sin pedir otro.
+
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+
+use crate::ticket::{SignedTicket, TicketBinding};
+
+/// Nombre del ejecutable (enlace al CLI) que Git invoca como programa de firma
+pub const SHIM_NAME: &str = "cliff-watch-sign-shim";
+
+/// Claves de configuración de Git que redirige el shim y dónde guarda las originales
+const PROGRAM_KEYS: [(&str, &str); 2] = [
+    ("gpg.program", "cliff-watch.gpgProgram"),
+    ("gpg.ssh.program", "cliff-watch.sshProgram"),
+];
+
+const PGP_BEGIN: &str = "-----BEGIN PGP SIGNATURE-----";
+const SSH_BEGIN: &str = "-----BEGIN SSH SIGNATURE-----";
+const SSH_END: &str = "-----END SSH SIGNATURE-----";
+
+/// Formato de firma según los argumentos con los que Git invoca el programa
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    OpenPgp,
+    Ssh,
+}
+
+impl SignatureFormat {
+    /// `gpg.ssh.program` se invoca como `ssh-keygen -Y <acción> …`
+    pub fn from_args(args: &[String]) -> Self {
+        if args.iter().any(|a| a == "-Y") {
+            Self::Ssh
+        } else {
+            Self::OpenPgp
+        }
+    }
+
+    /// ¿Es una petición de firma (y no de verificación)?
+    ///
+    /// Git firma con `gpg --status-fd=2 -bsau <clave>` y
+    /// `ssh-keygen -Y sign -n git -f <clave> <archivo>`.
+    pub fn is_sign_request(&self, args: &[String]) -> bool {
+        match self {
+            Self::OpenPgp => args.iter().any(|a| {
+                a == "--sign" || a == "--detach-sign" || (a.starts_with('-') && !a.starts_with("--") && a.contains('s'))
+            }),
+            Self::Ssh => args.windows(2).any(|w| w[0] == "-Y" && w[1] == "sign"),
+        }
+    }
+
+    /// Configuración de Git que apunta al shim
+    fn config_key(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::OpenPgp => PROGRAM_KEYS[0],
+            Self::Ssh => PROGRAM_KEYS[1],
+        }
+    }
+
+    fn default_program(&self) -> &'static str {
+        match self {
+            Self::OpenPgp => "gpg",
+            Self::Ssh => "ssh-keygen",
+        }
+    }
+}
+
+/// Firmante real al que el shim reenvía (el que estaba configurado al instalarlo)
+pub fn real_program(repo: Option<&Repository>, format: SignatureFormat) -> String {
+    let (_, saved_key) = format.config_key();
+    repo.and_then(|r| r.config().ok())
+        .and_then(|c| c.get_string(saved_key).ok())
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| format.default_program().to_string())
+}
+
+/// Ruta del enlace al shim dentro de `.git/cliff-watch/`
+pub fn shim_path(repo: &Repository) -> PathBuf {
+    repo.path().join("cliff-watch").join(SHIM_NAME)
+}
+
+/// Enlaza el shim a `exe` y lo configura como `gpg.program` y `gpg.ssh.program`
+/// del repositorio, guardando los programas que hubiera para reenviarles la firma.
+pub fn install_sign_shim(repo: &Repository, exe: &Path) -> Result<PathBuf, String> {
+    let shim = shim_path(repo);
+    if let Some(dir) = shim.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let _ = std::fs::remove_file(&shim);
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(exe, &shim).map_err(|e| format!("Failed to link {}: {}", shim.display(), e))?;
+    #[cfg(not(unix))]
+    std::fs::copy(exe, &shim).map_err(|e| format!("Failed to copy {}: {}", shim.display(), e))?;
+
+    let shim_str = shim.to_string_lossy().to_string();
+    let mut config = repo.config().map_err(|e| e.to_string())?;
+    for (key, saved_key) in PROGRAM_KEYS {
+        match config.get_string(key) {
+            Ok(current) if current == shim_str => {}
+            Ok(current) => config.set_str(saved_key, &current).map_err(|e| e.to_string())?,
+            Err(_) => {
+                let _ = config.remove(saved_key);
+            }
+        }
+        config.set_str(key, &shim_str).map_err(|e| format!("Failed to set {}: {}", key, e))?;
+    }
+    Ok(shim)
+}
+
+/// Restaura los programas de firma originales y elimina el enlace
+pub fn remove_sign_shim(repo: &Repository) -> Result<(), String> {
+    let mut config = repo.config().map_err(|e| e.to_string())?;
+    for (key, saved_key) in PROGRAM_KEYS {
+        match config.get_string(saved_key) {
+            Ok(original) => {
+                config.set_str(key, &original).map_err(|e| e.to_string())?;
+                let _ = config.remove(saved_key);
+            }
+            Err(_) => {
+                let _ = config.remove(key);
+            }
+        }
+    }
+    let _ = std::fs::remove_file(shim_path(repo));
+    Ok(())
+}
+
+/// Objeto commit que Git pide firmar
+#[derive(Debug, Clone)]
+pub struct CommitBuffer {
+    pub binding: TicketBinding,
+    pub message: String,
+}
+
+impl CommitBuffer {
+    /// None si el objeto no es un commit (tags, certificados de push)
+    pub fn parse(buffer: &str) -> Option<Self> {
+        let (headers, message) = buffer.split_once("\n\n").unwrap_or((buffer, ""));
+        let mut tree = None;
+        let mut parent = None;
+        let mut author = None;
+        for line in headers.lines() {
+            match line.split_once(' ') {
+                Some(("tree", v)) => tree = Some(v.to_string()),
+                Some(("parent", v)) if parent.is_none() => parent = Some(v.to_string()),
+                Some(("author", v)) => {
+                    author = v.split_once('<').and_then(|(_, rest)| rest.split_once('>')).map(|(email, _)| email.to_string())
+                }
+                _ => {}
+            }
+        }
+        Some(Self {
+            binding: TicketBinding { tree: tree?, parent, author: author? },
+            message: message.to_string(),
+        })
+    }
+
+    /// ¿Lleva ya un ticket para este commit (en el mensaje o pendiente del hook)?
+    ///
+    /// El ticket debe estar firmado por una clave de trust.toml tal como está
+    /// en el padre (o en el árbol de trabajo si es el primer commit).
+    pub fn has_ticket(&self, repo: &Repository) -> bool {
+        let pending = std::fs::read_to_string(repo.path().join("cliff-watch").join("latest_ticket")).unwrap_or_default();
+        let parent = self.binding.parent.as_deref().and_then(|p| git2::Oid::from_str(p).ok()).and_then(|p| repo.find_commit(p).ok());
+        let trust = match &parent {
+            Some(parent) => crate::trust::load_trust_at(repo, parent),
+            None => crate::trust::load_trust(repo),
+        };
+        trust.map(|trust| self.carries_ticket(&pending, &trust)).unwrap_or(false)
+    }
+
+    fn carries_ticket(&self, pending: &str, trust: &crate::trust::TrustConfig) -> bool {
+        crate::ticket::extract_ticket_values(&self.message)
+            .into_iter()
+            .chain(std::iter::once(pending.trim()).filter(|p| !p.is_empty()))
+            .filter_map(|value| SignedTicket::parse_trailer_value(value).ok())
+            .any(|ticket| ticket.payload.binding == self.binding && ticket.verify_issuer(trust).is_ok())
+    }
+}
+
+/// Inserta líneas `Clave: valor` en una firma OpenPGP o SSH
+pub fn embed_attestation(signature: &str, lines: &[(&str, String)]) -> Result<String, String> {
+    if lines.is_empty() {
+        return Ok(signature.to_string());
+    }
+    if let Some(rest) = signature.strip_prefix(PGP_BEGIN) {
+        let headers: String = lines.iter().map(|(k, v)| format!("\nComment: {}: {}", k, v.trim())).collect();
+        return Ok(format!("{}{}{}", PGP_BEGIN, headers, rest));
+    }
+    if signature.starts_with(SSH_BEGIN) && signature.contains(SSH_END) {
+        let mut out = signature.to_string();
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        for (k, v) in lines {
+            out.push_str(&format!("{}: {}\n", k, v.trim()));
+        }
+        return Ok(out);
+    }
+    Err("Unsupported signature format (expected OpenPGP or SSH)".to_string())
+}
+
+/// Líneas `Clave: valor` incrustadas en la firma de un commit
+pub fn extract_attestation(signature: &str) -> String {
+    signature
+        .lines()
+        .map(|line| line.strip_prefix("Comment: ").unwrap_or(line))
+        .filter(|line| line.contains(": "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Atestación incrustada en la firma (`gpgsig`) de un commit, si la hay
+pub fn read_signature_attestation(repo: &Repository, oid: git2::Oid) -> Option<String> {
+    let (signature, _) = repo.extract_signature(&oid, None).ok()?;
+    let text = extract_attestation(std::str::from_utf8(&signature).ok()?);
+    (!crate::ticket::extract_ticket_values(&text).is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUFFER: &str = "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+parent 1111111111111111111111111111111111111111\n\
+author Dev <dev@example.com> 1700000000 +0000\n\
+committer Dev <dev@example.com> 1700000000 +0000\n\
+\n\
+feat: x\n";
+
+    #[test]
+    fn recognises_git_signing_invocations() {
+        let args = |s: &str| s.split(' ').map(str::to_string).collect::<Vec<_>>();
+        let gpg = args("--status-fd=2 -bsau ABCD");
+        assert_eq!(SignatureFormat::from_args(&gpg), SignatureFormat::OpenPgp);
+        assert!(SignatureFormat::OpenPgp.is_sign_request(&gpg));
+        assert!(!SignatureFormat::OpenPgp.is_sign_request(&args("--status-fd=1 --keyid-format=long --verify /tmp/sig -")));
+
+        let ssh = args("-Y sign -n git -f /home/dev/.ssh/id_ed25519 /tmp/buffer");
+        assert_eq!(SignatureFormat::from_args(&ssh), SignatureFormat::Ssh);
+        assert!(SignatureFormat::Ssh.is_sign_request(&ssh));
+        assert!(!SignatureFormat::Ssh.is_sign_request(&args("-Y verify -n git -f allowed -I dev -s /tmp/sig")));
+
+        let commit = CommitBuffer::parse(BUFFER).unwrap();
+        assert_eq!(commit.binding.parent.as_deref(), Some("1111111111111111111111111111111111111111"));
+        assert_eq!(commit.binding.author, "dev@example.com");
+        assert!(CommitBuffer::parse("object 1111\ntype commit\ntag v1\n\nrelease\n").is_none());
+    }
+
+    #[test]
+    fn only_trusted_tickets_skip_the_daemon() {
+        use crate::crypto::{generate_keypair, key_id, DaemonIdentity};
+        use crate::ticket::{TicketMode, TicketPayload};
+
+        let commit = CommitBuffer::parse(BUFFER).unwrap();
+        let (signing_key, verifying_key) = generate_keypair();
+        let identity = DaemonIdentity::Software(signing_key);
+        let payload = TicketPayload::new(TicketMode::Valid, 5.0, commit.binding.clone(), key_id(&verifying_key));
+        let pending = SignedTicket::sign(payload, &identity).unwrap().to_trailer_value();
+
+        let mut trust = crate::trust::TrustConfig::default();
+        assert!(!commit.carries_ticket(&pending, &trust));
+        trust.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+        assert!(commit.carries_ticket(&pending, &trust));
+
+        let forged = pending.replace("score=5.00", "score=0.00");
+        assert!(!commit.carries_ticket(&forged, &trust));
+    }
+
+    #[test]
+    fn attestation_round_trips_through_both_signature_formats() {
+        let lines = [("cliff-watch-score", "v=1;mode=valid:sig=00".to_string()), ("Cliff-Watch-Proof", "ab".to_string())];
+        let pgp = "-----BEGIN PGP SIGNATURE-----\n\niHUEABYIAB0WIQQ=\n=abcd\n-----END PGP SIGNATURE-----\n";
+        let ssh = "-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n-----END SSH SIGNATURE-----\n";
+
+        for signature in [pgp, ssh] {
+            let embedded = embed_attestation(signature, &lines).unwrap();
+            assert!(embedded.starts_with("-----BEGIN"));
+            let text = extract_attestation(&embedded);
+            assert_eq!(crate::ticket::extract_ticket_values(&text), vec!["v=1;mode=valid:sig=00"]);
+            assert!(text.contains("Cliff-Watch-Proof: ab"));
+        }
+        assert!(embed_attestation("-----BEGIN SIGNED MESSAGE-----\n", &lines).is_err());
+    }
+}
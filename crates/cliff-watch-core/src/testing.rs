@@ -0,0 +1,59 @@
+//! Utilidades compartidas por los tests
+//!
+//! Solo se compila en los tests de este crate o, desde los demás, con la
+//! feature `test-util` en `[dev-dependencies]`.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Directorio temporal único que se borra (con todo su contenido) al salir
+/// de ámbito, también cuando el test falla a mitad
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Crea `<temporal del sistema>/<prefix>_<aleatorio>`, vacío
+    pub fn new(prefix: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("{}_{}", prefix, hex::encode(rand::random::<[u8; 8]>())));
+        std::fs::create_dir_all(&path).unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e));
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_the_directory_on_drop() {
+        let dir = TempDir::new("cliff_watch_testing");
+        std::fs::write(dir.join("f"), "x").unwrap();
+        let path = dir.path().to_path_buf();
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
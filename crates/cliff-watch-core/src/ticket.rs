@@ -0,0 +1,992 @@
+//! Tickets de Atención v1
+//!
+//! Un ticket es la prueba firmada por el daemon de que se pagó el costo
+//! entrópico de un commit concreto. El payload liga el pago al árbol staged,
+//! al commit padre y al autor, de modo que no pueda reutilizarse en otro commit.
+//!
+//! ## Formato del trailer
+//! ```text
+//! cliff-watch-score: v=1;mode=valid;score=12.50;tree=<oid>;parent=<oid|none>;author=<email>;ts=<unix>;nonce=<hex>;kid=<hex>:sig=<hex>
+//! ```
+//! La firma (Ed25519, o ECDSA-P256 `r || s` si la identidad reside en el TPM)
+//! cubre exactamente los bytes del payload (todo lo anterior a `:sig=`).
+//!
+//! Si el daemon pudo probar que el Human Score supera el umbral, el payload
+//! añade `;proof=<sha256>` con el digest del trailer `Cliff-Watch-Proof`
+//! (prueba de rango Bulletproofs ligada al mismo árbol).
+//!
+//! Un ticket pagado con prueba de trabajo (`mode=pow`, ver `crate::pow`) añade
+//! `;pow_bits=<n>;pow_salt=<hex>;pow_nonce=<n>`, que cualquiera puede
+//! recomprobar a partir del binding.
+//!
+//! El daemon sella el ticket con `;boot=<id>;mono=<ms>` (arranque y reloj
+//! monótono, ver `crate::clock`) para detectar relojes de pared retrasados.
+//!
+//! Cada identidad numera y encadena sus tickets: `;seq=<n>;prev=<sha256|none>`
+//! con el hash del payload de su ticket anterior (ver `crate::chain`).
+//!
+//! Si el daemon mantiene el log de transparencia (ver `crate::translog`), el
+//! payload termina con `;log=<n>`: el índice de la hoja que ocupa el ticket.
+//!
+//! ## Co-firmas
+//! Otros daemons (el navegante en pair programming, o un maintainer que
+//! avala el commit) pueden firmar el mismo payload con separación de dominio:
+//! ```text
+//! Cliff-Watch-Cosign: kid=<hex>:sig=<hex>
+//! ```
+//! Cada firma cuenta con el rol de su clave en trust.toml para la política
+//! `threshold` (ver `trust::ThresholdPolicy`).
+//!
+//! ## Autoría
+//! La clave del emisor debe poder firmar por el autor del commit (`emails` en
+//! trust.toml); si no, el ticket falla con `signer_mismatch`. Los co-autores
+//! declarados con `Co-authored-by:` no los cubre el ticket: cada uno cuenta
+//! como atestiguado solo si lo co-firma una clave que pueda firmar por su email.
+
+use crate::clock::ClockStamp;
+use crate::crypto::{DaemonIdentity, PublicKey};
+use crate::crypto::zkp::HumanityProof;
+use serde::{Deserialize, Serialize};
+
+/// Versión actual del payload. Incrementar solo ante cambios incompatibles.
+pub const TICKET_VERSION: u32 = 1;
+
+/// Clave del trailer donde viaja el ticket firmado
+pub const TICKET_TRAILER: &str = "cliff-watch-score";
+
+/// Clave del trailer con la prueba de rango serializada
+pub const PROOF_TRAILER: &str = "Cliff-Watch-Proof";
+
+/// Clave del trailer (o línea de nota) con una co-firma del ticket
+pub const COSIGN_TRAILER: &str = "Cliff-Watch-Cosign";
+
+/// Trailer estándar de co-autoría (GitHub, GitLab)
+pub const CO_AUTHOR_TRAILER: &str = "Co-authored-by";
+
+/// Separador entre payload y firma dentro del valor del trailer
+const SIG_SEPARATOR: &str = ":sig=";
+
+/// Separador de dominio de las co-firmas: una co-firma nunca vale como ticket
+const COSIGN_DOMAIN: &[u8] = b"cliff-watch-cosign/v1\0";
+
+/// Campos que puede llevar un payload; cualquier otro lo invalida
+const PAYLOAD_FIELDS: &[&str] = &[
+    "v", "mode", "score", "tree", "parent", "author", "ts", "nonce", "kid", "boot", "mono", "proof", "pow_bits", "pow_salt",
+    "pow_nonce", "seq", "prev", "log",
+];
+
+/// Modo en que el daemon emitió el ticket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TicketMode {
+    /// La batería cubrió el costo entrópico
+    Valid,
+    /// La batería no alcanzaba, pero el daemon está en Audit Mode
+    Audit,
+    /// La batería no alcanzaba y el déficit se pagó con prueba de trabajo
+    Pow,
+}
+
+impl TicketMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketMode::Valid => "valid",
+            TicketMode::Audit => "audit",
+            TicketMode::Pow => "pow",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "valid" => Ok(TicketMode::Valid),
+            "audit" => Ok(TicketMode::Audit),
+            "pow" => Ok(TicketMode::Pow),
+            other => Err(format!("Unknown ticket mode '{}'", other)),
+        }
+    }
+}
+
+/// Contenido que el CLI conoce del commit en curso y que el ticket debe atar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketBinding {
+    /// OID del árbol que resultará del índice staged
+    pub tree: String,
+    /// OID del commit padre (None en el primer commit)
+    pub parent: Option<String>,
+    /// Email del autor del commit
+    pub author: String,
+}
+
+impl TicketBinding {
+    /// Comprueba que el binding que envía el cliente no puede colar campos en
+    /// el payload: árbol y padre son OIDs hex y el autor no lleva separadores
+    pub fn validate(&self) -> Result<(), String> {
+        let is_oid = |v: &str| matches!(v.len(), 40 | 64) && v.chars().all(|c| c.is_ascii_hexdigit());
+        if !is_oid(&self.tree) {
+            return Err(format!("Invalid tree id '{}'", self.tree));
+        }
+        if let Some(parent) = self.parent.as_deref().filter(|p| !is_oid(p)) {
+            return Err(format!("Invalid parent id '{}'", parent));
+        }
+        if self.author.is_empty() || self.author.chars().any(|c| matches!(c, ';' | '=' | ':') || c.is_control()) {
+            return Err(format!("Invalid author '{}'", self.author));
+        }
+        Ok(())
+    }
+}
+
+/// Prueba de trabajo con la que se pagó un ticket `mode=pow`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowStamp {
+    /// Bits a cero iniciales exigidos por el reto
+    pub difficulty: u8,
+    /// Sal (hex) del reto; la semilla se recomputa desde el binding
+    pub salt: String,
+    pub nonce: u64,
+}
+
+/// Eslabón del ticket en la cadena de su identidad
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainLink {
+    /// Contador monótono de tickets emitidos por la clave (empieza en 1)
+    pub seq: u64,
+    /// `chain::ticket_hash` del ticket anterior (None en el primero)
+    pub prev: Option<String>,
+}
+
+/// Payload versionado que firma el daemon
+#[derive(Debug, Clone, PartialEq)]
+pub struct TicketPayload {
+    pub version: u32,
+    pub mode: TicketMode,
+    /// Costo entrópico pagado
+    pub score: f64,
+    pub binding: TicketBinding,
+    /// Hora de emisión (UNIX epoch, segundos, reloj de pared)
+    pub issued_at: i64,
+    /// Nonce aleatorio (hex) para que dos tickets nunca coincidan
+    pub nonce: String,
+    /// Identificador de la clave del daemon (ver `crypto::key_id`)
+    pub key_id: String,
+    /// Arranque y reloj monótono del daemon al emitir
+    pub clock: Option<ClockStamp>,
+    /// SHA256 (hex) del trailer `Cliff-Watch-Proof`, si hubo prueba de humanidad
+    pub proof_digest: Option<String>,
+    /// Prueba de trabajo que pagó el déficit (solo en `mode=pow`)
+    pub pow: Option<PowStamp>,
+    /// Posición en la cadena de tickets de la identidad
+    pub chain: Option<ChainLink>,
+    /// Índice de la hoja del ticket en el log de transparencia del daemon
+    pub log_index: Option<u64>,
+}
+
+impl TicketPayload {
+    /// Crea un payload nuevo con hora actual y nonce aleatorio
+    pub fn new(mode: TicketMode, score: f64, binding: TicketBinding, key_id: impl Into<String>) -> Self {
+        let nonce: [u8; 16] = rand::random();
+        Self {
+            version: TICKET_VERSION,
+            mode,
+            score,
+            binding,
+            issued_at: chrono::Utc::now().timestamp(),
+            nonce: hex::encode(nonce),
+            key_id: key_id.into(),
+            clock: None,
+            proof_digest: None,
+            pow: None,
+            chain: None,
+            log_index: None,
+        }
+    }
+
+    /// Sella el ticket con el arranque y el reloj monótono actuales
+    pub fn with_clock(mut self, stamp: ClockStamp) -> Self {
+        self.clock = Some(stamp);
+        self
+    }
+
+    /// Ata una prueba de rango serializada al payload
+    pub fn with_proof(mut self, proof_trailer_value: &str) -> Self {
+        self.proof_digest = Some(proof_digest(proof_trailer_value));
+        self
+    }
+
+    /// Marca el ticket como pagado con una prueba de trabajo
+    pub fn with_pow(mut self, stamp: PowStamp) -> Self {
+        self.mode = TicketMode::Pow;
+        self.pow = Some(stamp);
+        self
+    }
+
+    /// Encadena el ticket tras el anterior de la misma identidad
+    pub fn with_chain(mut self, link: ChainLink) -> Self {
+        self.chain = Some(link);
+        self
+    }
+
+    /// Reserva la hoja `index` del log de transparencia para este ticket
+    pub fn with_log_index(mut self, index: u64) -> Self {
+        self.log_index = Some(index);
+        self
+    }
+
+    /// Serialización canónica (es lo que se firma)
+    pub fn encode(&self) -> String {
+        let mut encoded = format!(
+            "v={};mode={};score={:.2};tree={};parent={};author={};ts={};nonce={};kid={}",
+            self.version,
+            self.mode.as_str(),
+            self.score,
+            self.binding.tree,
+            self.binding.parent.as_deref().unwrap_or("none"),
+            self.binding.author,
+            self.issued_at,
+            self.nonce,
+            self.key_id,
+        );
+        if let Some(stamp) = &self.clock {
+            encoded.push_str(&format!(";boot={};mono={}", stamp.boot_id, stamp.mono_ms));
+        }
+        if let Some(digest) = &self.proof_digest {
+            encoded.push_str(&format!(";proof={}", digest));
+        }
+        if let Some(pow) = &self.pow {
+            encoded.push_str(&format!(";pow_bits={};pow_salt={};pow_nonce={}", pow.difficulty, pow.salt, pow.nonce));
+        }
+        if let Some(link) = &self.chain {
+            encoded.push_str(&format!(";seq={};prev={}", link.seq, link.prev.as_deref().unwrap_or("none")));
+        }
+        if let Some(index) = self.log_index {
+            encoded.push_str(&format!(";log={}", index));
+        }
+        encoded
+    }
+
+    /// Parsea un payload canónico. Un campo repetido o desconocido lo invalida:
+    /// nunca se deja que un valor posterior pise al firmado antes
+    pub fn decode(encoded: &str) -> Result<Self, String> {
+        let mut fields = std::collections::HashMap::new();
+        for part in encoded.split(';') {
+            let (k, v) = part.split_once('=').ok_or_else(|| format!("Malformed ticket field '{}'", part))?;
+            if !PAYLOAD_FIELDS.contains(&k) {
+                return Err(format!("Unknown ticket field '{}'", k));
+            }
+            if fields.insert(k, v).is_some() {
+                return Err(format!("Duplicate ticket field '{}'", k));
+            }
+        }
+        let get = |k: &str| fields.get(k).copied().ok_or_else(|| format!("Ticket field '{}' missing", k));
+
+        let version: u32 = get("v")?.parse().map_err(|_| "Invalid ticket version".to_string())?;
+        if version != TICKET_VERSION {
+            return Err(format!("Unsupported ticket version {}", version));
+        }
+
+        let parent = match get("parent")? {
+            "none" => None,
+            p => Some(p.to_string()),
+        };
+
+        let pow = match fields.get("pow_bits") {
+            Some(bits) => Some(PowStamp {
+                difficulty: bits.parse().map_err(|_| "Invalid proof-of-work difficulty".to_string())?,
+                salt: get("pow_salt")?.to_string(),
+                nonce: get("pow_nonce")?.parse().map_err(|_| "Invalid proof-of-work nonce".to_string())?,
+            }),
+            None => None,
+        };
+
+        Ok(Self {
+            version,
+            mode: TicketMode::parse(get("mode")?)?,
+            score: get("score")?.parse().map_err(|_| "Invalid ticket score".to_string())?,
+            binding: TicketBinding {
+                tree: get("tree")?.to_string(),
+                parent,
+                author: get("author")?.to_string(),
+            },
+            issued_at: get("ts")?.parse().map_err(|_| "Invalid ticket timestamp".to_string())?,
+            nonce: get("nonce")?.to_string(),
+            key_id: get("kid")?.to_string(),
+            clock: match fields.get("boot") {
+                Some(boot_id) => Some(ClockStamp {
+                    boot_id: boot_id.to_string(),
+                    mono_ms: get("mono")?.parse().map_err(|_| "Invalid monotonic timestamp".to_string())?,
+                }),
+                None => None,
+            },
+            proof_digest: fields.get("proof").map(|d| d.to_string()),
+            pow,
+            chain: match fields.get("seq") {
+                Some(seq) => Some(ChainLink {
+                    seq: seq.parse().map_err(|_| "Invalid chain sequence".to_string())?,
+                    prev: match get("prev")? {
+                        "none" => None,
+                        p => Some(p.to_string()),
+                    },
+                }),
+                None => None,
+            },
+            log_index: match fields.get("log") {
+                Some(index) => Some(index.parse().map_err(|_| "Invalid log index".to_string())?),
+                None => None,
+            },
+        })
+    }
+}
+
+/// Ticket firmado tal como aparece en el trailer
+#[derive(Debug, Clone)]
+pub struct SignedTicket {
+    pub payload: TicketPayload,
+    /// Bytes exactos que cubre la firma
+    pub encoded: String,
+    pub signature: Vec<u8>,
+}
+
+impl SignedTicket {
+    /// Firma un payload con la identidad del daemon
+    pub fn sign(payload: TicketPayload, identity: &DaemonIdentity) -> Result<Self, String> {
+        let encoded = payload.encode();
+        let signature = identity.sign(encoded.as_bytes())?;
+        Ok(Self { payload, encoded, signature })
+    }
+
+    /// Reconstruye un ticket a partir del payload y la firma devueltos por el daemon
+    pub fn from_parts(encoded: &str, signature: Vec<u8>) -> Result<Self, String> {
+        Ok(Self {
+            payload: TicketPayload::decode(encoded)?,
+            encoded: encoded.to_string(),
+            signature,
+        })
+    }
+
+    /// Valor del trailer: `<payload>:sig=<hex>`
+    pub fn to_trailer_value(&self) -> String {
+        format!("{}{}{}", self.encoded, SIG_SEPARATOR, hex::encode(&self.signature))
+    }
+
+    /// Parsea el valor de un trailer `cliff-watch-score`
+    pub fn parse_trailer_value(value: &str) -> Result<Self, String> {
+        let (encoded, sig_hex) = value
+            .trim()
+            .rsplit_once(SIG_SEPARATOR)
+            .ok_or("Ticket without signature")?;
+        let signature = hex::decode(sig_hex).map_err(|_| "Invalid ticket signature encoding".to_string())?;
+        Self::from_parts(encoded, signature)
+    }
+
+    /// Verifica la firma del payload con una clave pública
+    pub fn verify_signature(&self, public_key: &PublicKey) -> bool {
+        public_key.verify(self.encoded.as_bytes(), &self.signature)
+    }
+
+    /// Clave de trust.toml que emitió el ticket, activa cuando se emitió
+    pub fn verify_issuer<'a>(&self, trust: &'a crate::trust::TrustConfig) -> Result<&'a crate::trust::TrustedKey, String> {
+        resolve_signer(trust, &self.payload.key_id, self.payload.issued_at, |pk| self.verify_signature(pk))
+    }
+
+    /// Comprueba que el ticket corresponde al commit: árbol, padre y autor.
+    ///
+    /// Devuelve un código de razón (`tree_mismatch`, `parent_mismatch`, `author_mismatch`).
+    pub fn check_binding(&self, commit: &git2::Commit) -> Result<(), String> {
+        let binding = &self.payload.binding;
+
+        if binding.tree != commit.tree_id().to_string() {
+            return Err("tree_mismatch".to_string());
+        }
+
+        let parent = commit.parent_id(0).ok().map(|p| p.to_string());
+        if binding.parent != parent {
+            return Err("parent_mismatch".to_string());
+        }
+
+        let author = commit.author();
+        if !binding.author.eq_ignore_ascii_case(author.email().unwrap_or("")) {
+            return Err("author_mismatch".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Recomprueba la prueba de trabajo de un ticket `mode=pow` contra su binding.
+    ///
+    /// Los tickets de otros modos no llevan prueba y pasan sin más.
+    pub fn check_pow(&self) -> Result<(), String> {
+        match (&self.payload.mode, &self.payload.pow) {
+            (TicketMode::Pow, Some(pow)) => {
+                crate::pow::verify_solution(&self.payload.binding, &pow.salt, pow.difficulty, pow.nonce)
+                    .map(|_| ())
+                    .map_err(|_| "invalid_pow".to_string())
+            }
+            (TicketMode::Pow, None) => Err("invalid_pow".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Firma de un daemon adicional sobre el payload de un ticket
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cosignature {
+    pub key_id: String,
+    pub signature: Vec<u8>,
+}
+
+impl Cosignature {
+    /// Bytes que cubre la co-firma: dominio + payload del ticket
+    fn message(encoded: &str) -> Vec<u8> {
+        [COSIGN_DOMAIN, encoded.as_bytes()].concat()
+    }
+
+    /// Co-firma el payload (ya codificado) de un ticket
+    pub fn sign(encoded: &str, identity: &DaemonIdentity) -> Result<Self, String> {
+        TicketPayload::decode(encoded)?;
+        Ok(Self {
+            key_id: identity.public_key().key_id(),
+            signature: identity.sign(&Self::message(encoded))?,
+        })
+    }
+
+    pub fn verify(&self, ticket: &SignedTicket, public_key: &PublicKey) -> bool {
+        public_key.verify(&Self::message(&ticket.encoded), &self.signature)
+    }
+
+    /// Valor del trailer: `kid=<hex>:sig=<hex>`
+    pub fn to_trailer_value(&self) -> String {
+        format!("kid={}{}{}", self.key_id, SIG_SEPARATOR, hex::encode(&self.signature))
+    }
+
+    pub fn parse_trailer_value(value: &str) -> Result<Self, String> {
+        let (kid, sig_hex) = value
+            .trim()
+            .strip_prefix("kid=")
+            .and_then(|v| v.split_once(SIG_SEPARATOR))
+            .ok_or("Malformed co-signature")?;
+        let signature = hex::decode(sig_hex).map_err(|_| "Invalid co-signature encoding".to_string())?;
+        Ok(Self { key_id: kid.to_string(), signature })
+    }
+}
+
+/// Firmante válido de un ticket (emisor o co-firmante)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TicketSigner {
+    pub alias: String,
+    pub role: String,
+    pub key_id: String,
+    /// Emails de autor por los que puede firmar la clave (vacío: cualquiera)
+    pub emails: Vec<String>,
+}
+
+impl TicketSigner {
+    fn new(key: &crate::trust::TrustedKey, key_id: String) -> Self {
+        Self { alias: key.alias.clone(), role: key.role.clone(), key_id, emails: key.emails.clone() }
+    }
+}
+
+/// Co-autor declarado con `Co-authored-by:` y quién lo atestigua
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoAuthor {
+    pub name: String,
+    pub email: String,
+    /// Alias del firmante (emisor o co-firmante) cuya clave cubre este email
+    pub attested_by: Option<String>,
+}
+
+/// Co-autores de un mensaje de commit (`Co-authored-by: Nombre <email>`, sin distinguir mayúsculas)
+pub fn co_authors(message: &str) -> Vec<(String, String)> {
+    message
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            if !key.trim().eq_ignore_ascii_case(CO_AUTHOR_TRAILER) {
+                return None;
+            }
+            let (name, rest) = value.split_once('<')?;
+            let (email, _) = rest.split_once('>')?;
+            Some((name.trim().to_string(), email.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Resuelve una firma contra trust.toml: la clave debe verificarla y estar activa en `at`.
+///
+/// Devuelve la clave o el código de razón (`unknown_key`, `bad_signature` o el del estado).
+fn resolve_signer<'a>(
+    trust: &'a crate::trust::TrustConfig,
+    key_id: &str,
+    at: i64,
+    verifies: impl Fn(&PublicKey) -> bool,
+) -> Result<&'a crate::trust::TrustedKey, String> {
+    let mut known_key = false;
+    for key in trust.keys.iter().filter(|k| k.key_id() == key_id) {
+        let public_key = match PublicKey::from_hex(&key.public_key) {
+            Ok(k) => k,
+            Err(_) => continue,
+        };
+        known_key = true;
+        if verifies(&public_key) {
+            return match trust.status_at(key, at).reason_code() {
+                Some(code) => Err(code.to_string()),
+                None => Ok(key),
+            };
+        }
+    }
+    Err(if known_key { "bad_signature" } else { "unknown_key" }.to_string())
+}
+
+/// Co-firmas válidas de un ticket. Las que no verifican simplemente no cuentan.
+pub fn verify_cosignatures(
+    attestation: &str,
+    ticket: &SignedTicket,
+    trust: &crate::trust::TrustConfig,
+    at: i64,
+) -> Vec<TicketSigner> {
+    extract_trailer_values(attestation, COSIGN_TRAILER)
+        .into_iter()
+        .filter_map(|value| Cosignature::parse_trailer_value(value).ok())
+        .filter_map(|cosig| {
+            resolve_signer(trust, &cosig.key_id, at, |pk| cosig.verify(ticket, pk))
+                .ok()
+                .map(|key| TicketSigner::new(key, cosig.key_id))
+        })
+        .collect()
+}
+
+/// Extrae todos los valores de trailer `cliff-watch-score` de un mensaje
+pub fn extract_ticket_values(message: &str) -> Vec<&str> {
+    extract_trailer_values(message, TICKET_TRAILER)
+}
+
+pub(crate) fn extract_trailer_values<'a>(message: &'a str, key: &str) -> Vec<&'a str> {
+    let prefix = format!("{}:", key);
+    message
+        .lines()
+        .filter_map(|line| line.strip_prefix(prefix.as_str()))
+        .map(|v| v.trim())
+        .collect()
+}
+
+/// Digest (hex) con el que el ticket se compromete a un trailer `Cliff-Watch-Proof`
+pub fn proof_digest(proof_trailer_value: &str) -> String {
+    hex::encode(crate::crypto::calculate_sha256(proof_trailer_value.trim().as_bytes()))
+}
+
+/// Prueba de rango a la que se comprometió el ticket, sin verificarla.
+///
+/// - `Ok(None)`: el ticket no se comprometió a ninguna prueba.
+/// - `Err(reason)`: `missing_proof`, `proof_digest_mismatch` o `invalid_proof` (no parsea).
+pub fn committed_proof(attestation: &str, ticket: &SignedTicket) -> Result<Option<HumanityProof>, String> {
+    let expected = match &ticket.payload.proof_digest {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+
+    let value = extract_trailer_values(attestation, PROOF_TRAILER)
+        .into_iter()
+        .find(|v| proof_digest(v) == *expected);
+
+    let value = match value {
+        Some(v) => v,
+        None if extract_trailer_values(attestation, PROOF_TRAILER).is_empty() => return Err("missing_proof".to_string()),
+        None => return Err("proof_digest_mismatch".to_string()),
+    };
+
+    HumanityProof::parse_trailer_value(value).map(Some).map_err(|_| "invalid_proof".to_string())
+}
+
+/// Verifica la prueba de rango de un commit ya autenticado por su ticket.
+///
+/// `attestation` es el texto donde buscar el trailer (ver `notes::read_attestation`).
+///
+/// - `Ok(None)`: el ticket no se comprometió a ninguna prueba (score bajo el umbral o Audit Mode).
+/// - `Ok(Some(threshold))`: la prueba existe, coincide con el digest firmado y es válida para el árbol.
+/// - `Err(reason)`: `missing_proof`, `proof_digest_mismatch` o `invalid_proof`.
+pub fn verify_commit_proof(commit: &git2::Commit, attestation: &str, ticket: &SignedTicket) -> Result<Option<u64>, String> {
+    let proof = match committed_proof(attestation, ticket)? {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+    proof.verify(commit.tree_id().to_string().as_bytes()).map_err(|_| "invalid_proof".to_string())?;
+    Ok(Some(proof.threshold_percent))
+}
+
+/// Resultado de verificar el ticket de un commit
+#[derive(Debug, Clone)]
+pub struct TicketVerification {
+    /// Alias de la clave confiable que firmó el ticket
+    pub signer: String,
+    pub ticket: SignedTicket,
+    /// Emisor y co-firmantes válidos, un alias por entrada
+    pub signers: Vec<TicketSigner>,
+    /// Co-autores del mensaje distintos del autor
+    pub co_authors: Vec<CoAuthor>,
+}
+
+/// Verifica el ticket de un commit contra las claves confiables del repositorio.
+///
+/// Los tickets se buscan en `attestation`: el mensaje del commit y, si la hay,
+/// su nota en `refs/notes/cliff-watch` (ver `notes::read_attestation`).
+///
+/// La clave se juzga con su estado en el instante del commit (hora del committer):
+/// una clave rotada o expirada después sigue validando el historial que firmó.
+///
+/// En caso de fallo devuelve el código de razón más específico encontrado:
+/// `no_ticket`, `malformed_ticket`, `unknown_key`, `bad_signature`,
+/// `key_not_yet_valid`, `key_expired`, `key_revoked`, un error de binding, `invalid_pow`,
+/// `timestamp_skew` si el ticket se emitió lejos de la hora del committer,
+/// `signer_mismatch` si la clave no puede firmar por el autor del commit
+/// o `threshold_not_met` si trust.toml exige más firmas de las presentes.
+pub fn verify_commit_ticket(
+    commit: &git2::Commit,
+    attestation: &str,
+    trust: &crate::trust::TrustConfig,
+) -> Result<TicketVerification, String> {
+    let values = extract_ticket_values(attestation);
+    if values.is_empty() {
+        return Err("no_ticket".to_string());
+    }
+
+    let commit_time = commit.time().seconds();
+    let mut reason = "malformed_ticket".to_string();
+    for value in values {
+        let ticket = match SignedTicket::parse_trailer_value(value) {
+            Ok(t) => t,
+            Err(_) => continue,
+        };
+
+        let signer = match resolve_signer(trust, &ticket.payload.key_id, commit_time, |pk| ticket.verify_signature(pk)) {
+            Ok(s) => s,
+            Err(r) => {
+                reason = r;
+                continue;
+            }
+        };
+
+        if let Err(r) = ticket.check_binding(commit).and_then(|_| ticket.check_pow()) {
+            reason = r;
+            continue;
+        }
+
+        if (ticket.payload.issued_at - commit_time).abs() > crate::clock::MAX_COMMIT_SKEW_SECS {
+            reason = "timestamp_skew".to_string();
+            continue;
+        }
+
+        // El binding ya garantiza que ticket y commit comparten autor
+        if !signer.may_sign_for(&ticket.payload.binding.author) {
+            reason = "signer_mismatch".to_string();
+            continue;
+        }
+
+        // Emisor + co-firmantes, sin repetir alias
+        let mut signers = vec![TicketSigner::new(signer, ticket.payload.key_id.clone())];
+        for cosigner in verify_cosignatures(attestation, &ticket, trust, commit_time) {
+            if !signers.iter().any(|s| s.alias == cosigner.alias) {
+                signers.push(cosigner);
+            }
+        }
+
+        let co_authors = co_authors(commit.message().unwrap_or(""))
+            .into_iter()
+            .filter(|(_, email)| !email.eq_ignore_ascii_case(&ticket.payload.binding.author))
+            .map(|(name, email)| {
+                // Una clave sin `emails` no atestigua a nadie en concreto
+                let attested_by = signers
+                    .iter()
+                    .find(|s| s.emails.iter().any(|e| e.eq_ignore_ascii_case(&email)))
+                    .map(|s| s.alias.clone());
+                CoAuthor { name, email, attested_by }
+            })
+            .collect();
+
+        match trust.threshold_policy() {
+            Ok(Some(policy)) if !policy.is_satisfied(signers.iter().map(|s| (s.alias.as_str(), s.role.as_str()))) => {
+                reason = "threshold_not_met".to_string();
+            }
+            Err(_) => reason = "invalid_threshold_policy".to_string(),
+            Ok(_) => return Ok(TicketVerification { signer: signer.alias.clone(), ticket, signers, co_authors }),
+        }
+    }
+
+    Err(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_keypair, key_id, VerifyingKey};
+    use crate::testing::TempDir;
+
+    fn sample_binding() -> TicketBinding {
+        TicketBinding {
+            tree: "4b825dc642cb6eb9a060e54bf8d69288fbee4904".to_string(),
+            parent: None,
+            author: "dev@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn payload_roundtrip() {
+        let payload = TicketPayload::new(TicketMode::Valid, 12.5, sample_binding(), "abcd");
+        let decoded = TicketPayload::decode(&payload.encode()).unwrap();
+        assert_eq!(decoded, payload);
+
+        let stamped = payload.clone().with_clock(ClockStamp { boot_id: "ab".repeat(16), mono_ms: 1234 });
+        assert!(stamped.encode().contains(";kid=abcd;boot=abababababababababababababababab;mono=1234"));
+        assert_eq!(TicketPayload::decode(&stamped.encode()).unwrap(), stamped);
+
+        let logged = payload
+            .with_chain(ChainLink { seq: 7, prev: Some("ab".repeat(32)) })
+            .with_log_index(41);
+        assert!(logged.encode().ends_with(";seq=7;prev=abababababababababababababababababababababababababababababababab;log=41"));
+        assert_eq!(TicketPayload::decode(&logged.encode()).unwrap(), logged);
+    }
+
+    #[test]
+    fn injected_binding_fields_are_rejected() {
+        // Un autor con separadores duplicaría `mode`/`proof` al codificar
+        let mut binding = sample_binding();
+        binding.author = "x;mode=valid".to_string();
+        let payload = TicketPayload::new(TicketMode::Audit, 1.0, binding.clone(), "abcd");
+        assert!(TicketPayload::decode(&payload.encode()).unwrap_err().contains("Duplicate"));
+        assert!(binding.validate().is_err());
+        binding.author = "x;evil=1".to_string();
+        let encoded = TicketPayload::new(TicketMode::Valid, 1.0, binding, "abcd").encode();
+        assert!(TicketPayload::decode(&encoded).unwrap_err().contains("Unknown"));
+
+        assert!(sample_binding().validate().is_ok());
+        let bad_tree = TicketBinding { tree: "t".to_string(), ..sample_binding() };
+        assert!(bad_tree.validate().is_err());
+        let bad_parent = TicketBinding { parent: Some("HEAD".to_string()), ..sample_binding() };
+        assert!(bad_parent.validate().is_err());
+    }
+
+    fn software_identity() -> (DaemonIdentity, VerifyingKey) {
+        let (signing_key, verifying_key) = generate_keypair();
+        (DaemonIdentity::Software(signing_key), verifying_key)
+    }
+
+    #[test]
+    fn signed_trailer_roundtrip_and_tamper() {
+        let (identity, verifying_key) = software_identity();
+        let payload = TicketPayload::new(TicketMode::Audit, 3.0, sample_binding(), key_id(&verifying_key));
+        let ticket = SignedTicket::sign(payload, &identity).unwrap();
+
+        let parsed = SignedTicket::parse_trailer_value(&ticket.to_trailer_value()).unwrap();
+        assert!(parsed.verify_signature(&identity.public_key()));
+        assert_eq!(parsed.payload.mode, TicketMode::Audit);
+
+        let forged = ticket.to_trailer_value().replace("score=3.00", "score=90.00");
+        let forged = SignedTicket::parse_trailer_value(&forged).unwrap();
+        assert!(!forged.verify_signature(&identity.public_key()));
+    }
+
+    #[test]
+    fn verify_commit_rejects_tree_mismatch() {
+        let repo_path = TempDir::new("ticket_repo");
+        let repo = git2::Repository::init(&repo_path).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+
+        let (identity, verifying_key) = software_identity();
+        let mut trusted = crate::trust::TrustConfig::default();
+        trusted.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+
+        let mut binding = sample_binding();
+        binding.tree = tree_id.to_string();
+        let good = SignedTicket::sign(TicketPayload::new(TicketMode::Valid, 5.0, binding.clone(), key_id(&verifying_key)), &identity).unwrap();
+        binding.tree = "0000000000000000000000000000000000000000".to_string();
+        let stale = SignedTicket::sign(TicketPayload::new(TicketMode::Valid, 5.0, binding, key_id(&verifying_key)), &identity).unwrap();
+
+        let msg = format!("ok\n\n{}: {}", TICKET_TRAILER, good.to_trailer_value());
+        let oid = repo.commit(None, &sig, &sig, &msg, &tree, &[]).unwrap();
+        let verification = verify_commit_ticket(&repo.find_commit(oid).unwrap(), &msg, &trusted).unwrap();
+        assert_eq!(verification.signer, "dev");
+
+        let msg = format!("stale\n\n{}: {}", TICKET_TRAILER, stale.to_trailer_value());
+        let oid = repo.commit(None, &sig, &sig, &msg, &tree, &[]).unwrap();
+        let err = verify_commit_ticket(&repo.find_commit(oid).unwrap(), &msg, &trusted).unwrap_err();
+        assert_eq!(err, "tree_mismatch");
+    }
+
+    #[test]
+    fn verify_uses_key_state_at_commit_time() {
+        let repo_path = TempDir::new("ticket_repo");
+        let repo = git2::Repository::init(&repo_path).unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+
+        let (identity, verifying_key) = software_identity();
+        let mut trusted = crate::trust::TrustConfig::default();
+        trusted.add_key("dev", &hex::encode(verifying_key.as_bytes()), None).unwrap();
+        let rotated_at: chrono::DateTime<chrono::Utc> = "2026-05-01T00:00:00Z".parse().unwrap();
+        trusted.rotate("dev", &hex::encode(generate_keypair().1.as_bytes()), rotated_at, chrono::Duration::zero()).unwrap();
+
+        let mut binding = sample_binding();
+        binding.tree = tree_id.to_string();
+
+        // Ticket emitido `skew` segundos después de un commit hecho en `ts`
+        let commit_at = |ts: i64, skew: i64| {
+            let mut payload = TicketPayload::new(TicketMode::Valid, 5.0, binding.clone(), key_id(&verifying_key));
+            payload.issued_at = ts + skew;
+            let ticket = SignedTicket::sign(payload, &identity).unwrap();
+            let msg = format!("ok\n\n{}: {}", TICKET_TRAILER, ticket.to_trailer_value());
+            let sig = git2::Signature::new("Dev", "dev@example.com", &git2::Time::new(ts, 0)).unwrap();
+            let oid = repo.commit(None, &sig, &sig, &msg, &tree, &[]).unwrap();
+            (repo.find_commit(oid).unwrap(), msg)
+        };
+
+        let (before, msg) = commit_at(rotated_at.timestamp() - 3600, 5);
+        assert_eq!(verify_commit_ticket(&before, &msg, &trusted).unwrap().signer, "dev");
+
+        let (after, after_msg) = commit_at(rotated_at.timestamp() + 3600, 5);
+        assert_eq!(verify_commit_ticket(&after, &after_msg, &trusted).unwrap_err(), "key_expired");
+
+        // Un ticket de hoy no sirve para un commit fechado meses atrás
+        let (backdated, backdated_msg) = commit_at(rotated_at.timestamp() - 3600, crate::clock::MAX_COMMIT_SKEW_SECS + 1);
+        assert_eq!(verify_commit_ticket(&backdated, &backdated_msg, &trusted).unwrap_err(), "timestamp_skew");
+
+        trusted.revoke(&key_id(&verifying_key), crate::trust::REASON_COMPROMISED, rotated_at).unwrap();
+        assert_eq!(verify_commit_ticket(&before, &msg, &trusted).unwrap_err(), "key_revoked");
+    }
+
+    #[test]
+    fn cosignatures_satisfy_the_threshold_policy() {
+        let repo_path = TempDir::new("ticket_repo");
+        let repo = git2::Repository::init(&repo_path).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+
+        let (driver, driver_key) = software_identity();
+        let (navigator, navigator_key) = software_identity();
+        let (maintainer, maintainer_key) = software_identity();
+        let mut trusted = crate::trust::TrustConfig::default();
+        trusted.add_key("driver", &hex::encode(driver_key.as_bytes()), None).unwrap();
+        trusted.add_key("navigator", &hex::encode(navigator_key.as_bytes()), None).unwrap();
+        trusted.add_key("lead", &hex::encode(maintainer_key.as_bytes()), None).unwrap();
+        trusted.keys[2].role = "Maintainer".to_string();
+        trusted.threshold = Some("2 of Contributor, or 1 Maintainer".to_string());
+
+        let mut binding = sample_binding();
+        binding.tree = tree_id.to_string();
+        let ticket = SignedTicket::sign(TicketPayload::new(TicketMode::Valid, 5.0, binding, key_id(&driver_key)), &driver).unwrap();
+        let cosign = |identity: &DaemonIdentity| Cosignature::sign(&ticket.encoded, identity).unwrap().to_trailer_value();
+
+        let verify = |msg: &str| {
+            let oid = repo.commit(None, &sig, &sig, msg, &tree, &[]).unwrap();
+            verify_commit_ticket(&repo.find_commit(oid).unwrap(), msg, &trusted)
+        };
+
+        let solo = format!("solo\n\n{}: {}", TICKET_TRAILER, ticket.to_trailer_value());
+        assert_eq!(verify(&solo).unwrap_err(), "threshold_not_met");
+
+        let pair = format!("{}\n{}: {}", solo, COSIGN_TRAILER, cosign(&navigator));
+        let verification = verify(&pair).unwrap();
+        assert_eq!(verification.signers.len(), 2);
+        assert_eq!(verification.signers[1].alias, "navigator");
+
+        let override_ = format!("{}\n{}: {}", solo, COSIGN_TRAILER, cosign(&maintainer));
+        assert_eq!(verify(&override_).unwrap().signers[1].role, "Maintainer");
+
+        // El emisor no puede co-firmarse a sí mismo, y una co-firma no vale como ticket
+        let self_cosigned = format!("{}\n{}: {}", solo, COSIGN_TRAILER, cosign(&driver));
+        assert_eq!(verify(&self_cosigned).unwrap_err(), "threshold_not_met");
+        let cosig = Cosignature::sign(&ticket.encoded, &navigator).unwrap();
+        assert!(!SignedTicket { signature: cosig.signature, ..ticket.clone() }.verify_signature(&navigator.public_key()));
+    }
+
+    #[test]
+    fn signer_must_be_allowed_for_the_author() {
+        let repo_path = TempDir::new("ticket_repo");
+        let repo = git2::Repository::init(&repo_path).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+
+        let (alice, alice_key) = software_identity();
+        let (bob, bob_key) = software_identity();
+        let mut trusted = crate::trust::TrustConfig::default();
+        trusted.add_key("alice", &hex::encode(alice_key.as_bytes()), None).unwrap();
+        trusted.add_key("bob", &hex::encode(bob_key.as_bytes()), None).unwrap();
+        trusted.set_emails("alice", &["Dev@example.com".to_string()]).unwrap();
+        trusted.set_emails("bob", &["bob@example.com".to_string()]).unwrap();
+
+        let mut binding = sample_binding();
+        binding.tree = tree_id.to_string();
+        let ticket = |identity: &DaemonIdentity, key: &VerifyingKey| {
+            SignedTicket::sign(TicketPayload::new(TicketMode::Valid, 5.0, binding.clone(), key_id(key)), identity).unwrap()
+        };
+        let verify = |msg: &str| {
+            let oid = repo.commit(None, &sig, &sig, msg, &tree, &[]).unwrap();
+            verify_commit_ticket(&repo.find_commit(oid).unwrap(), msg, &trusted)
+        };
+
+        // El daemon de Bob no firma por dev@example.com
+        let by_bob = format!("x\n\n{}: {}", TICKET_TRAILER, ticket(&bob, &bob_key).to_trailer_value());
+        assert_eq!(verify(&by_bob).unwrap_err(), "signer_mismatch");
+
+        // Co-autoría: solo la atestigua una co-firma de una clave con ese email
+        let by_alice = ticket(&alice, &alice_key);
+        let paired = format!("x\n\nco-authored-by: Bob <bob@example.com>\n{}: {}", TICKET_TRAILER, by_alice.to_trailer_value());
+        let verification = verify(&paired).unwrap();
+        assert_eq!(verification.signer, "alice");
+        assert_eq!(verification.co_authors[0].email, "bob@example.com");
+        assert_eq!(verification.co_authors[0].attested_by, None);
+
+        let cosign = Cosignature::sign(&by_alice.encoded, &bob).unwrap().to_trailer_value();
+        let cosigned = format!("{}\n{}: {}", paired, COSIGN_TRAILER, cosign);
+        assert_eq!(verify(&cosigned).unwrap().co_authors[0].attested_by.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn pow_tickets_roundtrip_and_are_rechecked() {
+        let (identity, verifying_key) = software_identity();
+        let binding = sample_binding();
+        let mut challenge = crate::pow::create_challenge(&binding, 1.0);
+        challenge.difficulty = 8;
+        let nonce = crate::pow::solve(&challenge, |_| {}).unwrap();
+
+        let stamp = PowStamp { difficulty: 8, salt: challenge.salt.clone(), nonce };
+        let payload = TicketPayload::new(TicketMode::Valid, 7.0, binding, key_id(&verifying_key)).with_pow(stamp);
+        assert_eq!(TicketPayload::decode(&payload.encode()).unwrap(), payload);
+
+        let ticket = SignedTicket::sign(payload, &identity).unwrap();
+        let parsed = SignedTicket::parse_trailer_value(&ticket.to_trailer_value()).unwrap();
+        assert_eq!(parsed.payload.mode, TicketMode::Pow);
+        assert!(parsed.check_pow().is_ok());
+
+        // Un nonce que no cumple la dificultad no pasa la recomprobación
+        let mut bogus = parsed.clone();
+        bogus.payload.pow.as_mut().unwrap().difficulty = 64;
+        assert_eq!(bogus.check_pow(), Err("invalid_pow".to_string()));
+    }
+
+    #[test]
+    fn proof_trailer_is_committed_by_ticket() {
+        let repo_path = TempDir::new("ticket_repo");
+        let repo = git2::Repository::init(&repo_path).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let (identity, verifying_key) = software_identity();
+
+        let mut binding = sample_binding();
+        binding.tree = tree_id.to_string();
+        let proof = HumanityProof::generate(75, 50, tree_id.to_string().as_bytes()).unwrap().to_trailer_value();
+        let payload = TicketPayload::new(TicketMode::Valid, 5.0, binding, key_id(&verifying_key)).with_proof(&proof);
+        let ticket = SignedTicket::sign(payload, &identity).unwrap();
+
+        let msg = format!("ok\n\n{}: {}\n{}: {}", TICKET_TRAILER, ticket.to_trailer_value(), PROOF_TRAILER, proof);
+        let oid = repo.commit(None, &sig, &sig, &msg, &tree, &[]).unwrap();
+        assert_eq!(verify_commit_proof(&repo.find_commit(oid).unwrap(), &msg, &ticket), Ok(Some(50)));
+
+        // Una prueba distinta (aunque válida) no coincide con el digest firmado
+        let other = HumanityProof::generate(99, 50, tree_id.to_string().as_bytes()).unwrap().to_trailer_value();
+        let msg = format!("swap\n\n{}: {}\n{}: {}", TICKET_TRAILER, ticket.to_trailer_value(), PROOF_TRAILER, other);
+        let oid = repo.commit(None, &sig, &sig, &msg, &tree, &[]).unwrap();
+        assert_eq!(verify_commit_proof(&repo.find_commit(oid).unwrap(), &msg, &ticket), Err("proof_digest_mismatch".to_string()));
+    }
+}
@@ -0,0 +1,516 @@
+//! Registro de transparencia local de los tickets emitidos
+//!
+//! Cada ticket que firma el daemon (incluidos los `mode=audit`, que pasan por
+//! encima de la batería) se añade como hoja de un árbol de Merkle RFC 6962/9162
+//! guardado en `~/.local/share/cliff-watch/log`:
+//!
+//! ```text
+//! entries   una hoja por línea: el valor del trailer cliff-watch-score
+//! heads     un Signed Tree Head por línea, tras cada hoja
+//! ```
+//!
+//! El ticket lleva su índice de hoja dentro del payload firmado (`;log=<n>`) y
+//! el commit recibe el STH del árbol que lo incluye:
+//!
+//! ```text
+//! Cliff-Watch-Log: size=<n>;root=<hex>;ts=<unix>;kid=<hex>:sig=<hex>
+//! ```
+//!
+//! Descartar o reescribir un ticket del log rompe la prueba de inclusión del
+//! commit que lo usa o la consistencia con los heads firmados posteriores.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{DaemonIdentity, PublicKey};
+
+/// Clave del trailer (o línea de nota) con el Signed Tree Head
+pub const LOG_TRAILER: &str = "Cliff-Watch-Log";
+
+/// Separador de dominio de las firmas de heads: nunca valen como ticket
+const STH_DOMAIN: &[u8] = b"cliff-watch-sth/v1\0";
+
+const ENTRIES_FILE: &str = "entries";
+const HEADS_FILE: &str = "heads";
+
+/// Hash de nodo del árbol (SHA256)
+pub type Hash = [u8; 32];
+
+/// Hash de hoja: `SHA256(0x00 || datos)`
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Hash de nodo interno: `SHA256(0x01 || izq || der)`
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Mayor potencia de dos estrictamente menor que `n` (n > 1)
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// Raíz (MTH) de una lista de hashes de hoja
+pub fn merkle_root(leaves: &[Hash]) -> Hash {
+    match leaves.len() {
+        0 => Sha256::digest([]).into(),
+        1 => leaves[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&merkle_root(&leaves[..k]), &merkle_root(&leaves[k..]))
+        }
+    }
+}
+
+/// Prueba de inclusión (PATH) de la hoja `index` en el árbol formado por `leaves`
+pub fn inclusion_path(leaves: &[Hash], index: usize) -> Vec<Hash> {
+    if leaves.len() <= 1 {
+        return Vec::new();
+    }
+    let k = split_point(leaves.len());
+    if index < k {
+        let mut path = inclusion_path(&leaves[..k], index);
+        path.push(merkle_root(&leaves[k..]));
+        path
+    } else {
+        let mut path = inclusion_path(&leaves[k..], index - k);
+        path.push(merkle_root(&leaves[..k]));
+        path
+    }
+}
+
+/// Prueba de consistencia (PROOF) entre los primeros `old_size` hashes y `leaves`
+pub fn consistency_path(leaves: &[Hash], old_size: usize) -> Vec<Hash> {
+    fn subproof(leaves: &[Hash], m: usize, complete: bool) -> Vec<Hash> {
+        let n = leaves.len();
+        if m == n {
+            return if complete { Vec::new() } else { vec![merkle_root(leaves)] };
+        }
+        let k = split_point(n);
+        if m <= k {
+            let mut proof = subproof(&leaves[..k], m, complete);
+            proof.push(merkle_root(&leaves[k..]));
+            proof
+        } else {
+            let mut proof = subproof(&leaves[k..], m - k, false);
+            proof.push(merkle_root(&leaves[..k]));
+            proof
+        }
+    }
+
+    if old_size == 0 || old_size >= leaves.len() {
+        return Vec::new();
+    }
+    subproof(leaves, old_size, true)
+}
+
+/// Verifica una prueba de inclusión (RFC 9162, 2.1.3.2)
+pub fn verify_inclusion(leaf: &Hash, index: u64, size: u64, proof: &[Hash], root: &Hash) -> bool {
+    if index >= size {
+        return false;
+    }
+    let (mut fn_, mut sn) = (index, size - 1);
+    let mut r = *leaf;
+    for p in proof {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            r = node_hash(p, &r);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    sn == 0 && &r == root
+}
+
+/// Verifica una prueba de consistencia entre dos raíces (RFC 9162, 2.1.4.2)
+pub fn verify_consistency(old_size: u64, new_size: u64, old_root: &Hash, new_root: &Hash, proof: &[Hash]) -> bool {
+    if old_size > new_size {
+        return false;
+    }
+    if old_size == new_size {
+        return proof.is_empty() && old_root == new_root;
+    }
+    if old_size == 0 {
+        return proof.is_empty();
+    }
+    if proof.is_empty() {
+        return false;
+    }
+
+    let mut path = proof.to_vec();
+    if old_size.is_power_of_two() {
+        path.insert(0, *old_root);
+    }
+
+    let (mut fn_, mut sn) = (old_size - 1, new_size - 1);
+    while fn_ & 1 == 1 {
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    let (mut fr, mut sr) = (path[0], path[0]);
+    for c in &path[1..] {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            fr = node_hash(c, &fr);
+            sr = node_hash(c, &sr);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            sr = node_hash(&sr, c);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    sn == 0 && &fr == old_root && &sr == new_root
+}
+
+/// Estado del árbol que firma el daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeHead {
+    pub size: u64,
+    pub root: Hash,
+    /// Hora de firma (UNIX epoch, segundos)
+    pub timestamp: i64,
+    /// Identificador de la clave del daemon (ver `crypto::key_id`)
+    pub key_id: String,
+}
+
+impl TreeHead {
+    /// Serialización canónica (es lo que se firma)
+    pub fn encode(&self) -> String {
+        format!("size={};root={};ts={};kid={}", self.size, hex::encode(self.root), self.timestamp, self.key_id)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, String> {
+        let mut fields = std::collections::HashMap::new();
+        for part in encoded.split(';') {
+            let (k, v) = part.split_once('=').ok_or_else(|| format!("Malformed tree head field '{}'", part))?;
+            fields.insert(k, v);
+        }
+        let get = |k: &str| fields.get(k).copied().ok_or_else(|| format!("Tree head field '{}' missing", k));
+
+        let root: Hash = hex::decode(get("root")?)
+            .ok()
+            .and_then(|r| r.try_into().ok())
+            .ok_or("Invalid tree head root")?;
+        Ok(Self {
+            size: get("size")?.parse().map_err(|_| "Invalid tree head size".to_string())?,
+            root,
+            timestamp: get("ts")?.parse().map_err(|_| "Invalid tree head timestamp".to_string())?,
+            key_id: get("kid")?.to_string(),
+        })
+    }
+}
+
+/// Tree head firmado tal como aparece en el trailer `Cliff-Watch-Log`
+#[derive(Debug, Clone)]
+pub struct SignedTreeHead {
+    pub head: TreeHead,
+    pub signature: Vec<u8>,
+}
+
+impl SignedTreeHead {
+    fn signed_bytes(head: &TreeHead) -> Vec<u8> {
+        [STH_DOMAIN, head.encode().as_bytes()].concat()
+    }
+
+    pub fn sign(head: TreeHead, identity: &DaemonIdentity) -> Result<Self, String> {
+        let signature = identity.sign(&Self::signed_bytes(&head))?;
+        Ok(Self { head, signature })
+    }
+
+    pub fn verify(&self, public_key: &PublicKey) -> bool {
+        public_key.verify(&Self::signed_bytes(&self.head), &self.signature)
+    }
+
+    /// Valor del trailer: `<head>:sig=<hex>`
+    pub fn to_trailer_value(&self) -> String {
+        format!("{}:sig={}", self.head.encode(), hex::encode(&self.signature))
+    }
+
+    pub fn parse_trailer_value(value: &str) -> Result<Self, String> {
+        let (encoded, sig_hex) = value.trim().rsplit_once(":sig=").ok_or("Tree head without signature")?;
+        Ok(Self {
+            head: TreeHead::decode(encoded)?,
+            signature: hex::decode(sig_hex).map_err(|_| "Invalid tree head signature encoding".to_string())?,
+        })
+    }
+}
+
+/// Log append-only en disco (hojas + heads firmados)
+#[derive(Debug)]
+pub struct TransparencyLog {
+    dir: PathBuf,
+    leaves: Vec<Hash>,
+}
+
+impl TransparencyLog {
+    /// `log/` dentro del directorio de datos (ver `config::data_dir`)
+    pub fn default_dir() -> Result<PathBuf, String> {
+        Ok(crate::config::data_dir()?.join("log"))
+    }
+
+    /// Abre (o crea) el log y recalcula los hashes de sus hojas
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create log directory {}: {}", dir.display(), e))?;
+        let mut log = Self { dir: dir.to_path_buf(), leaves: Vec::new() };
+        log.leaves = log.entries()?.iter().map(|e| leaf_hash(e.as_bytes())).collect();
+        Ok(log)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn size(&self) -> u64 {
+        self.leaves.len() as u64
+    }
+
+    /// Hojas del log, en orden
+    pub fn entries(&self) -> Result<Vec<String>, String> {
+        read_lines(&self.dir.join(ENTRIES_FILE))
+    }
+
+    /// Heads firmados, en orden de emisión
+    pub fn heads(&self) -> Result<Vec<SignedTreeHead>, String> {
+        read_lines(&self.dir.join(HEADS_FILE))?
+            .iter()
+            .map(|l| SignedTreeHead::parse_trailer_value(l))
+            .collect()
+    }
+
+    /// Raíz del árbol formado por las primeras `size` hojas
+    pub fn root_at(&self, size: u64) -> Option<Hash> {
+        self.leaves.get(..size as usize).map(merkle_root)
+    }
+
+    /// Añade una hoja y devuelve su índice. La entrada no puede contener saltos de línea.
+    pub fn append(&mut self, entry: &str) -> Result<u64, String> {
+        if entry.contains('\n') {
+            return Err("Log entries must be single-line".to_string());
+        }
+        append_line(&self.dir.join(ENTRIES_FILE), entry)?;
+        self.leaves.push(leaf_hash(entry.as_bytes()));
+        Ok(self.size() - 1)
+    }
+
+    /// Firma el estado actual del árbol y lo añade a `heads`
+    pub fn sign_head(&self, identity: &DaemonIdentity) -> Result<SignedTreeHead, String> {
+        let head = TreeHead {
+            size: self.size(),
+            root: merkle_root(&self.leaves),
+            timestamp: chrono::Utc::now().timestamp(),
+            key_id: identity.public_key().key_id(),
+        };
+        let sth = SignedTreeHead::sign(head, identity)?;
+        append_line(&self.dir.join(HEADS_FILE), &sth.to_trailer_value())?;
+        Ok(sth)
+    }
+
+    pub fn inclusion_proof(&self, index: u64, size: u64) -> Option<Vec<Hash>> {
+        let leaves = self.leaves.get(..size as usize)?;
+        (index < size).then(|| inclusion_path(leaves, index as usize))
+    }
+
+    pub fn consistency_proof(&self, old_size: u64, new_size: u64) -> Option<Vec<Hash>> {
+        let leaves = self.leaves.get(..new_size as usize)?;
+        (old_size <= new_size).then(|| consistency_path(leaves, old_size as usize))
+    }
+
+    /// Comprueba que un head firmado es un estado pasado de este log.
+    ///
+    /// Códigos de razón: `log_truncated` si el log es más corto que el head,
+    /// `inconsistent_log` si la prueba de consistencia no lleva a su raíz.
+    pub fn check_head(&self, head: &TreeHead) -> Result<(), String> {
+        let current = merkle_root(&self.leaves);
+        let proof = self.consistency_proof(head.size, self.size()).ok_or("log_truncated")?;
+        if !verify_consistency(head.size, self.size(), &head.root, &current, &proof) {
+            return Err("inconsistent_log".to_string());
+        }
+        Ok(())
+    }
+
+    /// Comprueba que `entry` es la hoja `index` del árbol firmado en `head`
+    /// y que el log actual sigue siendo consistente con ese head.
+    ///
+    /// Códigos de razón: `missing_entry`, `inclusion_failed` y los de `check_head`.
+    pub fn check_entry(&self, entry: &str, index: u64, head: &TreeHead) -> Result<(), String> {
+        if index >= head.size {
+            return Err("missing_entry".to_string());
+        }
+        let proof = self.inclusion_proof(index, head.size).ok_or("log_truncated")?;
+        if !verify_inclusion(&leaf_hash(entry.as_bytes()), index, head.size, &proof, &head.root) {
+            return Err("inclusion_failed".to_string());
+        }
+        self.check_head(head)
+    }
+
+    /// Comprueba el ticket de una atestación (mensaje y nota) contra el log:
+    /// inclusión en el head que lo acompaña y consistencia de ese head con el log actual.
+    ///
+    /// Devuelve el índice de la hoja, `None` si el ticket no se anotó (daemon sin log),
+    /// o un código de razón: `no_ticket`, `missing_log_head`, `unknown_log_key`,
+    /// `bad_head_signature` o los de `check_entry`.
+    pub fn check_attestation(&self, attestation: &str, public_key: impl Fn(&str) -> Option<PublicKey>) -> Result<Option<u64>, String> {
+        let tickets = crate::ticket::extract_ticket_values(attestation);
+        if tickets.is_empty() {
+            return Err("no_ticket".to_string());
+        }
+        let logged = tickets.into_iter().find_map(|value| {
+            let ticket = crate::ticket::SignedTicket::parse_trailer_value(value).ok()?;
+            Some((value, ticket.payload.log_index?))
+        });
+        let (value, index) = match logged {
+            Some(l) => l,
+            None => return Ok(None),
+        };
+
+        let sth = crate::ticket::extract_trailer_values(attestation, LOG_TRAILER)
+            .into_iter()
+            .find_map(|v| SignedTreeHead::parse_trailer_value(v).ok())
+            .ok_or("missing_log_head")?;
+        let key = public_key(&sth.head.key_id).ok_or("unknown_log_key")?;
+        if !sth.verify(&key) {
+            return Err("bad_head_signature".to_string());
+        }
+        self.check_entry(value, index, &sth.head)?;
+        Ok(Some(index))
+    }
+
+    /// Audita los heads firmados: firma, crecimiento monótono y consistencia
+    /// de cada uno con el log actual (y, por tanto, entre sí).
+    ///
+    /// Devuelve el número de heads comprobados, o el código de razón del primer
+    /// fallo (`unknown_log_key`, `bad_head_signature`, `inconsistent_heads`,
+    /// o los de `check_head`).
+    pub fn audit(&self, public_key: impl Fn(&str) -> Option<PublicKey>) -> Result<usize, String> {
+        let heads = self.heads()?;
+        let mut previous_size = 0;
+        for sth in &heads {
+            let key = public_key(&sth.head.key_id).ok_or("unknown_log_key")?;
+            if !sth.verify(&key) {
+                return Err("bad_head_signature".to_string());
+            }
+            if sth.head.size < previous_size {
+                return Err("inconsistent_heads".to_string());
+            }
+            self.check_head(&sth.head)?;
+            previous_size = sth.head.size;
+        }
+        Ok(heads.len())
+    }
+}
+
+fn read_lines(path: &Path) -> Result<Vec<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content.lines().filter(|l| !l.is_empty()).map(str::to_string).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> Result<(), String> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line)
+        .and_then(|_| file.sync_data())
+        .map_err(|e| format!("Failed to append to {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: usize) -> Vec<Hash> {
+        (0..n).map(|i| leaf_hash(format!("entry-{}", i).as_bytes())).collect()
+    }
+
+    #[test]
+    fn inclusion_and_consistency_proofs_verify() {
+        for n in 1..=17 {
+            let all = leaves(n);
+            let root = merkle_root(&all);
+            for i in 0..n {
+                let proof = inclusion_path(&all, i);
+                assert!(verify_inclusion(&all[i], i as u64, n as u64, &proof, &root), "inclusion {} in {}", i, n);
+                assert!(!verify_inclusion(&leaf_hash(b"other"), i as u64, n as u64, &proof, &root));
+            }
+            for m in 1..=n {
+                let proof = consistency_path(&all, m);
+                let old_root = merkle_root(&all[..m]);
+                assert!(verify_consistency(m as u64, n as u64, &old_root, &root, &proof), "consistency {} -> {}", m, n);
+                if m < n {
+                    assert!(!verify_consistency(m as u64, n as u64, &leaf_hash(b"forged"), &root, &proof));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dropped_entries_break_signed_heads() {
+        use crate::ticket::{SignedTicket, TicketBinding, TicketMode, TicketPayload};
+
+        let dir = crate::testing::TempDir::new("cliff_watch_log");
+        let identity = DaemonIdentity::Software(crate::crypto::generate_keypair().0);
+        let public_key = identity.public_key();
+        let keys = |_: &str| Some(public_key.clone());
+
+        // Mismo flujo que el daemon: reservar hoja, firmar, anotar y firmar el head
+        let mut log = TransparencyLog::open(&dir).unwrap();
+        let mut attestations = Vec::new();
+        for _ in 0..5 {
+            let binding = TicketBinding { tree: "t".into(), parent: None, author: "dev@example.com".into() };
+            let payload = TicketPayload::new(TicketMode::Audit, 1.0, binding, public_key.key_id()).with_log_index(log.size());
+            let ticket = SignedTicket::sign(payload, &identity).unwrap().to_trailer_value();
+            log.append(&ticket).unwrap();
+            let sth = log.sign_head(&identity).unwrap();
+            attestations.push(format!("feat: x\n\ncliff-watch-score: {}\n{}: {}\n", ticket, LOG_TRAILER, sth.to_trailer_value()));
+        }
+
+        let reopened = TransparencyLog::open(&dir).unwrap();
+        assert_eq!(reopened.audit(keys).unwrap(), 5);
+        for (i, attestation) in attestations.iter().enumerate() {
+            assert_eq!(reopened.check_attestation(attestation, keys), Ok(Some(i as u64)));
+        }
+        assert_eq!(reopened.check_attestation("feat: x", keys).unwrap_err(), "no_ticket");
+
+        // El desarrollador borra un ticket que le incomoda y reescribe el log
+        let entries = reopened.entries().unwrap();
+        std::fs::write(dir.join(ENTRIES_FILE), format!("{}\n{}\n", entries[0], entries[2..].join("\n"))).unwrap();
+        let rewritten = TransparencyLog::open(&dir).unwrap();
+        assert_eq!(rewritten.check_attestation(&attestations[0], keys), Ok(Some(0)), "El prefijo intacto sigue probándose");
+        assert_eq!(rewritten.check_attestation(&attestations[1], keys), Err("inconsistent_log".to_string()));
+        assert!(rewritten.check_attestation(&attestations[3], keys).is_err());
+        assert!(rewritten.audit(keys).is_err());
+
+    }
+}
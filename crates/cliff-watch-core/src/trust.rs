@@ -0,0 +1,1001 @@
+//! Conjunto de claves confiables del repositorio (`trust.toml`)
+//!
+//! Cada alias puede tener varias claves, cada una con su ventana de validez
+//! (`not_before`/`not_after`). Las revocaciones viven en una lista aparte con
+//! su motivo, de modo que el historial de claves nunca se reescribe.
+//!
+//! ```toml
+//! [[keys]]
+//! alias = "alice"
+//! public_key = "<hex>"
+//! role = "Contributor"
+//! not_before = "2026-01-01T00:00:00Z"
+//! not_after = "2026-07-01T00:00:00Z"
+//!
+//! [[revoked]]
+//! public_key = "<hex>"
+//! reason = "compromised"
+//! revoked_at = "2026-03-10T12:00:00Z"
+//! ```
+//!
+//! Un commit se juzga con el estado de la clave en el instante del commit:
+//! rotar o expirar una clave no invalida el historial ya firmado. La única
+//! excepción es una revocación por `compromised`, que es retroactiva.
+//!
+//! `verify` toma trust.toml del árbol del padre del commit (`load_trust_at`),
+//! no del working tree: un commit no puede admitir la clave que lo firma, y
+//! funciona igual en repos bare y hooks de servidor. El commit que introduce
+//! trust.toml por primera vez se juzga con su propio árbol (TOFU).
+//!
+//! Opcionalmente, `threshold` exige varias firmas por commit (ticket más
+//! co-firmas `Cliff-Watch-Cosign`), contadas por alias distinto y rol:
+//!
+//! ```toml
+//! threshold = "2 of Contributor, or 1 Maintainer"
+//! ```
+//!
+//! Las claves también pueden venir del `allowed_signers` de git
+//! (`gpg.ssh.allowedSignersFile`): se suman a trust.toml como Contributors,
+//! con `valid-after`/`valid-before` como ventana de validez.
+//!
+//! `emails` limita los autores de git por los que puede firmar una clave: un
+//! ticket de Alice sobre un commit de Bob falla con `signer_mismatch`. Sin
+//! `emails`, la clave firma por cualquier autor (comportamiento anterior).
+//!
+//! ## Red de confianza
+//!
+//! Cualquiera con permiso de escritura puede añadir claves a trust.toml. Con
+//! claves raíz fijadas en la configuración de git del clon
+//! (`cliff-watch.trustRoot`), el conjunto efectivo se calcula recorriendo
+//! avales desde esas raíces: una entrada solo cuenta si la avala (`endorsements`)
+//! un Maintainer efectivo, y el propio trust.toml debe llevar la firma
+//! (`signature`) de uno. Las claves sin aval, incluidas las de allowed_signers
+//! y el fichero legacy, quedan fuera.
+//!
+//! ```toml
+//! signature = "kid=<kid>;at=<unix>:sig=<hex>"
+//!
+//! [[keys]]
+//! alias = "bob"
+//! public_key = "<hex>"
+//! role = "Contributor"
+//! endorsements = ["kid=<kid de un Maintainer>;at=<unix>:sig=<hex>"]
+//! ```
+
+use crate::crypto::{ssh, DaemonIdentity, PublicKey};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Motivo de revocación que invalida también los commits anteriores a la revocación
+pub const REASON_COMPROMISED: &str = "compromised";
+
+/// Rol que puede avalar claves y firmar trust.toml
+pub const MAINTAINER_ROLE: &str = "Maintainer";
+
+/// Clave de configuración de git con las claves raíz fijadas (multivalor)
+pub const TRUST_ROOT_CONFIG: &str = "cliff-watch.trustRoot";
+
+const ENDORSEMENT_DOMAIN: &[u8] = b"cliff-watch-endorsement/v1\0";
+const DOCUMENT_DOMAIN: &[u8] = b"cliff-watch-trust/v1\0";
+
+/// Contenido de `trust.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustConfig {
+    /// Política de umbral (ver `ThresholdPolicy`); sin ella basta una firma
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<String>,
+    #[serde(default)]
+    pub keys: Vec<TrustedKey>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revoked: Vec<RevokedKey>,
+    /// Firma del documento por un Maintainer (ver `TrustSignature`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Una clave de un alias con su ventana de validez
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub alias: String,
+    pub public_key: String,
+    #[serde(default)]
+    pub role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<DateTime<Utc>>,
+    /// Emails de autor de git por los que puede firmar (vacío: cualquiera)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<String>,
+    /// Avales de Maintainers sobre esta entrada (ver `TrustSignature`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub endorsements: Vec<String>,
+}
+
+/// Entrada de la lista de revocación
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokedKey {
+    pub public_key: String,
+    pub reason: String,
+    pub revoked_at: DateTime<Utc>,
+}
+
+/// Estado de una clave en un instante dado
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyStatus {
+    Active,
+    NotYetValid,
+    Expired,
+    Revoked(String),
+}
+
+impl KeyStatus {
+    /// Código de razón usado por `verify` (None si la clave está activa)
+    pub fn reason_code(&self) -> Option<&'static str> {
+        match self {
+            KeyStatus::Active => None,
+            KeyStatus::NotYetValid => Some("key_not_yet_valid"),
+            KeyStatus::Expired => Some("key_expired"),
+            KeyStatus::Revoked(_) => Some("key_revoked"),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            KeyStatus::Active => "active".to_string(),
+            KeyStatus::NotYetValid => "not yet valid".to_string(),
+            KeyStatus::Expired => "expired".to_string(),
+            KeyStatus::Revoked(reason) => format!("revoked ({})", reason),
+        }
+    }
+}
+
+/// Una cláusula `N of Rol`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdRule {
+    pub count: usize,
+    /// Rol de trust.toml; `any` acepta cualquier rol
+    pub role: String,
+}
+
+/// Política de umbral: basta con que se cumpla una de sus cláusulas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdPolicy {
+    pub rules: Vec<ThresholdRule>,
+}
+
+impl ThresholdPolicy {
+    /// Parsea `"2 of Contributor, or 1 Maintainer"` (el `of` es opcional)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let tokens: Vec<&str> = spec
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let mut rules = Vec::new();
+        for clause in tokens.split(|t| t.eq_ignore_ascii_case("or")) {
+            let (count, role) = match clause {
+                [count, of, role] if of.eq_ignore_ascii_case("of") => (count, role),
+                [count, role] => (count, role),
+                _ => return Err(format!("Invalid threshold clause '{}'", clause.join(" "))),
+            };
+            let count = count
+                .parse::<usize>()
+                .ok()
+                .filter(|c| *c > 0)
+                .ok_or_else(|| format!("Invalid threshold count '{}'", count))?;
+            rules.push(ThresholdRule { count, role: role.to_string() });
+        }
+
+        if rules.is_empty() {
+            return Err("Empty threshold policy".to_string());
+        }
+        Ok(Self { rules })
+    }
+
+    /// Indica si los firmantes `(alias, rol)` cumplen alguna cláusula.
+    ///
+    /// Cada alias cuenta una sola vez aunque firme con varias claves.
+    pub fn is_satisfied<'a>(&self, signers: impl IntoIterator<Item = (&'a str, &'a str)>) -> bool {
+        let signers: Vec<(&str, &str)> = signers.into_iter().collect();
+        self.rules.iter().any(|rule| {
+            let mut aliases: Vec<&str> = signers
+                .iter()
+                .filter(|(_, role)| rule.role.eq_ignore_ascii_case("any") || rule.role.eq_ignore_ascii_case(role))
+                .map(|(alias, _)| *alias)
+                .collect();
+            aliases.sort_unstable();
+            aliases.dedup();
+            aliases.len() >= rule.count
+        })
+    }
+}
+
+/// Firma de un Maintainer sobre una entrada o sobre trust.toml:
+/// `kid=<kid>;at=<unix>:sig=<hex>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustSignature {
+    pub key_id: String,
+    /// Instante de la firma: el firmante debe estar activo entonces
+    pub at: i64,
+    pub signature: Vec<u8>,
+}
+
+impl TrustSignature {
+    pub fn encode(&self) -> String {
+        format!("kid={};at={}:sig={}", self.key_id, self.at, hex::encode(&self.signature))
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (fields, sig) = value.trim().split_once(":sig=").ok_or("Malformed trust signature")?;
+        let mut key_id = None;
+        let mut at = None;
+        for part in fields.split(';') {
+            match part.split_once('=') {
+                Some(("kid", v)) => key_id = Some(v.to_string()),
+                Some(("at", v)) => at = v.parse().ok(),
+                _ => return Err(format!("Malformed trust signature field '{}'", part)),
+            }
+        }
+        Ok(Self {
+            key_id: key_id.ok_or("Trust signature without kid")?,
+            at: at.ok_or("Trust signature without timestamp")?,
+            signature: hex::decode(sig).map_err(|_| "Invalid trust signature encoding".to_string())?,
+        })
+    }
+
+    fn sign(identity: &DaemonIdentity, at: i64, message: &[u8]) -> Result<Self, String> {
+        Ok(Self { key_id: identity.public_key().key_id(), at, signature: identity.sign(message)? })
+    }
+}
+
+impl TrustedKey {
+    /// Identificador corto de la clave (vacío si el hex es inválido)
+    pub fn key_id(&self) -> String {
+        PublicKey::from_hex(&self.public_key).map(|k| k.key_id()).unwrap_or_default()
+    }
+
+    /// ¿Puede esta clave firmar commits del autor `email`?
+    pub fn may_sign_for(&self, email: &str) -> bool {
+        self.emails.is_empty() || self.emails.iter().any(|e| e.eq_ignore_ascii_case(email))
+    }
+
+    /// Lo que cubre un aval: alias, clave, rol, emails y ventana de validez
+    fn endorsement_message(&self, at: i64) -> Vec<u8> {
+        let time = |t: Option<DateTime<Utc>>| t.map(|t| t.timestamp().to_string()).unwrap_or_else(|| "-".to_string());
+        let public_key = PublicKey::from_hex(&self.public_key).map(|k| k.to_hex()).unwrap_or_else(|_| self.public_key.to_lowercase());
+        let mut message = ENDORSEMENT_DOMAIN.to_vec();
+        message.extend(
+            format!(
+                "alias={}\npublic_key={}\nrole={}\nemails={}\nnot_before={}\nnot_after={}\nat={}\n",
+                self.alias,
+                public_key,
+                self.role,
+                self.emails.join(","),
+                time(self.not_before),
+                time(self.not_after),
+                at
+            )
+            .as_bytes(),
+        );
+        message
+    }
+}
+
+/// Aval de la identidad del daemon sobre una entrada de trust.toml (`Request::Endorse`)
+pub fn endorse(key: &TrustedKey, identity: &DaemonIdentity, at: i64) -> Result<String, String> {
+    TrustSignature::sign(identity, at, &key.endorsement_message(at)).map(|s| s.encode())
+}
+
+impl TrustConfig {
+    /// Lee un `trust.toml` (vacío si no existe)
+    pub fn read(path: &std::path::Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(content).map_err(|e| format!("Invalid trust.toml: {}", e))?;
+        config.threshold_policy().map_err(|e| format!("Invalid trust.toml: {}", e))?;
+        Ok(config)
+    }
+
+    /// Política de umbral declarada, si la hay
+    pub fn threshold_policy(&self) -> Result<Option<ThresholdPolicy>, String> {
+        self.threshold.as_deref().map(ThresholdPolicy::parse).transpose()
+    }
+
+    pub fn write(&self, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, self.to_toml()?).map_err(|e| e.to_string())
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// Revocación vigente de una clave, si existe
+    pub fn revocation(&self, key: &TrustedKey) -> Option<&RevokedKey> {
+        let kid = key.key_id();
+        self.revoked.iter().find(|r| {
+            PublicKey::from_hex(&r.public_key).map(|k| k.key_id() == kid).unwrap_or(false)
+        })
+    }
+
+    /// Estado de `key` en el instante `at` (UNIX epoch, segundos)
+    pub fn status_at(&self, key: &TrustedKey, at: i64) -> KeyStatus {
+        if let Some(revocation) = self.revocation(key) {
+            if revocation.reason == REASON_COMPROMISED || at >= revocation.revoked_at.timestamp() {
+                return KeyStatus::Revoked(revocation.reason.clone());
+            }
+        }
+        if key.not_before.is_some_and(|nb| at < nb.timestamp()) {
+            return KeyStatus::NotYetValid;
+        }
+        if key.not_after.is_some_and(|na| at >= na.timestamp()) {
+            return KeyStatus::Expired;
+        }
+        KeyStatus::Active
+    }
+
+    /// Claves cuyo identificador coincide con `key_id`
+    pub fn keys_with_id<'a>(&'a self, key_id: &'a str) -> impl Iterator<Item = &'a TrustedKey> + 'a {
+        self.keys.iter().filter(move |k| k.key_id() == key_id)
+    }
+
+    /// Busca una clave por hex completo o por su identificador corto
+    pub fn find_key(&self, key: &str) -> Option<&TrustedKey> {
+        let wanted = PublicKey::from_hex(key).map(|k| k.key_id()).unwrap_or_else(|_| key.to_lowercase());
+        self.keys.iter().find(|k| k.key_id() == wanted)
+    }
+
+    /// Añade una clave a un alias (varias claves por alias están permitidas)
+    pub fn add_key(&mut self, alias: &str, key_hex: &str, not_before: Option<DateTime<Utc>>) -> Result<(), String> {
+        // Normaliza (Ed25519 crudo o P-256 SEC1 comprimido) y rechaza claves inválidas
+        let public_key = PublicKey::from_hex(key_hex)?;
+        if let Some(existing) = self.keys.iter().find(|k| k.key_id() == public_key.key_id()) {
+            return Err(format!("Key {} already registered for alias '{}'", public_key.key_id(), existing.alias));
+        }
+
+        // Las claves nuevas de un alias heredan su rol y sus emails
+        let existing = self.keys.iter().find(|k| k.alias == alias);
+        let role = existing.map(|k| k.role.clone()).unwrap_or_else(|| "Contributor".to_string());
+        let emails = existing.map(|k| k.emails.clone()).unwrap_or_default();
+
+        self.keys.push(TrustedKey {
+            alias: alias.to_string(),
+            public_key: public_key.to_hex(),
+            role,
+            not_before,
+            not_after: None,
+            emails,
+            endorsements: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Fija los emails de autor de todas las claves de un alias; devuelve cuántas cambió
+    pub fn set_emails(&mut self, alias: &str, emails: &[String]) -> Result<usize, String> {
+        if let Some(invalid) = emails.iter().find(|e| !e.contains('@')) {
+            return Err(format!("'{}' is not an email address", invalid));
+        }
+        let mut changed = 0;
+        for key in self.keys.iter_mut().filter(|k| k.alias == alias) {
+            key.emails = emails.to_vec();
+            changed += 1;
+        }
+        if changed == 0 {
+            return Err(format!("Alias '{}' not found in trust.toml", alias));
+        }
+        Ok(changed)
+    }
+
+    /// Rota la clave de un alias: la nueva vale desde `now` y las claves
+    /// vigentes del alias expiran tras `overlap` (periodo de convivencia).
+    pub fn rotate(&mut self, alias: &str, key_hex: &str, now: DateTime<Utc>, overlap: chrono::Duration) -> Result<Vec<String>, String> {
+        if !self.keys.iter().any(|k| k.alias == alias) {
+            return Err(format!("Alias '{}' not found in trust.toml", alias));
+        }
+
+        let retire_at = now + overlap;
+        let statuses: Vec<KeyStatus> = self.keys.iter().map(|k| self.status_at(k, now.timestamp())).collect();
+        self.add_key(alias, key_hex, Some(now))?;
+
+        let mut retired = Vec::new();
+        for (key, status) in self.keys.iter_mut().zip(statuses) {
+            if key.alias == alias && status == KeyStatus::Active && key.not_after.is_none_or(|na| na > retire_at) {
+                key.not_after = Some(retire_at);
+                retired.push(key.key_id());
+            }
+        }
+        Ok(retired)
+    }
+
+    /// Importa las entradas de un `allowed_signers` aptas para el espacio `git`.
+    ///
+    /// El alias es el primer principal y los principals con `@` pasan a `emails`.
+    /// Las claves ya registradas se respetan tal cual; devuelve los key ids añadidos.
+    pub fn import_allowed_signers(&mut self, content: &str) -> Result<Vec<String>, String> {
+        let mut added = Vec::new();
+        for signer in ssh::parse_allowed_signers(content)? {
+            let kid = signer.public_key.key_id();
+            if !signer.allows_namespace("git") || self.keys.iter().any(|k| k.key_id() == kid) {
+                continue;
+            }
+            let alias = signer.principals.first().cloned().unwrap_or_default();
+            self.add_key(&alias, &signer.public_key.to_hex(), signer.valid_after)?;
+            if let Some(key) = self.keys.last_mut() {
+                key.not_after = signer.valid_before;
+                key.emails = signer.principals.iter().filter(|p| p.contains('@')).cloned().collect();
+            }
+            added.push(kid);
+        }
+        Ok(added)
+    }
+
+    /// Exporta las claves no revocadas en formato `allowed_signers`
+    pub fn to_allowed_signers(&self) -> String {
+        self.keys
+            .iter()
+            .filter(|k| self.revocation(k).is_none())
+            .filter_map(|k| {
+                let public_key = PublicKey::from_hex(&k.public_key).ok()?;
+                let principals = std::iter::once(k.alias.clone()).chain(k.emails.iter().filter(|e| **e != k.alias).cloned()).collect();
+                Some(ssh::format_allowed_signer(&ssh::AllowedSigner {
+                    principals,
+                    public_key,
+                    namespaces: None,
+                    valid_after: k.not_before,
+                    valid_before: k.not_after,
+                }))
+            })
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    /// Firma del documento completo con la identidad del daemon (`Request::SignTrust`)
+    pub fn sign_document(&self, identity: &DaemonIdentity, at: i64) -> Result<String, String> {
+        TrustSignature::sign(identity, at, &self.document_message(at)?).map(|s| s.encode())
+    }
+
+    /// Contenido firmado del documento: todo salvo la propia firma
+    fn document_message(&self, at: i64) -> Result<Vec<u8>, String> {
+        let unsigned = Self { signature: None, ..self.clone() };
+        let body = toml::to_string(&unsigned).map_err(|e| e.to_string())?;
+        let mut message = DOCUMENT_DOMAIN.to_vec();
+        message.extend(format!("at={}\n{}", at, body).as_bytes());
+        Ok(message)
+    }
+
+    /// Maintainer efectivo, activo en `signature.at`, cuya firma sobre `message` es válida
+    fn maintainer_signer<'a>(&self, effective: &'a [TrustedKey], signature: &TrustSignature, message: &[u8]) -> Option<&'a TrustedKey> {
+        effective.iter().find(|k| {
+            k.key_id() == signature.key_id
+                && k.role.eq_ignore_ascii_case(MAINTAINER_ROLE)
+                && self.status_at(k, signature.at) == KeyStatus::Active
+                && PublicKey::from_hex(&k.public_key).is_ok_and(|pk| pk.verify(message, &signature.signature))
+        })
+    }
+
+    /// Conjunto efectivo: las raíces (como Maintainers) más las entradas avaladas,
+    /// transitivamente, por Maintainers efectivos
+    pub fn effective_keys(&self, roots: &[PublicKey]) -> Vec<TrustedKey> {
+        let mut effective: Vec<TrustedKey> = roots
+            .iter()
+            .map(|root| {
+                let entry = self.keys.iter().find(|k| k.key_id() == root.key_id()).cloned().unwrap_or_else(|| TrustedKey {
+                    alias: format!("root-{}", root.key_id()),
+                    public_key: root.to_hex(),
+                    role: String::new(),
+                    not_before: None,
+                    not_after: None,
+                    emails: Vec::new(),
+                    endorsements: Vec::new(),
+                });
+                TrustedKey { role: MAINTAINER_ROLE.to_string(), ..entry }
+            })
+            .collect();
+
+        loop {
+            let admitted: Vec<TrustedKey> = self
+                .keys
+                .iter()
+                .filter(|k| !effective.iter().any(|e| e.key_id() == k.key_id()))
+                .filter(|k| {
+                    k.endorsements
+                        .iter()
+                        .filter_map(|e| TrustSignature::parse(e).ok())
+                        .any(|e| self.maintainer_signer(&effective, &e, &k.endorsement_message(e.at)).is_some())
+                })
+                .cloned()
+                .collect();
+            if admitted.is_empty() {
+                return effective;
+            }
+            effective.extend(admitted);
+        }
+    }
+
+    /// Comprueba que trust.toml lo firmó un Maintainer efectivo; devuelve su alias
+    pub fn verify_document(&self, roots: &[PublicKey]) -> Result<String, String> {
+        let signature = self
+            .signature
+            .as_deref()
+            .ok_or("trust.toml is not signed: the web of trust requires a maintainer signature (cliff-watch key sign-trust)")?;
+        let signature = TrustSignature::parse(signature)?;
+        let effective = self.effective_keys(roots);
+        if let Some(signer) = self.maintainer_signer(&effective, &signature, &self.document_message(signature.at)?) {
+            return Ok(signer.alias.clone());
+        }
+        if effective.iter().any(|k| k.key_id() == signature.key_id && k.role.eq_ignore_ascii_case(MAINTAINER_ROLE)) {
+            Err("trust.toml changed after it was signed (cliff-watch key sign-trust)".to_string())
+        } else {
+            Err(format!("trust.toml is signed by {}, which is not an endorsed maintainer", signature.key_id))
+        }
+    }
+
+    /// Revoca una clave (hex o identificador corto) con un motivo
+    pub fn revoke(&mut self, key: &str, reason: &str, at: DateTime<Utc>) -> Result<TrustedKey, String> {
+        let trusted = self.find_key(key).cloned().ok_or_else(|| format!("Key '{}' not found in trust.toml", key))?;
+        if self.revocation(&trusted).is_some() {
+            return Err(format!("Key {} is already revoked", trusted.key_id()));
+        }
+        self.revoked.push(RevokedKey {
+            public_key: trusted.public_key.clone(),
+            reason: reason.to_string(),
+            revoked_at: at,
+        });
+        Ok(trusted)
+    }
+}
+
+/// Nombre de trust.toml en la raíz del repositorio
+const TRUST_TOML: &str = "trust.toml";
+
+/// Ruta de `trust.toml` en el working tree (None en repos bare)
+pub fn trust_toml_path(repo: &git2::Repository) -> Option<PathBuf> {
+    repo.workdir().map(|w| w.join(TRUST_TOML))
+}
+
+/// Contenido del archivo `path` en el árbol de un commit, si lo tiene
+fn file_in(repo: &git2::Repository, commit: &git2::Commit, path: &str) -> Result<Option<String>, String> {
+    let tree = commit.tree().map_err(|e| e.to_string())?;
+    let Ok(entry) = tree.get_path(std::path::Path::new(path)) else {
+        return Ok(None);
+    };
+    let blob = repo.find_blob(entry.id()).map_err(|e| format!("Invalid {} in {}: {}", path, commit.id(), e))?;
+    String::from_utf8(blob.content().to_vec()).map(Some).map_err(|_| format!("{} in {} is not UTF-8", path, commit.id()))
+}
+
+/// Memoria de `file_in_history` para un recorrido de varios commits: si cada
+/// archivo existió en algún commit de la cadena de primeros padres hasta cada
+/// Oid visitado, para no volver a bajar hasta la raíz en cada commit
+#[derive(Debug, Default)]
+pub struct HistoryCache {
+    existed: HashMap<(&'static str, git2::Oid), bool>,
+}
+
+impl HistoryCache {
+    /// ¿Tuvo `path` el commit `start` o algún ancestro suyo por primer padre?
+    fn existed(&mut self, repo: &git2::Repository, start: &git2::Commit, path: &'static str) -> Result<bool, String> {
+        let mut visited = Vec::new();
+        let mut ancestor = Some(start.clone());
+        let existed = loop {
+            let Some(current) = ancestor else {
+                break false;
+            };
+            if let Some(&known) = self.existed.get(&(path, current.id())) {
+                break known;
+            }
+            visited.push(current.id());
+            if file_in(repo, &current, path)?.is_some() {
+                break true;
+            }
+            ancestor = current.parent(0).ok();
+        };
+        self.existed.extend(visited.into_iter().map(|oid| ((path, oid), existed)));
+        Ok(existed)
+    }
+}
+
+/// `path` tal como está en `start`: vacío si `start` lo borró (algún ancestro
+/// por primer padre lo tenía) y None si no existió nunca
+pub(crate) fn file_in_history(
+    repo: &git2::Repository,
+    start: Option<git2::Commit>,
+    path: &'static str,
+    cache: &mut HistoryCache,
+) -> Result<Option<(git2::Oid, String)>, String> {
+    let Some(start) = start else {
+        return Ok(None);
+    };
+    if let Some(content) = file_in(repo, &start, path)? {
+        cache.existed.insert((path, start.id()), true);
+        return Ok(Some((start.id(), content)));
+    }
+    // Borrarlo no puede resucitar una versión anterior: el estado es explícitamente vacío
+    match start.parent(0) {
+        Ok(parent) if cache.existed(repo, &parent, path)? => Ok(Some((start.id(), String::new()))),
+        _ => Ok(None),
+    }
+}
+
+/// `path` con el que se juzga un commit: el de su primer padre (vacío si este
+/// lo borró) o, si ningún ancestro lo tuvo, el del propio commit (TOFU)
+fn file_at(repo: &git2::Repository, commit: &git2::Commit, path: &'static str, cache: &mut HistoryCache) -> Result<Option<(git2::Oid, String)>, String> {
+    match file_in_history(repo, commit.parent(0).ok(), path, cache)? {
+        Some(found) => Ok(Some(found)),
+        None => Ok(file_in(repo, commit, path)?.map(|content| (commit.id(), content))),
+    }
+}
+
+/// trust.toml con el que se juzga un commit: el de su primer padre (vacío, sin
+/// claves, si lo borró) o, si ningún ancestro lo tuvo, el del propio commit (TOFU).
+///
+/// Devuelve el commit de cuyo árbol sale y su contenido.
+pub fn trust_toml_at(repo: &git2::Repository, commit: &git2::Commit, cache: &mut HistoryCache) -> Result<Option<(git2::Oid, String)>, String> {
+    file_at(repo, commit, TRUST_TOML, cache)
+}
+
+/// Claves confiables para verificar `commit` (ver `trust_toml_at`), más
+/// allowed_signers y las claves legacy locales
+pub fn load_trust_at(repo: &git2::Repository, commit: &git2::Commit) -> Result<TrustConfig, String> {
+    load_trust_at_with(repo, commit, &mut HistoryCache::default())
+}
+
+/// `load_trust_at` dentro de un recorrido, compartiendo `cache` entre commits
+pub fn load_trust_at_with(repo: &git2::Repository, commit: &git2::Commit, cache: &mut HistoryCache) -> Result<TrustConfig, String> {
+    let config = match trust_toml_at(repo, commit, cache)? {
+        Some((source, content)) => TrustConfig::parse(&content).map_err(|e| format!("{} (from {})", e, source))?,
+        None => TrustConfig::default(),
+    };
+    complete_trust(repo, config)
+}
+
+/// Claves confiables para verificar la revisión `rev` (ver `load_trust_at`).
+///
+/// Sin commits todavía (HEAD no nacido) se usa `load_trust`.
+pub fn load_trust_at_rev(repo: &git2::Repository, rev: &str) -> Result<TrustConfig, String> {
+    match repo.revparse_single(rev).and_then(|o| o.peel_to_commit()) {
+        Ok(commit) => load_trust_at(repo, &commit),
+        Err(_) if repo.head().is_err() => load_trust(repo),
+        Err(e) => Err(format!("Unknown revision '{}': {}", rev, e)),
+    }
+}
+
+/// Ruta de `gpg.ssh.allowedSignersFile`, si está configurada
+pub fn allowed_signers_path(repo: &git2::Repository) -> Option<PathBuf> {
+    let value = repo.config().ok()?.get_string("gpg.ssh.allowedSignersFile").ok()?;
+    let path = crate::crypto::expand_home(&value);
+    match (path.is_relative(), repo.workdir()) {
+        (true, Some(workdir)) => Some(workdir.join(path)),
+        _ => Some(path),
+    }
+}
+
+/// Claves raíz fijadas en este clon (`cliff-watch.trustRoot`)
+pub fn pinned_roots(repo: &git2::Repository) -> Result<Vec<PublicKey>, String> {
+    let config = repo.config().map_err(|e| e.to_string())?;
+    let mut roots = Vec::new();
+    if let Ok(mut entries) = config.multivar(TRUST_ROOT_CONFIG, None) {
+        while let Some(entry) = entries.next() {
+            let value = entry.map_err(|e| e.to_string())?.value().unwrap_or_default().to_string();
+            roots.push(PublicKey::from_hex(&value).map_err(|e| format!("Invalid {} '{}': {}", TRUST_ROOT_CONFIG, value, e))?);
+        }
+    }
+    Ok(roots)
+}
+
+/// Fija una clave raíz en la configuración local de git
+pub fn pin_root(repo: &git2::Repository, key_hex: &str) -> Result<PublicKey, String> {
+    let key = PublicKey::from_hex(key_hex)?;
+    let mut config = repo.config().map_err(|e| e.to_string())?;
+    config
+        .set_multivar(TRUST_ROOT_CONFIG, &format!("^{}$", key.to_hex()), &key.to_hex())
+        .map_err(|e| format!("Failed to set {}: {}", TRUST_ROOT_CONFIG, e))?;
+    Ok(key)
+}
+
+/// Carga el conjunto de claves confiables (trust.toml + allowed_signers + legacy `.git/cliff-watch/trusted_keys`).
+///
+/// Con raíces fijadas, trust.toml debe estar firmado y solo cuentan las claves avaladas.
+/// En repos bare se usa el trust.toml de HEAD.
+pub fn load_trust(repo: &git2::Repository) -> Result<TrustConfig, String> {
+    let config = match trust_toml_path(repo) {
+        Some(path) => TrustConfig::read(&path)?,
+        None => match repo.head().and_then(|h| h.peel_to_commit()) {
+            Ok(head) => file_in(repo, &head, TRUST_TOML)?.map(|c| TrustConfig::parse(&c)).transpose()?.unwrap_or_default(),
+            Err(_) => TrustConfig::default(),
+        },
+    };
+    complete_trust(repo, config)
+}
+
+/// Aplica la red de confianza y suma allowed_signers y el fichero legacy
+fn complete_trust(repo: &git2::Repository, mut config: TrustConfig) -> Result<TrustConfig, String> {
+    let roots = pinned_roots(repo)?;
+    if !roots.is_empty() {
+        config.verify_document(&roots)?;
+    }
+
+    // allowed_signers de git (`gpg.ssh.allowedSignersFile`)
+    if let Some(path) = allowed_signers_path(repo) {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            config
+                .import_allowed_signers(&content)
+                .map_err(|e| format!("Invalid allowed signers file {}: {}", path.display(), e))?;
+        }
+    }
+
+    // Legacy: `alias:hex` por línea, sin ventana de validez (Local override)
+    let legacy_file = repo.path().join("cliff-watch").join("trusted_keys");
+    if legacy_file.exists() {
+        if let Ok(content) = std::fs::read_to_string(legacy_file) {
+            for line in content.lines() {
+                if let Some((alias, key)) = line.split_once(':') {
+                    config.keys.push(TrustedKey {
+                        alias: alias.to_string(),
+                        public_key: key.to_string(),
+                        role: String::new(),
+                        not_before: None,
+                        not_after: None,
+                        emails: Vec::new(),
+                        endorsements: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+
+    if !roots.is_empty() {
+        config.keys = config.effective_keys(&roots);
+    }
+    Ok(config)
+}
+
+/// Registra una clave pública en el repositorio (preferentemente en trust.toml)
+pub fn register_public_key(repo: &git2::Repository, key_hex: &str, alias: &str, emails: &[String]) -> Result<(), String> {
+    // 1. Try to use trust.toml first (Distributed Trust)
+    if let Some(path) = trust_toml_path(repo) {
+        let mut config = TrustConfig::read(&path)?;
+        config.add_key(alias, key_hex, None)?;
+        if !emails.is_empty() {
+            config.set_emails(alias, emails)?;
+        }
+        return config.write(&path);
+    }
+    if !emails.is_empty() {
+        return Err("Author emails require trust.toml (not available in bare repositories)".to_string());
+    }
+
+    // 2. Fallback to local .git/cliff-watch/trusted_keys (Legacy)
+    let key_hex = PublicKey::from_hex(key_hex)?.to_hex();
+    let gov_dir = repo.path().join("cliff-watch");
+    if !gov_dir.exists() {
+        std::fs::create_dir_all(&gov_dir).map_err(|e| e.to_string())?;
+    }
+
+    let keys_file = gov_dir.join("trusted_keys");
+    let entry = format!("{}:{}\n", alias, key_hex);
+
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(keys_file)
+        .map_err(|e| e.to_string())?;
+
+    file.write_all(entry.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_keypair;
+
+    fn new_key_hex() -> String {
+        hex::encode(generate_keypair().1.as_bytes())
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn rotation_keeps_history_valid() {
+        let mut config = TrustConfig::default();
+        let old = new_key_hex();
+        let new = new_key_hex();
+        config.add_key("alice", &old, None).unwrap();
+
+        let now = at("2026-05-01T00:00:00Z");
+        let retired = config.rotate("alice", &new, now, chrono::Duration::days(7)).unwrap();
+        assert_eq!(retired.len(), 1);
+
+        let old_key = config.find_key(&old).unwrap().clone();
+        let new_key = config.find_key(&new).unwrap().clone();
+
+        // Antes de rotar: solo la clave vieja
+        let before = at("2026-04-01T00:00:00Z").timestamp();
+        assert_eq!(config.status_at(&old_key, before), KeyStatus::Active);
+        assert_eq!(config.status_at(&new_key, before), KeyStatus::NotYetValid);
+
+        // Durante el solape: ambas activas para el mismo alias
+        let overlap = at("2026-05-03T00:00:00Z").timestamp();
+        assert_eq!(config.status_at(&old_key, overlap), KeyStatus::Active);
+        assert_eq!(config.status_at(&new_key, overlap), KeyStatus::Active);
+
+        // Tras el solape: la vieja expira
+        let after = at("2026-06-01T00:00:00Z").timestamp();
+        assert_eq!(config.status_at(&old_key, after), KeyStatus::Expired);
+        assert_eq!(config.status_at(&new_key, after), KeyStatus::Active);
+    }
+
+    #[test]
+    fn revocation_is_retroactive_only_when_compromised() {
+        let mut config = TrustConfig::default();
+        let retired = new_key_hex();
+        let stolen = new_key_hex();
+        config.add_key("bob", &retired, None).unwrap();
+        config.add_key("bob", &stolen, None).unwrap();
+        assert!(config.add_key("carol", &stolen, None).is_err(), "A key belongs to one alias only");
+
+        let revoked_at = at("2026-05-01T00:00:00Z");
+        let retired_id = config.find_key(&retired).unwrap().key_id();
+        config.revoke(&retired_id, "superseded", revoked_at).unwrap();
+        config.revoke(&stolen, REASON_COMPROMISED, revoked_at).unwrap();
+        assert!(config.revoke(&stolen, REASON_COMPROMISED, revoked_at).is_err());
+
+        let before = at("2026-04-01T00:00:00Z").timestamp();
+        let after = at("2026-06-01T00:00:00Z").timestamp();
+        let retired_key = config.find_key(&retired).unwrap();
+        let stolen_key = config.find_key(&stolen).unwrap();
+
+        assert_eq!(config.status_at(retired_key, before), KeyStatus::Active);
+        assert_eq!(config.status_at(retired_key, after), KeyStatus::Revoked("superseded".to_string()));
+        assert_eq!(config.status_at(stolen_key, before), KeyStatus::Revoked(REASON_COMPROMISED.to_string()));
+    }
+
+    #[test]
+    fn threshold_policy_counts_distinct_aliases_by_role() {
+        let policy = ThresholdPolicy::parse("2 of Contributor, or 1 Maintainer").unwrap();
+        assert_eq!(policy.rules.len(), 2);
+        assert_eq!(ThresholdPolicy::parse("2 any").unwrap().rules[0].role, "any");
+        assert!(ThresholdPolicy::parse("two of Contributor").is_err());
+        assert!(ThresholdPolicy::parse("0 of Contributor").is_err());
+        assert!(ThresholdPolicy::parse("").is_err());
+
+        assert!(!policy.is_satisfied([("alice", "Contributor")]));
+        assert!(!policy.is_satisfied([("alice", "Contributor"), ("alice", "Contributor")]), "Two keys, one human");
+        assert!(policy.is_satisfied([("alice", "Contributor"), ("bob", "contributor")]));
+        assert!(policy.is_satisfied([("alice", "Contributor"), ("carol", "Maintainer")]));
+    }
+
+    #[test]
+    fn allowed_signers_import_and_export() {
+        let mut config = TrustConfig::default();
+        let alice = new_key_hex();
+        config.add_key("alice@example.com", &alice, Some(at("2026-01-01T00:00:00Z"))).unwrap();
+        config.keys[0].not_after = Some(at("2027-01-01T00:00:00Z"));
+        let revoked = new_key_hex();
+        config.add_key("mallory@example.com", &revoked, None).unwrap();
+        config.revoke(&revoked, REASON_COMPROMISED, at("2026-02-01T00:00:00Z")).unwrap();
+
+        let exported = config.to_allowed_signers();
+        assert_eq!(exported.lines().count(), 1, "Revoked keys are not exported");
+        assert!(exported.starts_with("alice@example.com valid-after=\"20260101000000Z\""));
+
+        let mut imported = TrustConfig::default();
+        let added = imported.import_allowed_signers(&exported).unwrap();
+        assert_eq!(added, vec![config.keys[0].key_id()]);
+        assert_eq!(imported.keys[0].alias, "alice@example.com");
+        assert_eq!(imported.keys[0].not_after, config.keys[0].not_after);
+        assert!(imported.import_allowed_signers(&exported).unwrap().is_empty(), "Import is idempotent");
+    }
+
+    #[test]
+    fn legacy_trust_toml_still_parses() {
+        let legacy = "[[keys]]\nalias = \"dev\"\npublic_key = \"00\"\nrole = \"Contributor\"\n";
+        let config: TrustConfig = toml::from_str(legacy).unwrap();
+        assert_eq!(config.keys.len(), 1);
+        assert!(config.revoked.is_empty());
+        assert!(config.keys[0].not_before.is_none());
+    }
+
+    #[test]
+    fn web_of_trust_walks_endorsements_from_pinned_roots() {
+        let identity = || DaemonIdentity::Software(generate_keypair().0);
+        let (root, maintainer, contributor) = (identity(), identity(), identity());
+        let entry = |alias: &str, id: &DaemonIdentity, role: &str| TrustedKey {
+            alias: alias.to_string(),
+            public_key: id.public_key().to_hex(),
+            role: role.to_string(),
+            not_before: None,
+            not_after: None,
+            emails: Vec::new(),
+            endorsements: Vec::new(),
+        };
+
+        let mut m2 = entry("m2", &maintainer, MAINTAINER_ROLE);
+        m2.endorsements.push(endorse(&m2, &root, 100).unwrap());
+        let mut c = entry("c", &contributor, "Contributor");
+        c.endorsements.push(endorse(&c, &maintainer, 200).unwrap());
+        // Aval de un Contributor: no cuenta
+        let mut d = entry("d", &identity(), "Contributor");
+        d.endorsements.push(endorse(&d, &contributor, 300).unwrap());
+
+        let mut config = TrustConfig { keys: vec![c, d, m2], ..Default::default() };
+        let roots = [root.public_key()];
+        let aliases = |c: &TrustConfig| c.effective_keys(&roots).into_iter().map(|k| k.alias).collect::<Vec<_>>();
+        assert_eq!(aliases(&config), vec![format!("root-{}", root.public_key().key_id()), "m2".to_string(), "c".to_string()]);
+
+        // El documento debe firmarlo un Maintainer efectivo y no cambiar después
+        assert!(config.verify_document(&roots).is_err());
+        config.signature = Some(config.sign_document(&contributor, 400).unwrap());
+        assert!(config.verify_document(&roots).unwrap_err().contains("not an endorsed maintainer"));
+        config.signature = Some(config.sign_document(&maintainer, 400).unwrap());
+        assert_eq!(config.verify_document(&roots).unwrap(), "m2");
+
+        // Ascender a un Contributor invalida su aval y la firma del documento
+        config.keys[0].role = MAINTAINER_ROLE.to_string();
+        assert!(!aliases(&config).contains(&"c".to_string()));
+        assert!(config.verify_document(&roots).unwrap_err().contains("changed after it was signed"));
+    }
+
+    #[test]
+    fn commits_are_judged_by_the_trust_toml_of_their_parent() {
+        let dir = crate::testing::TempDir::new("cliff_watch_trust");
+        let repo = git2::Repository::init_bare(&dir).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        let (alice, bob) = (new_key_hex(), new_key_hex());
+        let trust_with = |keys: &[&str]| {
+            let mut config = TrustConfig::default();
+            for (i, key) in keys.iter().enumerate() {
+                config.add_key(&format!("k{}", i), key, None).unwrap();
+            }
+            config.to_toml().unwrap()
+        };
+
+        // Sin trust.toml → lo introduce (alice) → añade a bob → lo borra → sigue sin él
+        let mut parent: Option<git2::Oid> = None;
+        let mut commits = Vec::new();
+        for content in [None, Some(trust_with(&[&alice])), Some(trust_with(&[&alice, &bob])), None, None] {
+            let mut builder = repo.treebuilder(None).unwrap();
+            builder.insert("f", repo.blob(b"x").unwrap(), 0o100644).unwrap();
+            if let Some(content) = content {
+                builder.insert(TRUST_TOML, repo.blob(content.as_bytes()).unwrap(), 0o100644).unwrap();
+            }
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let parents: Vec<git2::Commit> = parent.map(|p| repo.find_commit(p).unwrap()).into_iter().collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            parent = Some(repo.commit(Some("HEAD"), &sig, &sig, "c", &tree, &parents).unwrap());
+            commits.push(repo.find_commit(parent.unwrap()).unwrap());
+        }
+
+        let keys = |i: usize| load_trust_at(&repo, &commits[i]).unwrap().keys.into_iter().map(|k| k.public_key).collect::<Vec<_>>();
+        assert!(keys(0).is_empty());
+        assert_eq!(keys(1), vec![alice.clone()]); // TOFU: su propio árbol
+        assert_eq!(keys(2), vec![alice.clone()]); // bob no se admite a sí mismo
+        assert_eq!(keys(3), vec![alice.clone(), bob.clone()]);
+        let fresh = |commit: &git2::Commit| trust_toml_at(&repo, commit, &mut HistoryCache::default()).unwrap();
+        assert_eq!(fresh(&commits[3]).unwrap().0, commits[2].id());
+        // Borrarlo revoca: el commit siguiente no hereda el trust.toml de commits[2]
+        assert!(keys(4).is_empty());
+        assert_eq!(fresh(&commits[4]).unwrap(), (commits[3].id(), String::new()));
+        // Una caché compartida por el recorrido (en cualquier orden) da lo mismo que una por commit
+        for order in [commits.iter().collect::<Vec<_>>(), commits.iter().rev().collect()] {
+            let mut cache = HistoryCache::default();
+            for commit in order {
+                assert_eq!(trust_toml_at(&repo, commit, &mut cache).unwrap(), fresh(commit));
+            }
+        }
+        // Repo bare: load_trust lee el trust.toml de HEAD
+        assert!(trust_toml_path(&repo).is_none());
+        assert!(load_trust(&repo).unwrap().keys.is_empty());
+    }
+}
@@ -1,20 +1,72 @@
-use std::collections::VecDeque;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::net::UnixListener;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_util::sync::CancellationToken;
-use tracing::{info, error};
+use tracing::{info, warn, error};
 use anyhow::Result;
 use std::path::Path;
 use std::fs;
 
 use cliff_watch_core::protocol::{Request, Response};
 use cliff_watch_core::mouse_sentinel::KinematicMetrics;
-use cliff_watch_core::monitor::AttentionBattery;
+use cliff_watch_core::monitor::{AttentionBattery, MonitorStatsHandle};
 use cliff_watch_core::stats::calculate_human_score;
 use cliff_watch_core::focus_session::FocusTracker;
-use cliff_watch_core::git::WitnessData;
-use cliff_watch_core::crypto::zkp::HumanityProof;
+use cliff_watch_core::crypto::{DaemonIdentity, PublicKey};
+use cliff_watch_core::crypto::zkp::{HumanityProof, HUMANITY_THRESHOLD_PERCENT};
+use cliff_watch_core::pow;
+use cliff_watch_core::report::{self, AttentionMetrics, ProofOfWork};
+use cliff_watch_core::report::builder::SentinelBuilder;
+use cliff_watch_core::report::collector::{collect_metrics, CollectionInput, ObservationCursor};
+use cliff_watch_core::report::finalize::finalize_report;
+use cliff_watch_core::ticket::{Cosignature, PowStamp, SignedTicket, TicketBinding, TicketMode, TicketPayload};
+use cliff_watch_core::translog::TransparencyLog;
+use cliff_watch_core::chain::ChainState;
+use cliff_watch_core::certificate::OpeningStore;
+use cliff_watch_core::credential::VerifiedCredential;
+use cliff_watch_core::trust::{TrustConfig, MAINTAINER_ROLE};
+
+/// Tiempo que un reto de prueba de trabajo puede tardar en canjearse
+const POW_CHALLENGE_TTL: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Tamaño máximo de una petición (trust.toml completo, credenciales con avales)
+const MAX_REQUEST_BYTES: u64 = 1024 * 1024;
+
+/// Energía que cuesta una firma de gobernanza (aval, firma de trust.toml, co-firma)
+const GOVERNANCE_COST: f64 = 20.0;
+
+/// Tiempo para que el cliente envíe la petición completa
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Lee una petición hasta que el cliente cierra su mitad de escritura
+async fn read_request<R: tokio::io::AsyncRead + Unpin>(reader: R) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    reader.take(MAX_REQUEST_BYTES + 1).read_to_end(&mut buffer).await?;
+    if buffer.len() as u64 > MAX_REQUEST_BYTES {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("request over {} bytes", MAX_REQUEST_BYTES)));
+    }
+    Ok(buffer)
+}
+
+/// Registros locales que acompañan a cada ticket emitido
+#[derive(Clone, Default)]
+struct TicketRecords {
+    /// Contador y hash del último ticket de esta identidad
+    chain: Option<Arc<Mutex<ChainState>>>,
+    /// Log de transparencia donde se anota cada ticket emitido
+    log: Option<Arc<Mutex<TransparencyLog>>>,
+    /// Aperturas de las pruebas de rango, para certificados de rama
+    openings: Option<Arc<OpeningStore>>,
+}
+
+/// Reto emitido y pendiente de canje, indexado por su sal
+struct PendingPow {
+    binding: TicketBinding,
+    cost: f64,
+    difficulty: u8,
+    issued_at: std::time::Instant,
+}
 
 pub struct IpcServer {
     socket_path: String,
@@ -27,9 +79,23 @@ pub struct IpcServer {
     ncd_ref: Arc<RwLock<f64>>,
     shutdown: CancellationToken,
     start_time: std::time::Instant,
-    signing_key: Arc<cliff_watch_core::crypto::SigningKey>,
+    identity: Arc<DaemonIdentity>,
+    key_id: String,
     min_entropy: f64,
     audit_mode: bool,
+    monitor_stats: MonitorStatsHandle,
+    workspace_id: String,
+    /// Inicio de la ventana del próximo Sentinel Report
+    report_cursor: Arc<Mutex<ObservationCursor>>,
+    pow_fallback: bool,
+    /// Credencial de reputación de esta identidad
+    reputation: Option<Arc<VerifiedCredential>>,
+    pending_pow: Arc<Mutex<HashMap<String, PendingPow>>>,
+    /// Prueba de trabajo canjeada en la ventana actual (va al sobre del informe)
+    last_pow: Arc<Mutex<Option<ProofOfWork>>>,
+    /// Nonce del último ticket emitido: el informe de la ventana lo firma
+    last_ticket_nonce: Arc<Mutex<Option<String>>>,
+    ticket_records: TicketRecords,
 }
 
 impl IpcServer {
@@ -43,19 +109,20 @@ impl IpcServer {
         score_history_ref: Arc<RwLock<VecDeque<f64>>>,
         ncd_ref: Arc<RwLock<f64>>,
         shutdown: CancellationToken,
-        signing_key: cliff_watch_core::crypto::SigningKey,
+        identity: DaemonIdentity,
         min_entropy: f64,
         audit_mode: bool,
     ) -> Self {
-        let verifying_key = signing_key.verifying_key();
-        
-        let pubkey_bytes = verifying_key.as_bytes();
-        let mut pubkey_hex = String::with_capacity(pubkey_bytes.len() * 2);
-        for byte in pubkey_bytes {
-            pubkey_hex.push_str(&format!("{:02x}", byte));
-        }
-        
-        info!("Daemon started with Public Key: {}", pubkey_hex);
+        let public_key = identity.public_key();
+        let key_id = public_key.key_id();
+        info!(
+            "Daemon started with Public Key: {} (kid {}, {} via {})",
+            public_key.to_hex(),
+            key_id,
+            public_key.scheme(),
+            identity.backend()
+        );
+        info!("Daemon DID: {}", cliff_watch_core::crypto::did::to_did_key(&public_key));
 
         Self {
             socket_path,
@@ -68,12 +135,71 @@ impl IpcServer {
             ncd_ref,
             shutdown,
             start_time: std::time::Instant::now(),
-            signing_key: Arc::new(signing_key),
+            identity: Arc::new(identity),
+            key_id,
             min_entropy,
             audit_mode,
+            monitor_stats: MonitorStatsHandle::default(),
+            workspace_id: report::workspace_id(&std::env::current_dir().unwrap_or_else(|_| ".".into())),
+            report_cursor: Arc::new(Mutex::new(ObservationCursor::new(
+                report::now_us(),
+                0,
+                Default::default(),
+            ))),
+            pow_fallback: false,
+            pending_pow: Arc::new(Mutex::new(HashMap::new())),
+            last_pow: Arc::new(Mutex::new(None)),
+            last_ticket_nonce: Arc::new(Mutex::new(None)),
+            ticket_records: TicketRecords::default(),
+            reputation: None,
         }
     }
 
+    /// Ofrece un reto de prueba de trabajo cuando la batería no cubre el costo
+    pub fn with_pow_fallback(mut self, enabled: bool) -> Self {
+        self.pow_fallback = enabled;
+        self
+    }
+
+    /// Credencial de reputación ya verificada (ver `credential::verify_reputation`):
+    /// sus commits `valid` abaratan la prueba de trabajo
+    pub fn with_reputation(mut self, credential: VerifiedCredential) -> Self {
+        info!("Proof of work relaxed for {} valid commits vouched by {}", credential.summary.valid_commits, credential.issuer.key_id());
+        self.reputation = Some(Arc::new(credential));
+        self
+    }
+
+    /// Anota cada ticket (incluidos los de Audit Mode) en el log de transparencia
+    pub fn with_transparency_log(mut self, log: TransparencyLog) -> Self {
+        info!("Transparency log at {} ({} entries)", log.dir().display(), log.size());
+        self.ticket_records.log = Some(Arc::new(Mutex::new(log)));
+        self
+    }
+
+    /// Numera y encadena los tickets de esta identidad (`seq` + `prev`)
+    pub fn with_ticket_chain(mut self, chain: ChainState) -> Self {
+        info!("Ticket chain resumes at #{}", chain.seq + 1);
+        self.ticket_records.chain = Some(Arc::new(Mutex::new(chain)));
+        self
+    }
+
+    /// Guarda la apertura de cada prueba de rango (ver `cliff-watch certify`)
+    pub fn with_opening_store(mut self, openings: OpeningStore) -> Self {
+        self.ticket_records.openings = Some(Arc::new(openings));
+        self
+    }
+
+    /// Conecta los contadores vivos del `FileMonitor` a los Sentinel Reports
+    pub fn with_monitor_stats(mut self, monitor_stats: MonitorStatsHandle) -> Self {
+        self.report_cursor = Arc::new(Mutex::new(ObservationCursor::new(
+            report::now_us(),
+            self.events_captured.read().map(|g| *g as u64).unwrap_or(0),
+            monitor_stats.snapshot(),
+        )));
+        self.monitor_stats = monitor_stats;
+        self
+    }
+
     pub async fn start(self) -> Result<()> {
         if Path::new(&self.socket_path).exists() {
             let _ = fs::remove_file(&self.socket_path);
@@ -99,18 +225,40 @@ impl IpcServer {
                             let score_history_lock = self.score_history_ref.clone();
                             let ncd_lock = self.ncd_ref.clone();
                             let start_time = self.start_time;
-                            let signing_key_lock = self.signing_key.clone();
+                            let identity = self.identity.clone();
+                            let key_id = self.key_id.clone();
                             let difficulty_factor = self.min_entropy / 2.5;
+                            let monitor_stats = self.monitor_stats.clone();
+                            let workspace_id = self.workspace_id.clone();
+                            let report_cursor = self.report_cursor.clone();
+                            let pow_fallback = self.pow_fallback;
+                            let pending_pow_lock = self.pending_pow.clone();
+                            let last_pow_lock = self.last_pow.clone();
+                            let last_nonce_lock = self.last_ticket_nonce.clone();
+                            let ticket_records = self.ticket_records.clone();
+                            let reputation = self.reputation.clone();
                             
                             tokio::spawn(async move {
-                                let mut buffer = vec![0; 1024];
-                                let n = match stream.read(&mut buffer).await {
-                                    Ok(n) if n > 0 => n,
+                                // The client shuts down its write half once the request is sent
+                                let buffer = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+                                    Ok(Ok(buffer)) if !buffer.is_empty() => buffer,
+                                    Ok(Err(e)) => {
+                                        warn!("Rejected IPC request: {}", e);
+                                        return;
+                                    }
                                     _ => return,
                                 };
 
-                                let request_res: Result<Request, _> = serde_json::from_slice(&buffer[..n]);
+                                let request_res: Result<Request, _> = serde_json::from_slice(&buffer);
+                                // The binding ends up inside the signed payload: no separators, real OIDs only
+                                let invalid_binding = match &request_res {
+                                    Ok(Request::GetTicket { binding, .. }) | Ok(Request::RedeemPow { binding, .. }) => binding.validate().err(),
+                                    _ => None,
+                                };
                                 let response = match request_res {
+                                    _ if invalid_binding.is_some() => {
+                                        Response::Error(format!("Invalid ticket binding: {}", invalid_binding.unwrap_or_default()))
+                                    }
                                     Ok(Request::GetStatus) => {
                                         let events = events_captured_lock.read().map(|g| *g).unwrap_or(0);
                                         Response::Status {
@@ -152,12 +300,7 @@ impl IpcServer {
                                             let human_score = calculate_human_score(burstiness, code_ncd, focus_time_mins, nav_events, is_synthetic);
                                             
                                             // Generar ZKP si el score es humano (>= threshold)
-                                            let zkp_proof = if human_score >= 0.5 {
-                                                let score_u64 = (human_score * 100.0) as u64;
-                                                HumanityProof::generate(score_u64, 50).ok()
-                                            } else {
-                                                None
-                                            };
+                                            let zkp_proof = generate_humanity_proof(human_score, HUMANITY_THRESHOLD_PERCENT, b"metrics", None);
 
                                             if let Some(m) = m_guard.as_ref() {
                                                 Response::Metrics {
@@ -170,7 +313,7 @@ impl IpcServer {
                                                     focus_time_mins,
                                                     edit_bursts,
                                                     is_focused,
-                                                    zkp_proof: zkp_proof.map(|_| "ZKP_ACTIVE_B64_PLACEHOLDER".to_string()),
+                                                    zkp_proof,
                                                     score_history: history,
                                                 }
                                             } else {
@@ -184,7 +327,7 @@ impl IpcServer {
                                                     focus_time_mins,
                                                     edit_bursts,
                                                     is_focused,
-                                                    zkp_proof: zkp_proof.map(|_| "ZKP_ACTIVE_B64_PLACEHOLDER".to_string()),
+                                                    zkp_proof,
                                                     score_history: history,
                                                 }
                                             }
@@ -192,57 +335,248 @@ impl IpcServer {
                                             Response::Error("Failed to lock metrics".to_string())
                                         }
                                     }
-                                    Ok(Request::GetTicket { cost }) => {
+                                    Ok(Request::GetTicket { cost, binding, threshold }) => {
                                         let mut battery = battery_lock.write().map_err(|_| "Lock failed").unwrap();
                                         // APLICAR DIFICULTAD
                                         let adjusted_cost = cost * difficulty_factor;
                                         
-                                        if battery.consume(adjusted_cost) {
-                                            let message = format!("VALID:cost={:.2}:ts={}", cost, start_time.elapsed().as_secs());
-                                            let signature = cliff_watch_core::crypto::sign_data(&signing_key_lock, message.as_bytes()).ok();
-                                            Response::Ticket {
-                                                success: true,
-                                                signature,
-                                                message: "Ticket issued. Thermodynamic balance verified.".to_string(),
-                                            }
+                                        let mode = if battery.consume(adjusted_cost) {
+                                            Some(TicketMode::Valid)
                                         } else if self.audit_mode {
                                             // AUDIT MODE: Permitir commit pero con advertencia (Soft Enforcement)
-                                            let message = format!("AUDIT:cost={:.2}:ts={}", cost, start_time.elapsed().as_secs());
-                                            let signature = cliff_watch_core::crypto::sign_data(&signing_key_lock, message.as_bytes()).ok();
-                                            Response::Ticket {
-                                                success: true,
-                                                signature,
-                                                message: format!(
-                                                    "AUDIT WARNING: Battery at {:.2}. Threshold reached due to Audit Mode. Please focus more next time!",
-                                                    battery.level
-                                                ),
-                                            }
+                                            Some(TicketMode::Audit)
                                         } else {
-                                            Response::Ticket {
+                                            None
+                                        };
+
+                                        match mode {
+                                            Some(mode) => {
+                                                let human_score = current_human_score(&metrics_lock, &focus_tracker_lock, &ncd_lock);
+                                                let message = if mode == TicketMode::Valid {
+                                                    "Ticket issued. Thermodynamic balance verified.".to_string()
+                                                } else {
+                                                    format!(
+                                                        "AUDIT WARNING: Battery at {:.2}. Threshold reached due to Audit Mode. Please focus more next time!",
+                                                        battery.level
+                                                    )
+                                                };
+                                                let payload = TicketPayload::new(mode, cost, binding, key_id.as_str());
+                                                ticket_response(payload, human_score, threshold, &identity, &last_nonce_lock, &ticket_records, message)
+                                            }
+                                            None if pow_fallback => {
+                                                // El déficit se paga con trabajo ligado al árbol staged
+                                                let deficit = adjusted_cost - battery.level;
+                                                let mut challenge = pow::create_challenge(&binding, deficit);
+                                                // Contribuidor probado: credencial vigente de un emisor de confianza
+                                                let proven = reputation
+                                                    .as_ref()
+                                                    .filter(|c| c.valid_until.is_none_or(|until| std::time::SystemTime::now() <= until.into()))
+                                                    .map_or(0, |c| c.summary.valid_commits);
+                                                let base_difficulty = challenge.difficulty;
+                                                challenge.difficulty = pow::relax_for_history(base_difficulty, proven);
+                                                if challenge.difficulty < base_difficulty {
+                                                    info!("Proof-of-work relaxed from {} to {} bits ({} verified commits)", base_difficulty, challenge.difficulty, proven);
+                                                }
+                                                if let Ok(mut pending) = pending_pow_lock.lock() {
+                                                    pending.retain(|_, p| p.issued_at.elapsed() < POW_CHALLENGE_TTL);
+                                                    pending.insert(challenge.salt.clone(), PendingPow {
+                                                        binding,
+                                                        cost,
+                                                        difficulty: challenge.difficulty,
+                                                        issued_at: std::time::Instant::now(),
+                                                    });
+                                                }
+                                                Response::PowChallenge {
+                                                    message: format!(
+                                                        "Battery at {:.2}, required {:.2}. Deficit {:.2} can be paid with proof of work ({} bits).",
+                                                        battery.level, adjusted_cost, deficit, challenge.difficulty
+                                                    ),
+                                                    challenge,
+                                                }
+                                            }
+                                            None => Response::Ticket {
                                                 success: false,
                                                 signature: None,
+                                                payload: None,
+                                                proof: None,
+                                                log_head: None,
                                                 message: format!(
                                                     "THERMODYNAMIC FAILURE: Required {:.2} (difficulty factor {:.2}), Battery at {:.2}. Focus more!",
                                                     adjusted_cost, difficulty_factor, battery.level
                                                 ),
+                                            },
+                                        }
+                                    }
+
+                                    Ok(Request::RedeemPow { cost, binding, salt, nonce, threshold }) => {
+                                        let pending = pending_pow_lock.lock().ok().and_then(|mut p| p.remove(&salt));
+                                        match pending {
+                                            Some(p) if p.binding == binding && p.cost == cost && p.issued_at.elapsed() < POW_CHALLENGE_TTL => {
+                                                match pow::verify_solution(&binding, &salt, p.difficulty, nonce) {
+                                                    Ok(hash) => {
+                                                        if let Ok(mut last) = last_pow_lock.lock() {
+                                                            *last = Some(ProofOfWork { difficulty: p.difficulty, nonce, hash: hex::encode(hash) });
+                                                        }
+                                                        let human_score = current_human_score(&metrics_lock, &focus_tracker_lock, &ncd_lock);
+                                                        let payload = TicketPayload::new(TicketMode::Pow, cost, binding, key_id.as_str())
+                                                            .with_pow(PowStamp { difficulty: p.difficulty, salt, nonce });
+                                                        info!("Proof-of-work ticket issued ({} bits)", p.difficulty);
+                                                        ticket_response(
+                                                            payload,
+                                                            human_score,
+                                                            threshold,
+                                                            &identity,
+                                                            &last_nonce_lock,
+                                                            &ticket_records,
+                                                            format!("Ticket issued. Deficit paid with proof of work ({} bits).", p.difficulty),
+                                                        )
+                                                    }
+                                                    Err(e) => Response::Error(e),
+                                                }
                                             }
+                                            _ => Response::Error("Unknown or expired proof-of-work challenge".to_string()),
                                         }
                                     }
 
-                                    Ok(Request::GetWitness { reset }) => {
-                                        if let Ok(mut tracker) = focus_tracker_lock.write() {
-                                            let metrics = tracker.get_metrics();
-                                            let witness = WitnessData::from_metrics(&metrics);
-                                            let data = witness.to_json();
-                                            
-                                            if reset {
-                                                tracker.reset();
-                                                info!("FocusTracker reset after GetWitness");
+                                    Ok(Request::GetReport { reset }) => {
+                                        let end_us = report::now_us();
+                                        let human_score = current_human_score(&metrics_lock, &focus_tracker_lock, &ncd_lock);
+                                        let input_events = events_captured_lock.read().map(|g| *g as u64).unwrap_or(0);
+                                        let monitor_now = monitor_stats.snapshot();
+                                        let attention = AttentionMetrics {
+                                            battery_level: battery_lock.read().map(|g| g.level).unwrap_or(0.0),
+                                            coupling: coupling_lock.read().map(|g| *g).unwrap_or(1.0),
+                                            human_score,
+                                        };
+
+                                        let kinematic = metrics_lock.read().ok().and_then(|g| g.clone());
+                                        let pow = last_pow_lock.lock().ok().and_then(|g| g.clone());
+                                        let ticket_nonce = last_nonce_lock.lock().ok().and_then(|g| g.clone());
+
+                                        let sealed = match (focus_tracker_lock.write(), report_cursor.lock()) {
+                                            (Ok(mut tracker), Ok(mut cursor)) => {
+                                                let focus = tracker.get_metrics();
+                                                let (observation, monitor) = cursor.window(end_us, input_events, &monitor_now);
+                                                let metrics = collect_metrics(CollectionInput {
+                                                    focus: Some(&focus),
+                                                    kinematic: kinematic.as_ref(),
+                                                    code_ncd: ncd_lock.read().map(|v| *v).ok(),
+                                                    attention: Some(attention),
+                                                    monitor: Some(monitor),
+                                                });
+
+                                                let sealed = SentinelBuilder::new()
+                                                    .report_id(report::new_report_id())
+                                                    .workspace_id(workspace_id.as_str())
+                                                    .observation(observation)
+                                                    .ticket_nonce(ticket_nonce)
+                                                    .metrics(metrics)
+                                                    .build()
+                                                    .map_err(|e| e.to_string())
+                                                    .and_then(|r| finalize_report(r, &identity, pow).map_err(|e| e.to_string()));
+
+                                                if reset && sealed.is_ok() {
+                                                    tracker.reset();
+                                                    if let Ok(mut last) = last_pow_lock.lock() {
+                                                        *last = None;
+                                                    }
+                                                    if let Ok(mut last) = last_nonce_lock.lock() {
+                                                        *last = None;
+                                                    }
+                                                    *cursor = ObservationCursor::new(end_us, input_events, monitor_now);
+                                                    info!("Observation window reset after GetReport");
+                                                }
+                                                sealed
                                             }
-                                            
-                                            Response::Witness { data }
-                                        } else {
-                                            Response::Error("Failed to lock FocusTracker".to_string())
+                                            _ => Err("Failed to lock daemon state".to_string()),
+                                        };
+
+                                        match sealed {
+                                            Ok(report) => Response::Report { report: Box::new(report) },
+                                            Err(e) => Response::Error(format!("Failed to seal sentinel report: {}", e)),
+                                        }
+                                    }
+
+                                    Ok(Request::Cosign { payload }) => {
+                                        let human_score = current_human_score(&metrics_lock, &focus_tracker_lock, &ncd_lock);
+                                        let cosigned = TicketPayload::decode(&payload).and_then(|decoded| {
+                                            require_presence(&battery_lock, human_score, GOVERNANCE_COST * difficulty_factor)?;
+                                            Cosignature::sign(&payload, &identity).map(|cosig| (decoded, cosig))
+                                        });
+                                        match cosigned {
+                                            Ok((decoded, cosig)) => {
+                                                let binding = &decoded.binding;
+                                                info!(
+                                                    "Ticket co-signed: tree {} parent {} author {} (issuer kid {})",
+                                                    binding.tree,
+                                                    binding.parent.as_deref().unwrap_or("none"),
+                                                    binding.author,
+                                                    decoded.key_id
+                                                );
+                                                Response::Cosignature { value: cosig.to_trailer_value() }
+                                            }
+                                            Err(e) => Response::Error(format!("Refusing to co-sign: {}", e)),
+                                        }
+                                    }
+
+                                    Ok(Request::GetIdentity) => {
+                                        let public_key = identity.public_key();
+                                        Response::Identity {
+                                            public_key: public_key.to_hex(),
+                                            did: cliff_watch_core::crypto::did::to_did_key(&public_key),
+                                            backend: identity.backend().to_string(),
+                                        }
+                                    }
+
+                                    Ok(Request::IssueCredential { repo, subject, limit }) => {
+                                        // El daemon recorre el historial él mismo, fuera del runtime y sin locks
+                                        let identity = identity.clone();
+                                        let issued = tokio::task::spawn_blocking(move || {
+                                            let subject = PublicKey::from_hex(&subject)?;
+                                            let repository = cliff_watch_core::git::open_repository(Path::new(&repo))?;
+                                            cliff_watch_core::credential::issue(&repository, &subject, &identity, limit, std::time::SystemTime::now().into())
+                                        })
+                                        .await
+                                        .unwrap_or_else(|e| Err(e.to_string()));
+                                        match issued {
+                                            Ok(document) => {
+                                                info!("Reputation credential issued");
+                                                Response::Credential { document }
+                                            }
+                                            Err(e) => Response::Error(format!("Refusing to issue credential: {}", e)),
+                                        }
+                                    }
+
+                                    Ok(Request::Endorse { key, config, roots }) => {
+                                        let at = unix_now();
+                                        let human_score = current_human_score(&metrics_lock, &focus_tracker_lock, &ncd_lock);
+                                        let endorsed = TrustConfig::parse(&config)
+                                            .and_then(|config| require_maintainer(&config, &roots, &identity))
+                                            .and_then(|_| require_presence(&battery_lock, human_score, GOVERNANCE_COST * difficulty_factor))
+                                            .and_then(|_| cliff_watch_core::trust::endorse(&key, &identity, at));
+                                        match endorsed {
+                                            Ok(value) => {
+                                                info!("Trust entry endorsed: {} ({})", key.alias, key.key_id());
+                                                Response::TrustSignature { value }
+                                            }
+                                            Err(e) => Response::Error(format!("Refusing to endorse: {}", e)),
+                                        }
+                                    }
+
+                                    Ok(Request::SignTrust { config, roots }) => {
+                                        let at = unix_now();
+                                        let human_score = current_human_score(&metrics_lock, &focus_tracker_lock, &ncd_lock);
+                                        let signed = TrustConfig::parse(&config).and_then(|config| {
+                                            require_maintainer(&config, &roots, &identity)?;
+                                            require_presence(&battery_lock, human_score, GOVERNANCE_COST * difficulty_factor)?;
+                                            config.sign_document(&identity, at)
+                                        });
+                                        match signed {
+                                            Ok(value) => {
+                                                info!("trust.toml signed");
+                                                Response::TrustSignature { value }
+                                            }
+                                            Err(e) => Response::Error(format!("Refusing to sign trust.toml: {}", e)),
                                         }
                                     }
 
@@ -264,3 +598,241 @@ impl IpcServer {
         Ok(())
     }
 }
+
+/// Segundos Unix actuales (instante de avales y firmas de trust.toml)
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// La identidad del daemon debe ser Maintainer efectivo de `config` con las
+/// raíces `roots` (hex): sin raíces fijadas nadie lo es
+fn require_maintainer(config: &TrustConfig, roots: &[String], identity: &DaemonIdentity) -> Result<(), String> {
+    let roots = roots.iter().map(|r| PublicKey::from_hex(r)).collect::<Result<Vec<_>, _>>()?;
+    if roots.is_empty() {
+        return Err("no trust root pinned (cliff-watch key pin-root)".to_string());
+    }
+    let kid = identity.public_key().key_id();
+    let maintainer = config
+        .effective_keys(&roots)
+        .iter()
+        .any(|k| k.key_id() == kid && k.role.eq_ignore_ascii_case(MAINTAINER_ROLE));
+    if !maintainer {
+        return Err(format!("daemon key {} is not an endorsed maintainer of this trust.toml", kid));
+    }
+    Ok(())
+}
+
+/// Presencia humana para firmas que no son tickets: Human Score por encima
+/// del umbral de `zkp` y batería para pagar `cost`. Sin Audit Mode ni prueba
+/// de trabajo: un proceso automatizado no puede pedirlas.
+fn require_presence(battery: &RwLock<AttentionBattery>, human_score: f64, cost: f64) -> Result<(), String> {
+    let score_percent = (human_score * 100.0) as u64;
+    if score_percent < HUMANITY_THRESHOLD_PERCENT {
+        return Err(format!("human score {}% below {}%", score_percent, HUMANITY_THRESHOLD_PERCENT));
+    }
+    let mut battery = battery.write().map_err(|_| "Failed to lock battery".to_string())?;
+    if !battery.consume(cost) {
+        return Err(format!("battery at {:.2}, required {:.2}", battery.level, cost));
+    }
+    Ok(())
+}
+
+/// Human Score actual (misma fórmula que expone `GetMetrics`)
+fn current_human_score(
+    metrics: &RwLock<Option<KinematicMetrics>>,
+    focus_tracker: &RwLock<FocusTracker>,
+    ncd: &RwLock<f64>,
+) -> f64 {
+    let (focus_time_mins, nav_events, is_synthetic_focus) = match focus_tracker.read() {
+        Ok(ft) => {
+            let m = ft.get_metrics();
+            (m.total_focus_mins, m.navigation_events, m.is_synthetic)
+        }
+        Err(_) => (0.0, 0, false),
+    };
+    let code_ncd = ncd.read().map(|v| *v).unwrap_or(0.2);
+    let (burstiness, is_synthetic_kinematic) = match metrics.read() {
+        Ok(guard) => guard.as_ref().map(|m| (m.burstiness, m.is_synthetic)).unwrap_or((0.0, false)),
+        Err(_) => (0.0, false),
+    };
+
+    calculate_human_score(burstiness, code_ncd, focus_time_mins, nav_events, is_synthetic_focus || is_synthetic_kinematic)
+}
+
+/// Firma un ticket, adjuntando la prueba de rango si el score la permite
+/// (contra `threshold` si lo alcanza; si no, contra el umbral por defecto).
+///
+/// Recuerda su nonce para que el Sentinel Report de la ventana quede ligado a él.
+/// El ticket se sella con el arranque y el reloj monótono (ver `clock`).
+/// Con cadena, el ticket lleva el siguiente `seq` y el hash del anterior.
+/// Con log de transparencia, el ticket reserva la siguiente hoja, se anota y
+/// viaja con el head firmado; si el log no acepta la hoja no se emite ticket.
+fn ticket_response(
+    payload: TicketPayload,
+    human_score: f64,
+    threshold: Option<u64>,
+    identity: &DaemonIdentity,
+    last_nonce: &Mutex<Option<String>>,
+    records: &TicketRecords,
+    message: String,
+) -> Response {
+    // Prueba de rango ligada al árbol staged: "score >= umbral" sin revelar el score
+    let proof = threshold
+        .filter(|t| *t > HUMANITY_THRESHOLD_PERCENT)
+        .and_then(|t| generate_humanity_proof(human_score, t, payload.binding.tree.as_bytes(), records.openings.as_deref()))
+        .or_else(|| generate_humanity_proof(human_score, HUMANITY_THRESHOLD_PERCENT, payload.binding.tree.as_bytes(), records.openings.as_deref()));
+    let payload = match &proof {
+        Some(p) => payload.with_proof(p),
+        None => payload,
+    };
+
+    // Los locks cubren numeración, firma y anotación: dos tickets nunca comparten eslabón ni hoja
+    let mut chain = match records.chain.as_ref().map(|c| c.lock()) {
+        Some(Ok(guard)) => Some(guard),
+        Some(Err(_)) => return Response::Error("Failed to lock ticket chain".to_string()),
+        None => None,
+    };
+    let mut log = match records.log.as_ref().map(|l| l.lock()) {
+        Some(Ok(guard)) => Some(guard),
+        Some(Err(_)) => return Response::Error("Failed to lock transparency log".to_string()),
+        None => None,
+    };
+    // Arranque + reloj monótono: un reloj de pared retrasado queda a la vista
+    let payload = payload.with_clock(cliff_watch_core::clock::ClockStamp::now());
+    if let Some(jump) = chain.as_ref().and_then(|c| c.clock_jump(&payload)) {
+        warn!("Clock jump before ticket for {}: {}", payload.binding.tree, jump);
+    }
+    let payload = match &chain {
+        Some(chain) => payload.with_chain(chain.next_link()),
+        None => payload,
+    };
+    let payload = match &log {
+        Some(log) => payload.with_log_index(log.size()),
+        None => payload,
+    };
+
+    let ticket = match SignedTicket::sign(payload, identity) {
+        Ok(ticket) => ticket,
+        Err(e) => return Response::Error(format!("Failed to sign ticket: {}", e)),
+    };
+    // Log antes que cadena: si el log falla, el eslabón queda libre para el siguiente ticket
+    let log_head = match log.as_mut() {
+        Some(log) => match log.append(&ticket.to_trailer_value()).and_then(|_| log.sign_head(identity)) {
+            Ok(sth) => Some(sth.to_trailer_value()),
+            Err(e) => return Response::Error(format!("Failed to record ticket in transparency log: {}", e)),
+        },
+        None => None,
+    };
+    if let Some(chain) = chain.as_mut() {
+        if let Err(e) = chain.record(&ticket) {
+            return Response::Error(format!("Failed to advance ticket chain: {}", e));
+        }
+    }
+
+    if let Ok(mut last) = last_nonce.lock() {
+        *last = Some(ticket.payload.nonce.clone());
+    }
+    Response::Ticket {
+        success: true,
+        signature: Some(ticket.signature),
+        payload: Some(ticket.encoded),
+        proof,
+        log_head,
+        message,
+    }
+}
+
+/// Genera la prueba de rango serializada (valor del trailer) si el score supera `threshold` (%).
+///
+/// Con `openings`, guarda la apertura del compromiso para poder agregarla en un
+/// certificado de rama; si no se puede guardar, la prueba se emite igualmente.
+fn generate_humanity_proof(human_score: f64, threshold: u64, context: &[u8], openings: Option<&OpeningStore>) -> Option<String> {
+    let score_percent = (human_score * 100.0) as u64;
+    if score_percent < threshold {
+        return None;
+    }
+    let (proof, opening) = HumanityProof::generate_with_opening(score_percent, threshold, context).ok()?;
+    if let Some(Err(e)) = openings.map(|store| store.save(&opening)) {
+        error!("Range proof opening not stored, this commit cannot be certified: {}", e);
+    }
+    Some(proof.to_trailer_value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_requests_larger_than_one_read() {
+        let config = format!("[[keys]]\nalias = \"dev\"\nkey = \"{}\"\n", "ab".repeat(2048));
+        let request = serde_json::to_vec(&Request::SignTrust { config, roots: Vec::new() }).unwrap();
+        assert!(request.len() > 4096);
+
+        let (mut client, server) = tokio::net::UnixStream::pair().unwrap();
+        let sent = request.clone();
+        let writer = tokio::spawn(async move {
+            client.write_all(&sent).await.unwrap();
+            client.shutdown().await.unwrap();
+        });
+        let received = read_request(server).await.unwrap();
+        writer.await.unwrap();
+        assert_eq!(received, request);
+        assert!(matches!(serde_json::from_slice(&received), Ok(Request::SignTrust { .. })));
+
+        let oversized = vec![b' '; MAX_REQUEST_BYTES as usize + 1];
+        assert!(read_request(&oversized[..]).await.is_err());
+    }
+
+    #[test]
+    fn governance_signatures_need_a_maintainer_and_human_presence() {
+        let identity = DaemonIdentity::Software(cliff_watch_core::crypto::generate_keypair().0);
+        let root = identity.public_key().to_hex();
+        let other = cliff_watch_core::crypto::generate_keypair().1;
+        let mut config = TrustConfig::default();
+        config.add_key("lead", &root, None).unwrap();
+        assert!(require_maintainer(&config, &[], &identity).is_err());
+        assert!(require_maintainer(&config, &[hex::encode(other.as_bytes())], &identity).is_err());
+        assert!(require_maintainer(&config, &[root], &identity).is_ok());
+
+        let battery = RwLock::new(AttentionBattery::new());
+        assert!(require_presence(&battery, 0.9, GOVERNANCE_COST).is_err());
+        battery.write().unwrap().level = 100.0;
+        assert!(require_presence(&battery, 0.1, GOVERNANCE_COST).is_err());
+        assert!(require_presence(&battery, 0.9, GOVERNANCE_COST).is_ok());
+        assert!(battery.read().unwrap().level <= 100.0 - GOVERNANCE_COST);
+    }
+
+    #[test]
+    fn failed_log_append_leaves_the_chain_untouched() {
+        let dir = cliff_watch_core::testing::TempDir::new("ipc_records");
+        let (signing_key, verifying_key) = cliff_watch_core::crypto::generate_keypair();
+        let identity = DaemonIdentity::Software(signing_key);
+        let kid = cliff_watch_core::crypto::key_id(&verifying_key);
+
+        let log = TransparencyLog::open(&dir.join("log")).unwrap();
+        // El directorio del log pasa a ser un archivo: cualquier append falla
+        std::fs::remove_dir_all(dir.join("log")).unwrap();
+        std::fs::write(dir.join("log"), "").unwrap();
+        let records = TicketRecords {
+            chain: Some(Arc::new(Mutex::new(ChainState::open(&dir.join("chain"), &kid).unwrap()))),
+            log: Some(Arc::new(Mutex::new(log))),
+            openings: None,
+        };
+
+        let binding = TicketBinding {
+            tree: "4b825dc642cb6eb9a060e54bf8d69288fbee4904".to_string(),
+            parent: None,
+            author: "dev@example.com".to_string(),
+        };
+        let payload = TicketPayload::new(TicketMode::Valid, 5.0, binding, kid.clone());
+        let response = ticket_response(payload, 0.0, None, &identity, &Mutex::new(None), &records, String::new());
+        assert!(matches!(response, Response::Error(ref e) if e.contains("transparency log")));
+
+        let chain = records.chain.as_ref().unwrap().lock().unwrap();
+        assert_eq!(chain.seq, 0);
+        assert_eq!(ChainState::open(&dir.join("chain"), &kid).unwrap().seq, 0);
+    }
+}
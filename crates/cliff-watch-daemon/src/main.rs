@@ -1,7 +1,7 @@
 use anyhow::Result;
 use tokio::sync::{mpsc, watch};
 use tokio_util::sync::CancellationToken;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod ipc;
@@ -86,10 +86,31 @@ async fn main() -> Result<()> {
     let events_captured_ref = monitor.get_events_captured_ref();
     let score_history_ref = monitor.get_score_history_ref();
     
-    // Load or create persistent identity
-    let signing_key = cliff_watch_core::crypto::load_or_create_identity()
+    // Load or create persistent identity (file, TPM or SSH, see [identity])
+    let identity = cliff_watch_core::crypto::load_daemon_identity(&gov_config.identity)
         .map_err(|e| anyhow::anyhow!("Failed to initialize identity: {}", e))?;
+    if gov_config.identity.backend == "file"
+        && !cliff_watch_core::crypto::daemon_key_path().is_ok_and(|p| cliff_watch_core::crypto::identity_is_encrypted(&p))
+    {
+        warn!("Daemon key is stored unencrypted; protect it with `cliff-watch key passwd`");
+    }
+
+    // Append-only transparency log of issued tickets
+    let transparency_log = cliff_watch_core::translog::TransparencyLog::default_dir()
+        .and_then(|dir| cliff_watch_core::translog::TransparencyLog::open(&dir))
+        .map_err(|e| anyhow::anyhow!("Failed to open transparency log: {}", e))?;
+
+    // Per-identity ticket counter and previous-ticket hash
+    let ticket_chain = cliff_watch_core::chain::ChainState::default_dir()
+        .and_then(|dir| cliff_watch_core::chain::ChainState::open(&dir, &identity.public_key().key_id()))
+        .map_err(|e| anyhow::anyhow!("Failed to open ticket chain state: {}", e))?;
 
+    // Openings of issued range proofs (`cliff-watch certify`)
+    let opening_store = cliff_watch_core::certificate::OpeningStore::default_dir()
+        .and_then(|dir| cliff_watch_core::certificate::OpeningStore::open(&dir))
+        .map_err(|e| anyhow::anyhow!("Failed to open proof opening store: {}", e))?;
+
+    let daemon_key = identity.public_key();
     let ipc_server = IpcServer::new(
         "/tmp/cliff-watch.sock".to_string(),
         metrics_ref,
@@ -100,10 +121,38 @@ async fn main() -> Result<()> {
         score_history_ref,
         monitor.get_ncd_ref(),
         shutdown.clone(),
-        signing_key,
+        identity,
         gov_config.governance.min_entropy,
         gov_config.governance.audit_mode,
-    );
+    )
+    .with_monitor_stats(file_monitor.stats_handle())
+    .with_pow_fallback(gov_config.governance.pow_fallback)
+    .with_transparency_log(transparency_log)
+    .with_ticket_chain(ticket_chain)
+    .with_opening_store(opening_store);
+
+    // Reputation credential vouched by a trusted issuer: cheaper proof of work
+    let ipc_server = match &gov_config.governance.reputation_credential {
+        Some(path) => {
+            let path = cliff_watch_core::crypto::expand_home(path);
+            let verified = std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|document| {
+                cliff_watch_core::credential::verify_reputation(
+                    &document,
+                    &daemon_key,
+                    &gov_config.governance.trusted_issuers,
+                    std::time::SystemTime::now().into(),
+                )
+            });
+            match verified {
+                Ok(credential) => ipc_server.with_reputation(credential),
+                Err(e) => {
+                    warn!("Reputation credential {} ignored: {}", path.display(), e);
+                    ipc_server
+                }
+            }
+        }
+        None => ipc_server,
+    };
 
     // Start IPC server task
     tokio::spawn(async move {